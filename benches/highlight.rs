@@ -0,0 +1,142 @@
+//! Redrawing a large diff with and without the highlight cache.
+//!
+//! Run with `cargo bench --bench highlight`. `redraw/visible_lines` is the
+//! path before whole-file highlighting: every frame highlights the rows on
+//! screen from scratch. `redraw/full_file_uncached` drops the cache before
+//! every frame, so it re-highlights the whole 6,000-line file each time.
+//! `redraw/cached` is what a redraw costs now.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use dif::app::App;
+use dif::diff::{DiffCell, DiffRow};
+use dif::highlight::{Highlighter, SourceFile};
+use dif::ui;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use tempfile::TempDir;
+
+const LINES: usize = 6_000;
+const VIEW_ROWS: usize = 48;
+
+#[derive(Clone, Copy)]
+enum Side {
+    Old,
+    New,
+}
+
+impl Side {
+    fn cell(self, row: &DiffRow) -> Option<&DiffCell> {
+        match self {
+            Self::Old => row.old.as_ref(),
+            Self::New => row.new.as_ref(),
+        }
+    }
+}
+
+fn redraw(c: &mut Criterion) {
+    let repo = setup_repo();
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    assert!(
+        app.select_tree_path("src/big.rs")
+            .expect("selecting should succeed")
+    );
+    let highlighter = Highlighter::new().expect("highlighter should load");
+    let mut terminal =
+        Terminal::new(TestBackend::new(160, VIEW_ROWS as u16)).expect("terminal should open");
+
+    let mut group = c.benchmark_group("redraw");
+    group.bench_function("visible_lines", |b| {
+        b.iter(|| {
+            app.diff_scroll = (app.diff_scroll + 7) % app.diff_rows.len();
+            terminal
+                .draw(|frame| ui::render(frame, &app, &highlighter))
+                .expect("draw should succeed");
+            // The cached draw above only looks spans up; this adds the
+            // per-frame highlighting the old path did for both sides.
+            let rows = &app.diff_rows[app.diff_scroll..];
+            for side in [Side::Old, Side::New] {
+                let visible = SourceFile::from_lines(
+                    rows.iter()
+                        .take(VIEW_ROWS)
+                        .filter_map(|row| side.cell(row))
+                        .filter_map(|cell| Some((cell.line_no?, cell.text.as_str()))),
+                );
+                highlighter.highlight_file(Some("src/big.rs"), &visible, &app.settings, false);
+            }
+        })
+    });
+    let mut generation = u64::MAX;
+    group.bench_function("full_file_uncached", |b| {
+        b.iter(|| {
+            generation -= 1;
+            highlighter.prune_cache(generation, &[]);
+            app.diff_scroll = (app.diff_scroll + 7) % app.diff_rows.len();
+            terminal
+                .draw(|frame| ui::render(frame, &app, &highlighter))
+                .expect("draw should succeed");
+        })
+    });
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            app.diff_scroll = (app.diff_scroll + 7) % app.diff_rows.len();
+            terminal
+                .draw(|frame| ui::render(frame, &app, &highlighter))
+                .expect("draw should succeed");
+        })
+    });
+    group.finish();
+}
+
+/// A Rust file with a doc comment and a one-line change every 25 lines, so
+/// the diff has hundreds of hunks.
+fn setup_repo() -> TempDir {
+    let temp = TempDir::new().expect("tempdir should be created");
+    let repo = temp.path();
+    git(repo, &["init"]);
+    git(repo, &["config", "user.email", "dif-bench@example.com"]);
+    git(repo, &["config", "user.name", "dif-bench"]);
+
+    let source = |edited: bool| {
+        let mut text = String::new();
+        for line in 0..LINES {
+            match line % 25 {
+                0 => text.push_str("/// Adds one to `value`.\n"),
+                1 => {
+                    let _ = writeln!(text, "pub fn step_{line}(value: u64) -> u64 {{");
+                }
+                2 if edited => text.push_str("    value.wrapping_add(1) // \"edited\"\n"),
+                2 => text.push_str("    value + 1\n"),
+                3 => text.push_str("}\n"),
+                _ => text.push('\n'),
+            }
+        }
+        text
+    };
+    fs::create_dir(repo.join("src")).expect("src should be created");
+    fs::write(repo.join("src/big.rs"), source(false)).expect("write should succeed");
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-m", "init"]);
+    fs::write(repo.join("src/big.rs"), source(true)).expect("write should succeed");
+    temp
+}
+
+fn git(repo: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .current_dir(repo)
+        .args(args)
+        .output()
+        .expect("git should run");
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+criterion_group!(benches, redraw);
+criterion_main!(benches);
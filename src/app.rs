@@ -1,32 +1,50 @@
-use std::path::PathBuf;
-use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::{Context, Result, anyhow};
-use crossterm::event::KeyEvent;
+use anyhow::Result;
 use ratatui::layout::Rect;
 
-use crate::diff::{CellKind, DiffRow, parse_unified_diff, unified_line_count};
-use crate::git::{self, BranchEntry, DiffMode, FileEntry, UnstagedKind};
+use crate::diff::{
+    CellKind, DiffRow, WrapWidths, display_line_count, display_offset_for_line, display_width,
+    line_number_width, parse_unified_diff,
+};
+use crate::git::{self, BranchEntry, DiffMode, FileEntry, LineStat, UnstagedKind};
+use crate::highlight::{MAX_SOURCE_BYTES, SourceFile};
+use crate::keymap::{Action, Keymap};
 use crate::layout;
 use crate::settings::{
-    self, AUTO_SPLIT_MIN_WIDTH_MAX, AUTO_SPLIT_MIN_WIDTH_MIN, AppSettings, DiffViewMode,
-    SIDEBAR_WIDTH_MAX, SIDEBAR_WIDTH_MIN,
+    self, AppSettings, DiffViewMode, RepoConfig, SIDEBAR_WIDTH_MAX, SIDEBAR_WIDTH_MIN,
+    TERMINAL_DOCK_SIZE_MAX, TERMINAL_DOCK_SIZE_MIN,
 };
-use crate::terminal::TerminalSession;
 
+mod commands;
+mod finder;
+mod settings_panel;
 mod status;
+mod tasks;
+mod terminal;
+mod tree;
+mod trust;
 mod util;
 
+pub use commands::CommandEntry;
+pub use finder::FinderResult;
 pub use status::{StatusKind, StatusMessage};
+pub use tasks::{TaskEntry, TaskRunState, TaskSource};
+pub use terminal::{TabAttention, TerminalLinkSpan, TerminalSearchState, TerminalTab};
+use tree::sort_tree_files;
+pub use tree::{TreeRow, TreeRowKind};
 use util::{
-    clamp_text_cursor, contains, ensure_visible, find_query_in_line, move_text_cursor_down,
-    move_text_cursor_end, move_text_cursor_home, move_text_cursor_up, next_text_cursor,
-    order_positions, prev_text_cursor, shift_and_clamp_u16,
+    clamp_text_cursor, contains, ensure_visible, move_text_cursor_down, move_text_cursor_end,
+    move_text_cursor_home, move_text_cursor_up, next_text_cursor, prev_text_cursor,
+    shift_and_clamp_u16,
 };
 
-const SETTINGS_FIELD_COUNT: usize = 7;
 const AUTO_REFRESH_INTERVAL: Duration = Duration::from_millis(750);
-const SETTINGS_WRITE_DEBOUNCE: Duration = Duration::from_millis(400);
+const DIFF_JUMP_CONTEXT_LINES: usize = 3;
+const DIFF_HSCROLL_STEP: usize = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FocusSection {
@@ -38,6 +56,7 @@ pub enum FocusSection {
 pub enum PaneFocus {
     Sidebar,
     Diff,
+    Terminal,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -70,6 +89,10 @@ pub struct UiLayout {
     pub tree_inner: Rect,
     pub diff_area: Rect,
     pub diff_viewport_height: usize,
+    /// Text columns in the narrowest diff pane, past the line numbers.
+    pub diff_text_width: usize,
+    pub terminal_dock: Rect,
+    pub terminal_output: Rect,
 }
 
 impl Default for UiLayout {
@@ -80,22 +103,58 @@ impl Default for UiLayout {
             tree_inner: Rect::new(0, 0, 0, 0),
             diff_area: Rect::new(0, 0, 0, 0),
             diff_viewport_height: 0,
+            diff_text_width: 0,
+            terminal_dock: Rect::new(0, 0, 0, 0),
+            terminal_output: Rect::new(0, 0, 0, 0),
         }
     }
 }
 
+/// An untracked file's line count and the size and mtime it was counted at,
+/// so refreshes only reread files that changed.
+#[derive(Debug, Clone, Copy)]
+struct UntrackedStat {
+    modified: Option<SystemTime>,
+    len: u64,
+    stat: LineStat,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct TreeFileEntry {
     pub path: String,
     pub staged: bool,
     pub unstaged: bool,
     pub untracked: bool,
+    pub staged_stat: LineStat,
+    pub unstaged_stat: LineStat,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoAction {
+    /// Restore index and worktree from the mainline ref.
+    Mainline,
+    /// Restore the worktree from the index, keeping staged changes.
+    DiscardWorktree,
+    /// Restore index and worktree from `HEAD`.
+    ResetToHead,
+}
+
+impl UndoAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Mainline => "undo to mainline",
+            Self::DiscardWorktree => "discard unstaged changes",
+            Self::ResetToHead => "reset to HEAD",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 struct PendingUndoConfirmation {
     path: String,
     was_untracked: bool,
+    action: UndoAction,
 }
 
 pub struct App {
@@ -104,8 +163,25 @@ pub struct App {
     settings_dirty: bool,
     last_settings_change: Option<Instant>,
     pub settings: AppSettings,
+    pub keymap: Keymap,
+    repo_config: RepoConfig,
+    /// `repo_root` as listed in `trusted_repos`.
+    repo_trust_key: String,
+    pending_repo_trust: bool,
+    pub task_picker_open: bool,
+    pub task_picker_selected: usize,
     pub settings_open: bool,
     pub help_open: bool,
+    pub finder_open: bool,
+    pub finder_query: String,
+    pub finder_selected: usize,
+    finder_results: Vec<FinderResult>,
+    pub command_palette_open: bool,
+    pub command_palette_query: String,
+    pub command_palette_selected: usize,
+    command_palette_results: Vec<CommandEntry>,
+    pub tree_filter: String,
+    pub tree_filter_editing: bool,
     pub settings_selected: usize,
     pub git_panel_open: bool,
     pub git_panel_mode: GitPanelMode,
@@ -115,17 +191,15 @@ pub struct App {
     pub git_commit_input: String,
     git_commit_cursor: usize,
     pub terminal_open: bool,
-    pub terminal_scrollback: usize,
-    pub terminal_copy_mode: bool,
-    pub terminal_search_open: bool,
-    pub terminal_search_query: String,
-    terminal_last_search: String,
-    terminal_cursor_row: usize,
-    terminal_cursor_col: usize,
-    terminal_selection_anchor: Option<(usize, usize)>,
+    pub terminal_rename_open: bool,
+    pub terminal_rename_input: String,
+    terminal_tabs: Vec<TerminalTab>,
+    terminal_active: usize,
     terminal_view_rows: usize,
     terminal_view_cols: usize,
-    terminal_session: Option<TerminalSession>,
+    host_notifications: Vec<String>,
+    /// The host terminal's background from OSC 11, for `theme_mode = "auto"`.
+    host_background: Option<(u8, u8, u8)>,
     pub unstaged: Vec<FileEntry>,
     pub staged: Vec<String>,
     pub focus: FocusSection,
@@ -137,8 +211,21 @@ pub struct App {
     pub staged_scroll: usize,
     pub tree_scroll: usize,
     pub tree_files: Vec<TreeFileEntry>,
+    pub tree_rows: Vec<TreeRow>,
+    collapsed_dirs: BTreeSet<String>,
+    change_files: Vec<TreeFileEntry>,
+    untracked_stats: BTreeMap<String, UntrackedStat>,
     pub diff_rows: Vec<DiffRow>,
+    /// Whole old and new file behind the selected diff, for highlighting.
+    diff_old_source: Option<SourceFile>,
+    diff_new_source: Option<SourceFile>,
+    /// Bumped by every refresh so the renderer can drop stale highlighting.
+    refresh_generation: u64,
     pub diff_scroll: usize,
+    /// Columns scrolled right while wrapping is off.
+    pub diff_hscroll: usize,
+    /// Characters in the longest line of the selected diff.
+    diff_max_text_width: usize,
     pub diff_content_height: usize,
     pub status: StatusMessage,
     pub layout: UiLayout,
@@ -148,13 +235,43 @@ pub struct App {
 
 impl App {
     pub fn new(repo_root: PathBuf) -> Result<Self> {
-        let (settings, status) = match settings::load() {
+        let (settings, mut status) = match settings::load() {
             Ok(settings) => (settings, StatusMessage::info("Ready")),
             Err(error) => (
                 AppSettings::default(),
                 StatusMessage::warn(format!("Settings parse error, using defaults ({error})")),
             ),
         };
+        let repo_config = match settings::load_repo_config(&repo_root) {
+            Ok(config) => config,
+            Err(error) => {
+                status = StatusMessage::warn(format!(
+                    "Repo config parse error, ignoring {} ({error})",
+                    settings::REPO_CONFIG_FILE
+                ));
+                RepoConfig::default()
+            }
+        };
+        let repo_trust_key = settings::repo_trust_key(&repo_root);
+        let (keymap, key_problems) = Keymap::from_config(&settings.keys);
+        if let Some(warning) = config_problem_status("keys", &key_problems)
+            .or_else(|| config_problem_status("themes", &settings.theme_problems()))
+        {
+            status = warning;
+        } else if repo_config.runs_commands()
+            && !settings.is_repo_trusted(&repo_trust_key, &repo_config.sha256)
+        {
+            let state = if settings.repo_config_changed(&repo_trust_key, &repo_config.sha256) {
+                "changed"
+            } else {
+                "untrusted"
+            };
+            status = StatusMessage::warn(format!(
+                "Ignoring commands in {state} {}; {} to review and trust this repo",
+                settings::REPO_CONFIG_FILE,
+                keymap.key(Action::TrustRepoConfig)
+            ));
+        }
 
         let mut app = Self {
             repo_root,
@@ -162,8 +279,24 @@ impl App {
             settings_dirty: false,
             last_settings_change: None,
             settings,
+            keymap,
+            repo_config,
+            repo_trust_key,
+            pending_repo_trust: false,
+            task_picker_open: false,
+            task_picker_selected: 0,
             settings_open: false,
             help_open: false,
+            finder_open: false,
+            finder_query: String::new(),
+            finder_selected: 0,
+            finder_results: Vec::new(),
+            command_palette_open: false,
+            command_palette_query: String::new(),
+            command_palette_selected: 0,
+            command_palette_results: Vec::new(),
+            tree_filter: String::new(),
+            tree_filter_editing: false,
             settings_selected: 0,
             git_panel_open: false,
             git_panel_mode: GitPanelMode::Browse,
@@ -173,17 +306,14 @@ impl App {
             git_commit_input: String::new(),
             git_commit_cursor: 0,
             terminal_open: false,
-            terminal_scrollback: 0,
-            terminal_copy_mode: false,
-            terminal_search_open: false,
-            terminal_search_query: String::new(),
-            terminal_last_search: String::new(),
-            terminal_cursor_row: 0,
-            terminal_cursor_col: 0,
-            terminal_selection_anchor: None,
+            terminal_rename_open: false,
+            terminal_rename_input: String::new(),
+            terminal_tabs: Vec::new(),
+            terminal_active: 0,
             terminal_view_rows: 0,
             terminal_view_cols: 0,
-            terminal_session: None,
+            host_notifications: Vec::new(),
+            host_background: None,
             unstaged: Vec::new(),
             staged: Vec::new(),
             focus: FocusSection::Unstaged,
@@ -195,8 +325,17 @@ impl App {
             staged_scroll: 0,
             tree_scroll: 0,
             tree_files: Vec::new(),
+            tree_rows: Vec::new(),
+            collapsed_dirs: BTreeSet::new(),
+            change_files: Vec::new(),
+            untracked_stats: BTreeMap::new(),
             diff_rows: Vec::new(),
+            diff_old_source: None,
+            diff_new_source: None,
+            refresh_generation: 0,
             diff_scroll: 0,
+            diff_hscroll: 0,
+            diff_max_text_width: 0,
             diff_content_height: 0,
             status,
             layout: UiLayout::default(),
@@ -206,6 +345,14 @@ impl App {
 
         app.refresh()?;
         app.last_auto_refresh = Instant::now();
+        let restored = app.restore_persistent_terminal_tabs();
+        if restored > 0 && app.status.kind == StatusKind::Info {
+            app.set_status_info(format!(
+                "{restored} persistent terminal tab{} still running; {} to reattach",
+                if restored == 1 { "" } else { "s" },
+                app.keymap.key(Action::OpenTerminal)
+            ));
+        }
         Ok(app)
     }
 
@@ -227,8 +374,10 @@ impl App {
         self.unstaged = status.unstaged;
         self.staged = status.staged;
         self.branches = git::list_local_branches(&self.repo_root)?;
+        let staged_stats = git::numstat(&self.repo_root, true)?;
+        let unstaged_stats = git::numstat(&self.repo_root, false)?;
 
-        self.restore_tree_selection(previous_tree);
+        self.restore_tree_selection(previous_tree, &staged_stats, &unstaged_stats);
         self.restore_unstaged_selection(previous_unstaged);
         self.restore_staged_selection(previous_staged);
         self.restore_branch_selection(previous_branch);
@@ -237,45 +386,22 @@ impl App {
         let preserve_diff_scroll = self.active_selection() == previous_active;
         self.load_current_diff_with_scroll(preserve_diff_scroll, previous_diff_scroll)?;
         self.last_auto_refresh = Instant::now();
+        self.refresh_generation += 1;
 
         Ok(())
     }
 
     pub fn tick(&mut self) -> bool {
         let mut changed = false;
-        let mut exit_message = None;
-        let mut terminal_error = None;
-
-        if let Some(session) = self.terminal_session.as_mut() {
-            if session.pump_output() {
-                changed = true;
-            }
-
-            match session.poll_exit_message() {
-                Ok(message) => exit_message = message,
-                Err(error) => terminal_error = Some(error.to_string()),
-            }
-
-            if self.terminal_scrollback > 0 {
-                session.set_scrollback(self.terminal_scrollback);
-                self.terminal_scrollback = session.scrollback();
-                changed = true;
-            }
-        }
-
-        if let Some(error) = terminal_error {
-            self.set_status_error(error);
+        if self.pump_terminal_tabs() {
             changed = true;
         }
 
-        if let Some(message) = exit_message {
-            self.set_status_info(format!("Terminal session ended: {message}"));
-            changed = true;
-        }
-
-        if !self.terminal_open
+        if !self.terminal_overlay_open()
             && !self.settings_open
             && !self.git_panel_open
+            && !self.finder_open
+            && !self.command_palette_open
             && let Err(error) = self.auto_refresh_if_due()
         {
             self.set_status_error(error);
@@ -305,20 +431,23 @@ impl App {
     }
 
     pub fn cycle_focus_ring(&mut self, delta: isize) -> Result<()> {
-        if !self.has_sidebar() {
-            self.pane_focus = PaneFocus::Diff;
-            return Ok(());
+        let mut ring = Vec::with_capacity(3);
+        if self.has_sidebar() {
+            ring.push(PaneFocus::Sidebar);
         }
-
-        let mut remaining = delta.unsigned_abs().max(1);
-        while remaining > 0 {
-            self.pane_focus = match self.pane_focus {
-                PaneFocus::Sidebar => PaneFocus::Diff,
-                PaneFocus::Diff => PaneFocus::Sidebar,
-            };
-            remaining -= 1;
+        ring.push(PaneFocus::Diff);
+        if self.has_terminal_dock() {
+            ring.push(PaneFocus::Terminal);
         }
 
+        let current = ring
+            .iter()
+            .position(|pane| *pane == self.pane_focus)
+            .unwrap_or(0);
+        let delta = if delta == 0 { 1 } else { delta };
+        let next = (current as isize + delta).rem_euclid(ring.len() as isize);
+        self.pane_focus = ring[next as usize];
+
         self.normalize_focus();
         self.load_current_diff()
     }
@@ -387,6 +516,13 @@ impl App {
     }
 
     pub fn toggle_stage_state(&mut self) -> Result<()> {
+        if let Some(dir) = self.selected_tree_dir().cloned() {
+            if dir.acts_on_unstaged() && dir.unstaged_count > 0 {
+                return self.stage_selected_directory(&dir);
+            }
+            return self.unstage_selected_directory(&dir);
+        }
+
         let Some(entry) = self.selected_tree_file().cloned() else {
             self.set_status_warn("No file selected");
             return Ok(());
@@ -419,6 +555,10 @@ impl App {
     }
 
     pub fn stage_selected(&mut self) -> Result<()> {
+        if let Some(dir) = self.selected_tree_dir().cloned() {
+            return self.stage_selected_directory(&dir);
+        }
+
         let Some(entry) = self.selected_tree_file().cloned() else {
             self.set_status_warn("No file selected");
             return Ok(());
@@ -436,6 +576,10 @@ impl App {
     }
 
     pub fn unstage_selected(&mut self) -> Result<()> {
+        if let Some(dir) = self.selected_tree_dir().cloned() {
+            return self.unstage_selected_directory(&dir);
+        }
+
         let Some(entry) = self.selected_tree_file().cloned() else {
             self.set_status_warn("No file selected");
             return Ok(());
@@ -453,7 +597,7 @@ impl App {
     }
 
     pub fn undo_selected_to_mainline(&mut self) -> Result<()> {
-        let Some(target) = self.selected_undo_target() else {
+        let Some(target) = self.selected_undo_target(UndoAction::Mainline) else {
             return Ok(());
         };
 
@@ -466,24 +610,82 @@ impl App {
             return Ok(());
         }
 
-        self.apply_undo_to_mainline(target)
+        self.apply_undo(target)
+    }
+
+    pub fn discard_selected_worktree_changes(&mut self) -> Result<()> {
+        let Some(target) = self.selected_undo_target(UndoAction::DiscardWorktree) else {
+            return Ok(());
+        };
+
+        let prompt = if target.was_untracked {
+            format!(
+                "Delete untracked {}? Press Enter/y to confirm, n/Esc to cancel",
+                target.path
+            )
+        } else {
+            format!(
+                "Discard unstaged changes to {}? Staged changes are kept. Press Enter/y to confirm, n/Esc to cancel",
+                target.path
+            )
+        };
+        self.pending_undo_confirmation = Some(target);
+        self.set_status_warn(prompt);
+        Ok(())
+    }
+
+    pub fn reset_selected_to_head(&mut self) -> Result<()> {
+        let Some(target) = self.selected_undo_target(UndoAction::ResetToHead) else {
+            return Ok(());
+        };
+
+        // Resetting deletes files HEAD does not have; say so up front.
+        let prompt = if target.was_untracked {
+            format!(
+                "Delete untracked {}? Press Enter/y to confirm, n/Esc to cancel",
+                target.path
+            )
+        } else if !git::exists_in_head(&self.repo_root, &target.path)? {
+            format!(
+                "{} is not in HEAD; delete it? Press Enter/y to confirm, n/Esc to cancel",
+                target.path
+            )
+        } else {
+            format!(
+                "Reset {} to HEAD, dropping staged and unstaged changes? Press Enter/y to confirm, n/Esc to cancel",
+                target.path
+            )
+        };
+        self.pending_undo_confirmation = Some(target);
+        self.set_status_warn(prompt);
+        Ok(())
     }
 
     pub fn has_pending_undo_confirmation(&self) -> bool {
         self.pending_undo_confirmation.is_some()
     }
 
+    pub fn pending_undo_action(&self) -> Option<UndoAction> {
+        self.pending_undo_confirmation
+            .as_ref()
+            .map(|target| target.action)
+    }
+
     pub fn confirm_pending_undo_to_mainline(&mut self) -> Result<()> {
         let Some(target) = self.pending_undo_confirmation.take() else {
             return Ok(());
         };
 
-        self.apply_undo_to_mainline(target)
+        self.apply_undo(target)
     }
 
     pub fn cancel_pending_undo_to_mainline(&mut self) {
-        if self.pending_undo_confirmation.take().is_some() {
-            self.set_status_info("Undo cancelled");
+        if let Some(target) = self.pending_undo_confirmation.take() {
+            self.set_status_info(match target.action {
+                UndoAction::Mainline => "Undo cancelled",
+                UndoAction::DiscardWorktree => "Discard cancelled",
+                UndoAction::ResetToHead => "Reset cancelled",
+            });
         }
     }
 
@@ -498,17 +700,37 @@ impl App {
     ) -> Result<()> {
         if !preserve_scroll {
             self.diff_scroll = 0;
+            self.diff_hscroll = 0;
         }
 
         let Some((path, mode)) = self.active_selection() else {
             self.diff_rows.clear();
+            self.diff_old_source = None;
+            self.diff_new_source = None;
             self.diff_content_height = 0;
             self.diff_scroll = 0;
+            self.diff_hscroll = 0;
+            self.diff_max_text_width = 0;
             return Ok(());
         };
 
         let raw_diff = git::diff_for_file(&self.repo_root, &path, mode)?;
         self.diff_rows = parse_unified_diff(&raw_diff);
+        let (old, new) = git::file_versions(&self.repo_root, &path, mode, MAX_SOURCE_BYTES);
+        self.diff_old_source = old
+            .map(SourceFile::new)
+            .or_else(|| hunk_source(&self.diff_rows, true));
+        self.diff_new_source = new
+            .map(SourceFile::new)
+            .or_else(|| hunk_source(&self.diff_rows, false));
+        self.diff_max_text_width = self
+            .diff_rows
+            .iter()
+            .flat_map(|row| [&row.old, &row.new])
+            .flatten()
+            .map(|cell| display_width(&cell.text))
+            .max()
+            .unwrap_or(0);
         self.diff_content_height = self.diff_rows.len();
 
         if preserve_scroll {
@@ -529,6 +751,17 @@ impl App {
         Ok(())
     }
 
+    pub fn toggle_wrap_lines(&mut self) {
+        self.settings.wrap_lines = !self.settings.wrap_lines;
+        self.diff_hscroll = 0;
+        self.mark_settings_dirty();
+        self.set_status_info(if self.settings.wrap_lines {
+            "Line wrapping on"
+        } else {
+            "Line wrapping off"
+        });
+    }
+
     pub fn toggle_sidebar_visibility(&mut self) -> Result<()> {
         self.settings.sidebar_visible = !self.settings.sidebar_visible;
         if !self.settings.sidebar_visible {
@@ -562,6 +795,28 @@ impl App {
         Ok(())
     }
 
+    pub fn resize_terminal_dock(&mut self, delta: isize) -> Result<()> {
+        let next = shift_and_clamp_u16(
+            self.settings.terminal_dock_size,
+            delta,
+            5,
+            TERMINAL_DOCK_SIZE_MIN,
+            TERMINAL_DOCK_SIZE_MAX,
+        );
+
+        if next == self.settings.terminal_dock_size {
+            return Ok(());
+        }
+
+        self.settings.terminal_dock_size = next;
+        self.mark_settings_dirty();
+        self.set_status_info(format!(
+            "Terminal dock size: {}%",
+            self.settings.terminal_dock_size
+        ));
+        Ok(())
+    }
+
     pub fn toggle_git_panel(&mut self) -> Result<()> {
         if self.git_panel_open {
             self.close_git_panel();
@@ -829,421 +1084,6 @@ impl App {
         self.pending_branch_delete.as_deref()
     }
 
-    pub fn open_terminal(&mut self) -> Result<()> {
-        self.settings_open = false;
-        self.help_open = false;
-        self.git_panel_open = false;
-        self.reset_git_panel_state();
-        self.hide_terminal_panel();
-        self.terminal_open = true;
-        self.reset_terminal_navigation_state();
-
-        let session = self.ensure_live_terminal_session()?;
-        session.set_scrollback(0);
-        session.pump_output();
-        self.terminal_scrollback = session.scrollback();
-
-        self.set_status_info("Terminal open (interactive)");
-        Ok(())
-    }
-
-    pub fn close_terminal(&mut self) {
-        if self.terminal_open {
-            self.hide_terminal_panel();
-            self.reset_terminal_navigation_state();
-            self.terminal_view_rows = 0;
-            self.terminal_view_cols = 0;
-            self.terminal_session = None;
-            self.set_status_info("Terminal closed");
-            if let Err(error) = self.refresh() {
-                self.set_status_error(error);
-            }
-        }
-    }
-
-    pub fn terminal_send_key(&mut self, key: KeyEvent) -> Result<()> {
-        self.with_live_terminal_session(|session| session.send_key(key))
-    }
-
-    pub fn terminal_send_text(&mut self, text: &str) -> Result<()> {
-        self.with_live_terminal_session(|session| session.send_text(text))
-    }
-
-    pub fn terminal_resize(&mut self, rows: u16, cols: u16) -> Result<()> {
-        if let Some(session) = self.terminal_session.as_mut() {
-            session.resize(rows, cols)?;
-        }
-        Ok(())
-    }
-
-    pub fn terminal_screen(&self) -> Option<&vt100::Screen> {
-        self.terminal_session.as_ref().map(TerminalSession::screen)
-    }
-
-    fn terminal_rows_snapshot(&self) -> Vec<String> {
-        let Some(screen) = self.terminal_screen() else {
-            return Vec::new();
-        };
-
-        let (_, cols) = screen.size();
-        screen.rows(0, cols).collect()
-    }
-
-    pub fn scroll_terminal(&mut self, delta: isize) {
-        let Some(session) = self.terminal_session.as_mut() else {
-            return;
-        };
-
-        let target = if delta < 0 {
-            self.terminal_scrollback
-                .saturating_sub(delta.unsigned_abs())
-        } else {
-            self.terminal_scrollback.saturating_add(delta as usize)
-        };
-
-        session.set_scrollback(target);
-        self.terminal_scrollback = session.scrollback();
-
-        let max_row = self.terminal_view_rows.saturating_sub(1);
-        self.terminal_cursor_row = self.terminal_cursor_row.min(max_row);
-    }
-
-    pub fn set_terminal_viewport(&mut self, rows: usize, cols: usize) -> Result<()> {
-        self.terminal_view_rows = rows;
-        self.terminal_view_cols = cols;
-        self.terminal_cursor_row = self.terminal_cursor_row.min(rows.saturating_sub(1));
-        self.terminal_cursor_col = self.terminal_cursor_col.min(cols.saturating_sub(1));
-        self.terminal_resize(rows as u16, cols as u16)
-    }
-
-    pub fn terminal_enter_copy_mode(&mut self) {
-        self.terminal_copy_mode = true;
-        self.terminal_search_open = false;
-        self.terminal_search_query.clear();
-        self.terminal_selection_anchor = None;
-        self.terminal_cursor_row = self.terminal_view_rows.saturating_sub(1);
-        self.terminal_cursor_col = 0;
-        self.set_status_info("Copy mode");
-    }
-
-    pub fn terminal_exit_copy_mode(&mut self) {
-        if self.terminal_copy_mode {
-            self.terminal_copy_mode = false;
-            self.terminal_search_open = false;
-            self.terminal_search_query.clear();
-            self.terminal_selection_anchor = None;
-            self.set_status_info("Terminal interactive mode");
-        }
-    }
-
-    pub fn terminal_move_cursor(&mut self, row_delta: isize, col_delta: isize) {
-        if row_delta != 0 {
-            if row_delta < 0 {
-                for _ in 0..row_delta.unsigned_abs() {
-                    if self.terminal_cursor_row > 0 {
-                        self.terminal_cursor_row -= 1;
-                    } else {
-                        self.scroll_terminal(1);
-                    }
-                }
-            } else {
-                for _ in 0..row_delta as usize {
-                    let max_row = self.terminal_view_rows.saturating_sub(1);
-                    if self.terminal_cursor_row < max_row {
-                        self.terminal_cursor_row += 1;
-                    } else if self.terminal_scrollback > 0 {
-                        self.scroll_terminal(-1);
-                    }
-                }
-            }
-        }
-
-        if col_delta < 0 {
-            self.terminal_cursor_col = self
-                .terminal_cursor_col
-                .saturating_sub(col_delta.unsigned_abs());
-        } else if col_delta > 0 {
-            self.terminal_cursor_col = self
-                .terminal_cursor_col
-                .saturating_add(col_delta as usize)
-                .min(self.terminal_view_cols.saturating_sub(1));
-        }
-    }
-
-    pub fn terminal_toggle_selection_anchor(&mut self) {
-        if self.terminal_selection_anchor.is_some() {
-            self.terminal_selection_anchor = None;
-            self.set_status_info("Selection cleared");
-        } else {
-            self.terminal_selection_anchor =
-                Some((self.terminal_cursor_row, self.terminal_cursor_col));
-            self.set_status_info("Selection anchor set");
-        }
-    }
-
-    pub fn terminal_yank_selection(&mut self) -> Result<()> {
-        let Some(((start_row, start_col), (end_row, end_col))) = self.terminal_selection_bounds()
-        else {
-            self.set_status_warn("No selection anchor; press v first");
-            return Ok(());
-        };
-
-        let rows = self.terminal_rows_snapshot();
-        if rows.is_empty() {
-            self.set_status_warn("Nothing to copy");
-            return Ok(());
-        }
-
-        let mut out = String::new();
-        for row_idx in start_row..=end_row {
-            let line = rows.get(row_idx).map(String::as_str).unwrap_or("");
-            let line_len = line.chars().count();
-
-            let from = if row_idx == start_row {
-                start_col.min(line_len)
-            } else {
-                0
-            };
-            let to_exclusive = if row_idx == end_row {
-                end_col.saturating_add(1).min(line_len)
-            } else {
-                line_len
-            };
-
-            if from < to_exclusive {
-                out.extend(line.chars().skip(from).take(to_exclusive - from));
-            }
-
-            if row_idx < end_row {
-                out.push('\n');
-            }
-        }
-
-        if out.is_empty() {
-            self.set_status_warn("Selection is empty");
-            return Ok(());
-        }
-
-        let mut clipboard =
-            arboard::Clipboard::new().context("failed to access system clipboard")?;
-        clipboard
-            .set_text(out.clone())
-            .context("failed to copy selection to clipboard")?;
-
-        self.set_status_info(format!("Copied {} chars", out.chars().count()));
-        self.terminal_selection_anchor = None;
-        Ok(())
-    }
-
-    pub fn terminal_open_search(&mut self) {
-        self.terminal_search_open = true;
-        self.terminal_search_query = self.terminal_last_search.clone();
-    }
-
-    pub fn terminal_cancel_search(&mut self) {
-        self.terminal_search_open = false;
-        self.terminal_search_query.clear();
-        self.set_status_info("Search cancelled");
-    }
-
-    pub fn terminal_search_append(&mut self, ch: char) {
-        self.terminal_search_query.push(ch);
-    }
-
-    pub fn terminal_search_backspace(&mut self) {
-        self.terminal_search_query.pop();
-    }
-
-    pub fn terminal_search_next(&mut self) {
-        let query = if self.terminal_search_open {
-            self.terminal_search_query.trim().to_owned()
-        } else {
-            self.terminal_last_search.trim().to_owned()
-        };
-
-        if query.is_empty() {
-            self.set_status_warn("Search query is empty");
-            self.terminal_search_open = false;
-            return;
-        }
-
-        self.terminal_last_search = query.clone();
-        self.terminal_search_open = false;
-
-        let rows = self.terminal_rows_snapshot();
-        if rows.is_empty() {
-            self.set_status_warn("No terminal output to search");
-            return;
-        }
-
-        let start_row = self.terminal_cursor_row.min(rows.len().saturating_sub(1));
-        let start_col = self.terminal_cursor_col.saturating_add(1);
-
-        let found = (start_row..rows.len())
-            .chain(0..start_row)
-            .find_map(|row_idx| {
-                let col_start = if row_idx == start_row { start_col } else { 0 };
-                find_query_in_line(&rows[row_idx], &query, col_start)
-                    .map(|col_idx| (row_idx, col_idx))
-            });
-
-        if let Some((row_idx, col_idx)) = found {
-            self.terminal_cursor_row = row_idx.min(self.terminal_view_rows.saturating_sub(1));
-            self.terminal_cursor_col = col_idx.min(self.terminal_view_cols.saturating_sub(1));
-            self.set_status_info(format!("Found `{}`", query));
-        } else {
-            self.set_status_warn(format!("No match for `{}`", query));
-        }
-    }
-
-    pub fn terminal_cursor(&self) -> (usize, usize) {
-        (self.terminal_cursor_row, self.terminal_cursor_col)
-    }
-
-    fn terminal_selection_bounds(&self) -> Option<((usize, usize), (usize, usize))> {
-        self.terminal_selection_anchor.map(|anchor| {
-            order_positions(anchor, (self.terminal_cursor_row, self.terminal_cursor_col))
-        })
-    }
-
-    pub fn terminal_selection_rows(&self) -> Option<(usize, usize)> {
-        self.terminal_selection_bounds()
-            .map(|((start_row, _), (end_row, _))| (start_row, end_row))
-    }
-
-    pub fn toggle_settings_panel(&mut self) {
-        self.help_open = false;
-        self.hide_terminal_panel();
-        self.git_panel_open = false;
-        self.reset_git_panel_state();
-        self.settings_open = !self.settings_open;
-        if self.settings_open {
-            self.set_status_info("Settings open");
-        } else {
-            self.finish_close_settings_panel();
-        }
-    }
-
-    pub fn close_settings_panel(&mut self) {
-        if self.settings_open {
-            self.settings_open = false;
-            self.finish_close_settings_panel();
-        }
-    }
-
-    pub fn move_settings_selection(&mut self, delta: isize) {
-        if SETTINGS_FIELD_COUNT == 0 {
-            self.settings_selected = 0;
-            return;
-        }
-
-        let current = self.settings_selected.min(SETTINGS_FIELD_COUNT - 1);
-        self.settings_selected = if delta < 0 {
-            current.saturating_sub(delta.unsigned_abs())
-        } else {
-            (current + delta as usize).min(SETTINGS_FIELD_COUNT - 1)
-        };
-    }
-
-    pub fn adjust_selected_setting(&mut self, delta: isize) -> Result<()> {
-        match self.settings_selected {
-            0 => {
-                self.settings.diff_view_mode = self.settings.diff_view_mode.cycle(delta);
-                self.mark_settings_dirty();
-                self.set_status_info(format!(
-                    "Diff view: {}",
-                    self.settings.diff_view_mode.label()
-                ));
-            }
-            1 => {
-                self.settings.sidebar_visible = !self.settings.sidebar_visible;
-                self.mark_settings_dirty();
-                self.set_status_info(if self.settings.sidebar_visible {
-                    String::from("Sidebar shown")
-                } else {
-                    String::from("Sidebar hidden")
-                });
-            }
-            2 => {
-                self.settings.sidebar_position = self.settings.sidebar_position.cycle(delta);
-                self.mark_settings_dirty();
-                self.set_status_info(format!(
-                    "Sidebar side: {}",
-                    self.settings.sidebar_position.label()
-                ));
-            }
-            3 => {
-                self.settings.sidebar_width = shift_and_clamp_u16(
-                    self.settings.sidebar_width,
-                    delta,
-                    2,
-                    SIDEBAR_WIDTH_MIN,
-                    SIDEBAR_WIDTH_MAX,
-                );
-                self.mark_settings_dirty();
-                self.set_status_info(format!("Sidebar width: {}", self.settings.sidebar_width));
-            }
-            4 => {
-                self.settings.auto_split_min_width = shift_and_clamp_u16(
-                    self.settings.auto_split_min_width,
-                    delta,
-                    4,
-                    AUTO_SPLIT_MIN_WIDTH_MIN,
-                    AUTO_SPLIT_MIN_WIDTH_MAX,
-                );
-                self.mark_settings_dirty();
-                self.set_status_info(format!(
-                    "Auto split min width: {}",
-                    self.settings.auto_split_min_width
-                ));
-            }
-            5 => {
-                self.settings.theme = self.settings.theme.cycle(delta);
-                self.mark_settings_dirty();
-                self.set_status_info(format!("Theme: {}", self.settings.theme.label()));
-            }
-            6 => {
-                self.settings.confirm_undo_to_mainline = !self.settings.confirm_undo_to_mainline;
-                self.mark_settings_dirty();
-                self.set_status_info(if self.settings.confirm_undo_to_mainline {
-                    String::from("Undo confirmation: enabled")
-                } else {
-                    String::from("Undo confirmation: disabled")
-                });
-            }
-            _ => {}
-        }
-
-        Ok(())
-    }
-
-    pub fn settings_rows(&self) -> Vec<(&'static str, String)> {
-        vec![
-            (
-                "Diff Layout",
-                self.settings.diff_view_mode.label().to_owned(),
-            ),
-            (
-                "Sidebar Visible",
-                yes_no_label(self.settings.sidebar_visible).to_owned(),
-            ),
-            (
-                "Sidebar Side",
-                self.settings.sidebar_position.label().to_owned(),
-            ),
-            ("Sidebar Width", self.settings.sidebar_width.to_string()),
-            (
-                "Auto Split Min Width",
-                self.settings.auto_split_min_width.to_string(),
-            ),
-            ("Theme", self.settings.theme.label().to_owned()),
-            (
-                "Confirm Undo",
-                yes_no_label(self.settings.confirm_undo_to_mainline).to_owned(),
-            ),
-        ]
-    }
-
     pub fn resolved_diff_layout(&self, diff_width: u16) -> ResolvedDiffLayout {
         match self.settings.diff_view_mode {
             DiffViewMode::Split => ResolvedDiffLayout::Split,
@@ -1290,6 +1130,7 @@ impl App {
         match self.pane_focus {
             PaneFocus::Diff => "Diff",
             PaneFocus::Sidebar => "Sidebar",
+            PaneFocus::Terminal => "Terminal",
         }
     }
 
@@ -1334,6 +1175,17 @@ impl App {
         (added, removed)
     }
 
+    /// Staged and unstaged line totals across the files shown in the sidebar.
+    pub fn tree_line_totals(&self) -> (LineStat, LineStat) {
+        let mut staged = LineStat::default();
+        let mut unstaged = LineStat::default();
+        for entry in &self.tree_files {
+            staged.accumulate(entry.staged_stat);
+            unstaged.accumulate(entry.unstaged_stat);
+        }
+        (staged, unstaged)
+    }
+
     pub fn status_text(&self) -> &str {
         self.status.text.as_str()
     }
@@ -1344,7 +1196,9 @@ impl App {
 
     pub fn update_layout(&mut self, root: Rect) -> Result<()> {
         let (main_area, _) = layout::split_root(root);
-        let (sidebar_area, diff_area) = layout::split_main_area(main_area, &self.settings);
+        let (content_area, dock_area) =
+            layout::split_terminal_dock(main_area, &self.settings, self.terminal_open);
+        let (sidebar_area, diff_area) = layout::split_main_area(content_area, &self.settings);
 
         let tree_inner = if let Some(area) = sidebar_area {
             layout::bordered_inner(area)
@@ -1355,21 +1209,21 @@ impl App {
         let (_, diff_body_area) = layout::split_diff(diff_area);
         let resolved_layout = self.resolved_diff_layout(diff_body_area.width);
 
-        let (diff_viewport_height, diff_content_height) = match resolved_layout {
+        let diff_viewport_height = match resolved_layout {
             ResolvedDiffLayout::Split => {
                 let (old_pane, new_pane) = layout::split_split_diff(diff_body_area);
                 let old_inner = layout::bordered_inner(old_pane);
                 let new_inner = layout::bordered_inner(new_pane);
-                (
-                    old_inner.height.min(new_inner.height) as usize,
-                    self.diff_rows.len(),
-                )
+                old_inner.height.min(new_inner.height) as usize
             }
-            ResolvedDiffLayout::Unified => (
-                layout::bordered_inner(diff_body_area).height as usize,
-                unified_line_count(&self.diff_rows),
-            ),
+            ResolvedDiffLayout::Unified => layout::bordered_inner(diff_body_area).height as usize,
         };
+        let text_widths = self.diff_text_widths(resolved_layout, diff_body_area);
+        let diff_content_height = display_line_count(
+            &self.diff_rows,
+            resolved_layout == ResolvedDiffLayout::Unified,
+            self.settings.wrap_lines.then_some(text_widths),
+        );
 
         self.layout = UiLayout {
             unstaged_inner: Rect::new(0, 0, 0, 0),
@@ -1377,16 +1231,25 @@ impl App {
             tree_inner,
             diff_area: diff_body_area,
             diff_viewport_height,
+            diff_text_width: text_widths.old.min(text_widths.new),
+            terminal_dock: dock_area.unwrap_or_default(),
+            terminal_output: if self.terminal_open {
+                layout::terminal_output_area(root, &self.settings)
+            } else {
+                Rect::default()
+            },
         };
         self.diff_content_height = diff_content_height;
         self.sync_scrolls();
 
-        if !self.has_sidebar() {
+        if (!self.has_sidebar() && self.pane_focus == PaneFocus::Sidebar)
+            || (!self.has_terminal_dock() && self.pane_focus == PaneFocus::Terminal)
+        {
             self.pane_focus = PaneFocus::Diff;
         }
 
         if self.terminal_open {
-            let output_area = layout::terminal_output_area(root);
+            let output_area = self.layout.terminal_output;
             self.set_terminal_viewport(output_area.height as usize, output_area.width as usize)?;
         }
 
@@ -1424,7 +1287,7 @@ impl App {
         );
         ensure_visible(
             self.tree_selected,
-            self.tree_files.len(),
+            self.tree_rows.len(),
             tree_visible,
             &mut self.tree_scroll,
         );
@@ -1436,6 +1299,76 @@ impl App {
             let max_scroll = self.diff_content_height.saturating_sub(diff_visible);
             self.diff_scroll = self.diff_scroll.min(max_scroll);
         }
+
+        if self.settings.wrap_lines {
+            self.diff_hscroll = 0;
+        } else {
+            let max_hscroll = self
+                .diff_max_text_width
+                .saturating_sub(self.layout.diff_text_width);
+            self.diff_hscroll = self.diff_hscroll.min(max_hscroll);
+        }
+    }
+
+    /// Scrolls the diff sideways by `delta` steps; a no-op while wrapping.
+    pub fn scroll_diff_horizontal(&mut self, delta: isize) {
+        if self.settings.wrap_lines {
+            self.set_status_warn("Lines are wrapped; turn wrapping off to scroll sideways");
+            return;
+        }
+
+        let columns = delta.unsigned_abs().saturating_mul(DIFF_HSCROLL_STEP);
+        self.diff_hscroll = if delta < 0 {
+            self.diff_hscroll.saturating_sub(columns)
+        } else {
+            self.diff_hscroll.saturating_add(columns)
+        };
+        self.sync_scrolls();
+    }
+
+    /// Text columns each diff pane wraps at, or `None` when wrapping is off.
+    pub fn diff_wrap_widths(
+        &self,
+        resolved_layout: ResolvedDiffLayout,
+        diff_body_area: Rect,
+    ) -> Option<WrapWidths> {
+        self.settings
+            .wrap_lines
+            .then(|| self.diff_text_widths(resolved_layout, diff_body_area))
+    }
+
+    /// Columns left for text in each pane once borders, line numbers and the
+    /// unified marker are drawn.
+    fn diff_text_widths(
+        &self,
+        resolved_layout: ResolvedDiffLayout,
+        diff_body_area: Rect,
+    ) -> WrapWidths {
+        let old_numbers = line_number_width(&self.diff_rows, true);
+        let new_numbers = line_number_width(&self.diff_rows, false);
+        match resolved_layout {
+            ResolvedDiffLayout::Split => {
+                let (old_pane, new_pane) = layout::split_split_diff(diff_body_area);
+                let text_width = |pane: Rect, numbers: usize| {
+                    usize::from(layout::bordered_inner(pane).width)
+                        .saturating_sub(numbers + 1)
+                        .max(1)
+                };
+                WrapWidths {
+                    old: text_width(old_pane, old_numbers),
+                    new: text_width(new_pane, new_numbers),
+                }
+            }
+            ResolvedDiffLayout::Unified => {
+                let width = usize::from(layout::bordered_inner(diff_body_area).width)
+                    .saturating_sub(old_numbers + new_numbers + 4)
+                    .max(1);
+                WrapWidths {
+                    old: width,
+                    new: width,
+                }
+            }
+        }
     }
 
     pub fn scroll_diff(&mut self, delta: isize) {
@@ -1447,6 +1380,22 @@ impl App {
         self.sync_scrolls();
     }
 
+    /// Scrolls the diff so new-side `line` sits near the top. Returns false
+    /// when the line is past the last hunk.
+    pub fn scroll_diff_to_line(&mut self, line: usize) -> bool {
+        let resolved_layout = self.resolved_diff_layout(self.layout.diff_area.width);
+        let unified = resolved_layout == ResolvedDiffLayout::Unified;
+        let wrap = self.diff_wrap_widths(resolved_layout, self.layout.diff_area);
+        let Some(offset) = display_offset_for_line(&self.diff_rows, line, unified, wrap) else {
+            return false;
+        };
+
+        self.diff_content_height = display_line_count(&self.diff_rows, unified, wrap);
+        self.diff_scroll = offset.saturating_sub(DIFF_JUMP_CONTEXT_LINES);
+        self.sync_scrolls();
+        true
+    }
+
     pub fn is_diff_focused(&self) -> bool {
         self.pane_focus == PaneFocus::Diff
     }
@@ -1459,7 +1408,7 @@ impl App {
 
         self.pane_focus = match self.pane_focus {
             PaneFocus::Sidebar => PaneFocus::Diff,
-            PaneFocus::Diff => PaneFocus::Sidebar,
+            PaneFocus::Diff | PaneFocus::Terminal => PaneFocus::Sidebar,
         };
     }
 
@@ -1472,7 +1421,7 @@ impl App {
                 return Ok(());
             }
             let idx = self.tree_scroll + offset.saturating_sub(1);
-            if idx < self.tree_files.len() {
+            if idx < self.tree_rows.len() {
                 self.tree_selected = Some(idx);
             }
             self.load_current_diff()?;
@@ -1507,6 +1456,10 @@ impl App {
             self.pane_focus = PaneFocus::Diff;
         }
 
+        if contains(self.layout.terminal_dock, column, row) {
+            self.pane_focus = PaneFocus::Terminal;
+        }
+
         Ok(())
     }
 
@@ -1514,12 +1467,35 @@ impl App {
         contains(self.layout.diff_area, column, row)
     }
 
+    /// Shows the first problem found in a config section, if any.
+    pub fn report_config_problems(&mut self, section: &str, problems: &[String]) {
+        if let Some(warning) = config_problem_status(section, problems) {
+            self.status = warning;
+        }
+    }
+
     pub fn set_error(&mut self, error: impl ToString) {
         self.set_status_error(error.to_string());
     }
 
+    pub fn diff_old_source(&self) -> Option<&SourceFile> {
+        self.diff_old_source.as_ref()
+    }
+
+    pub fn diff_new_source(&self) -> Option<&SourceFile> {
+        self.diff_new_source.as_ref()
+    }
+
+    pub fn refresh_generation(&self) -> u64 {
+        self.refresh_generation
+    }
+
     pub fn active_path(&self) -> Option<&str> {
-        self.selected_tree_path()
+        self.selected_tree_file().map(|entry| entry.path.as_str())
+    }
+
+    pub fn selected_tree_dir(&self) -> Option<&TreeRow> {
+        self.selected_tree_row().filter(|row| row.is_header())
     }
 
     pub fn active_label(&self) -> &'static str {
@@ -1540,88 +1516,65 @@ impl App {
         }
     }
 
-    fn selected_undo_target(&mut self) -> Option<PendingUndoConfirmation> {
+    fn selected_undo_target(&mut self, action: UndoAction) -> Option<PendingUndoConfirmation> {
+        if self.selected_tree_dir().is_some() {
+            self.set_status_warn(format!(
+                "Select a file to {}; directories cannot be changed at once",
+                action.label()
+            ));
+            return None;
+        }
+
         let Some(entry) = self.selected_tree_file().cloned() else {
             self.set_status_warn("No file selected");
             return None;
         };
 
+        if action == UndoAction::DiscardWorktree && !entry.unstaged {
+            self.set_status_warn("Selected file has no unstaged changes");
+            return None;
+        }
+
         Some(PendingUndoConfirmation {
             path: entry.path,
             was_untracked: entry.untracked,
+            action,
         })
     }
 
-    fn apply_undo_to_mainline(&mut self, target: PendingUndoConfirmation) -> Result<()> {
-        let mainline =
-            git::undo_file_to_mainline(&self.repo_root, &target.path, target.was_untracked)?;
-        self.refresh()?;
-        self.set_status_info(format!("Reverted {} to {mainline}", target.path));
-        Ok(())
-    }
-
-    pub fn flush_pending_settings(&mut self) -> Result<()> {
-        self.flush_settings_if_dirty()
-    }
-
-    fn mark_settings_dirty(&mut self) {
-        self.settings.normalize();
-        self.settings_dirty = true;
-        self.last_settings_change = Some(Instant::now());
-    }
-
-    fn flush_settings_if_due(&mut self) -> Result<bool> {
-        if !self.settings_dirty {
-            return Ok(false);
-        }
-
-        let Some(changed_at) = self.last_settings_change else {
-            return Ok(false);
+    fn apply_undo(&mut self, target: PendingUndoConfirmation) -> Result<()> {
+        let message = match target.action {
+            UndoAction::Mainline => {
+                let mainline = git::undo_file_to_mainline(
+                    &self.repo_root,
+                    &target.path,
+                    target.was_untracked,
+                )?;
+                format!("Reverted {} to {mainline}", target.path)
+            }
+            UndoAction::DiscardWorktree => {
+                git::discard_worktree_changes(&self.repo_root, &target.path, target.was_untracked)?;
+                if target.was_untracked {
+                    format!("Deleted untracked {}", target.path)
+                } else {
+                    format!("Discarded unstaged changes to {}", target.path)
+                }
+            }
+            UndoAction::ResetToHead => {
+                git::reset_file_to_head(&self.repo_root, &target.path, target.was_untracked)?;
+                if self.repo_root.join(&target.path).exists() {
+                    format!("Reset {} to HEAD", target.path)
+                } else {
+                    format!("Deleted {}", target.path)
+                }
+            }
         };
 
-        if changed_at.elapsed() < SETTINGS_WRITE_DEBOUNCE {
-            return Ok(false);
-        }
-
-        settings::save(&self.settings)?;
-        self.settings_dirty = false;
-        self.last_settings_change = None;
-        Ok(true)
-    }
-
-    fn flush_settings_if_dirty(&mut self) -> Result<()> {
-        if !self.settings_dirty {
-            return Ok(());
-        }
-
-        settings::save(&self.settings)?;
-        self.settings_dirty = false;
-        self.last_settings_change = None;
+        self.refresh()?;
+        self.set_status_info(message);
         Ok(())
     }
 
-    fn finish_close_settings_panel(&mut self) {
-        if let Err(error) = self.flush_settings_if_dirty() {
-            self.set_status_error(error);
-            return;
-        }
-        self.set_status_info("Settings closed");
-    }
-
-    fn hide_terminal_panel(&mut self) {
-        self.terminal_open = false;
-        self.terminal_copy_mode = false;
-        self.terminal_search_open = false;
-        self.terminal_search_query.clear();
-        self.terminal_selection_anchor = None;
-    }
-
-    fn reset_terminal_navigation_state(&mut self) {
-        self.terminal_scrollback = 0;
-        self.terminal_cursor_row = 0;
-        self.terminal_cursor_col = 0;
-    }
-
     fn reset_git_panel_state(&mut self) {
         self.set_git_panel_browse_mode();
         self.git_branch_input.clear();
@@ -1634,34 +1587,6 @@ impl App {
         self.pending_branch_delete = None;
     }
 
-    fn ensure_live_terminal_session(&mut self) -> Result<&mut TerminalSession> {
-        let must_start = self
-            .terminal_session
-            .as_ref()
-            .map(|session| session.is_exited())
-            .unwrap_or(true);
-
-        if must_start {
-            self.terminal_session = Some(TerminalSession::start(&self.repo_root, 24, 80)?);
-            self.terminal_scrollback = 0;
-        }
-
-        self.terminal_session
-            .as_mut()
-            .ok_or_else(|| anyhow!("terminal session should exist after initialization"))
-    }
-
-    fn with_live_terminal_session<F>(&mut self, action: F) -> Result<()>
-    where
-        F: FnOnce(&mut TerminalSession) -> Result<()>,
-    {
-        let session = self.ensure_live_terminal_session()?;
-        action(session)?;
-        session.pump_output();
-        self.terminal_scrollback = session.scrollback();
-        Ok(())
-    }
-
     fn auto_refresh_if_due(&mut self) -> Result<()> {
         if self.last_auto_refresh.elapsed() < AUTO_REFRESH_INTERVAL {
             return Ok(());
@@ -1675,6 +1600,10 @@ impl App {
         self.layout.tree_inner.width > 0
     }
 
+    fn has_terminal_dock(&self) -> bool {
+        self.terminal_open && self.layout.terminal_dock.width > 0
+    }
+
     fn selected_unstaged(&self) -> Option<&FileEntry> {
         self.unstaged_selected
             .and_then(|idx| self.unstaged.get(idx))
@@ -1690,20 +1619,25 @@ impl App {
     }
 
     fn selected_tree_file(&self) -> Option<&TreeFileEntry> {
-        self.tree_selected.and_then(|idx| self.tree_files.get(idx))
+        self.selected_tree_row()
+            .and_then(|row| row.file_index)
+            .and_then(|idx| self.tree_files.get(idx))
     }
 
     fn selected_tree_path(&self) -> Option<&str> {
-        self.selected_tree_file().map(|entry| entry.path.as_str())
+        self.selected_tree_row().map(|row| row.path.as_str())
     }
 
     fn selected_branch(&self) -> Option<&BranchEntry> {
         self.branch_selected.and_then(|idx| self.branches.get(idx))
     }
 
-    fn restore_tree_selection(&mut self, preferred_path: Option<String>) {
-        use std::collections::BTreeMap;
-
+    fn restore_tree_selection(
+        &mut self,
+        preferred_path: Option<String>,
+        staged_stats: &BTreeMap<String, LineStat>,
+        unstaged_stats: &BTreeMap<String, LineStat>,
+    ) {
         let mut map: BTreeMap<String, TreeFileEntry> = BTreeMap::new();
 
         for path in &self.staged {
@@ -1712,8 +1646,10 @@ impl App {
                 ..TreeFileEntry::default()
             });
             entry.staged = true;
+            entry.staged_stat = staged_stats.get(path).copied().unwrap_or_default();
         }
 
+        let previous_stats = std::mem::take(&mut self.untracked_stats);
         for file in &self.unstaged {
             let entry = map
                 .entry(file.path.clone())
@@ -1723,25 +1659,50 @@ impl App {
                 });
             entry.unstaged = true;
             entry.untracked = file.kind == UnstagedKind::Untracked;
+            entry.unstaged_stat = if entry.untracked {
+                match cached_untracked_stat(&self.repo_root, &previous_stats, &file.path) {
+                    Some(counted) => {
+                        self.untracked_stats.insert(file.path.clone(), counted);
+                        counted.stat
+                    }
+                    None => LineStat::default(),
+                }
+            } else {
+                unstaged_stats.get(&file.path).copied().unwrap_or_default()
+            };
+        }
+
+        self.change_files = map.into_values().collect();
+        for entry in &mut self.change_files {
+            entry.modified = fs::metadata(self.repo_root.join(&entry.path))
+                .and_then(|meta| meta.modified())
+                .ok();
         }
+        sort_tree_files(&mut self.change_files, self.settings.sidebar_sort);
+        self.apply_tree_filter(preferred_path);
+    }
 
-        self.tree_files = map.into_values().collect();
+    fn apply_tree_filter(&mut self, preferred_path: Option<String>) {
+        let section = self.selected_tree_section();
+        self.tree_files = self.filtered_tree_files();
+        self.rebuild_tree_rows();
 
-        if self.tree_files.is_empty() {
+        if self.tree_rows.is_empty() {
             self.tree_selected = None;
             self.tree_scroll = 0;
             return;
         }
 
-        if let Some(path) = preferred_path
-            && let Some(idx) = self.tree_files.iter().position(|entry| entry.path == path)
-        {
-            self.tree_selected = Some(idx);
-            return;
+        if let Some(path) = preferred_path {
+            let visible = self.visible_ancestor_path(&path, section.as_deref());
+            if let Some(idx) = self.tree_row_index(&visible, section.as_deref()) {
+                self.tree_selected = Some(idx);
+                return;
+            }
         }
 
         if let Some(idx) = self.tree_selected
-            && idx < self.tree_files.len()
+            && idx < self.tree_rows.len()
         {
             return;
         }
@@ -1822,7 +1783,7 @@ impl App {
     }
 
     fn normalize_focus(&mut self) {
-        if self.tree_files.is_empty() {
+        if self.tree_rows.is_empty() {
             self.tree_selected = None;
             self.tree_scroll = 0;
         } else if self.tree_selected.is_none() {
@@ -1848,7 +1809,7 @@ impl App {
     }
 
     fn focus_len(&self) -> usize {
-        self.tree_files.len()
+        self.tree_rows.len()
     }
 
     fn focus_selected(&self) -> Option<usize> {
@@ -1884,6 +1845,47 @@ fn initial_commit_cursor(template: &str) -> usize {
     0
 }
 
-fn yes_no_label(value: bool) -> &'static str {
-    if value { "Yes" } else { "No" }
+/// One side's lines straight from the diff, for files too big or otherwise
+/// unavailable to highlight whole.
+fn hunk_source(rows: &[DiffRow], old_side: bool) -> Option<SourceFile> {
+    let source = SourceFile::from_lines(rows.iter().filter_map(|row| {
+        let cell = if old_side { &row.old } else { &row.new };
+        let cell = cell.as_ref().filter(|cell| cell.kind != CellKind::Meta)?;
+        Some((cell.line_no?, cell.text.as_str()))
+    }));
+    (!source.is_empty()).then_some(source)
+}
+
+/// Reuses the previous count while the file's size and mtime are unchanged.
+fn cached_untracked_stat(
+    repo_root: &Path,
+    previous: &BTreeMap<String, UntrackedStat>,
+    path: &str,
+) -> Option<UntrackedStat> {
+    let meta = fs::metadata(repo_root.join(path)).ok()?;
+    let modified = meta.modified().ok();
+    let len = meta.len();
+    if let Some(cached) = previous
+        .get(path)
+        .filter(|cached| modified.is_some() && cached.modified == modified && cached.len == len)
+    {
+        return Some(*cached);
+    }
+
+    Some(UntrackedStat {
+        modified,
+        len,
+        stat: git::untracked_line_stat(repo_root, path)?,
+    })
+}
+
+/// A startup warning for the first problem in a config section.
+fn config_problem_status(section: &str, problems: &[String]) -> Option<StatusMessage> {
+    let problem = problems.first()?;
+    let more = problems.len() - 1;
+    Some(StatusMessage::warn(if more == 0 {
+        format!("[{section}] {problem}")
+    } else {
+        format!("[{section}] {problem} (+{more} more)")
+    }))
 }
@@ -0,0 +1,181 @@
+use crate::fuzzy;
+use crate::keymap::Action;
+
+use super::App;
+
+/// One row of the command palette.
+#[derive(Debug, Clone)]
+pub struct CommandEntry {
+    pub action: Action,
+    pub keys: String,
+    pub enabled: bool,
+    pub positions: Vec<usize>,
+}
+
+impl App {
+    pub fn open_command_palette(&mut self) {
+        self.settings_open = false;
+        self.help_open = false;
+        self.finder_open = false;
+        self.tree_filter_editing = false;
+        self.command_palette_open = true;
+        self.command_palette_query.clear();
+        self.command_palette_selected = 0;
+        self.update_command_palette_results();
+        self.set_status_info("Command palette: type to filter, Enter to run");
+    }
+
+    pub fn close_command_palette(&mut self) {
+        if self.command_palette_open {
+            self.command_palette_open = false;
+            self.command_palette_query.clear();
+            self.command_palette_results.clear();
+            self.command_palette_selected = 0;
+            self.set_status_info("Command palette closed");
+        }
+    }
+
+    pub fn command_palette_append(&mut self, ch: char) {
+        self.command_palette_query.push(ch);
+        self.command_palette_selected = 0;
+        self.update_command_palette_results();
+    }
+
+    pub fn command_palette_backspace(&mut self) {
+        self.command_palette_query.pop();
+        self.command_palette_selected = 0;
+        self.update_command_palette_results();
+    }
+
+    pub fn command_palette_move_selection(&mut self, delta: isize) {
+        let len = self.command_palette_results.len();
+        if len == 0 {
+            self.command_palette_selected = 0;
+            return;
+        }
+
+        let current = self.command_palette_selected.min(len - 1);
+        self.command_palette_selected = if delta < 0 {
+            current.saturating_sub(delta.unsigned_abs())
+        } else {
+            (current + delta as usize).min(len - 1)
+        };
+    }
+
+    /// Closes the palette and hands back the selected action for the caller
+    /// to run. A disabled action keeps the palette open with a warning.
+    pub fn submit_command_palette(&mut self) -> Option<Action> {
+        let Some(entry) = self
+            .command_palette_results
+            .get(self.command_palette_selected)
+            .cloned()
+        else {
+            self.set_status_warn(format!(
+                "No command matches `{}`",
+                self.command_palette_query
+            ));
+            return None;
+        };
+
+        if !entry.enabled {
+            self.set_status_warn(format!("Cannot {} here", entry.action.description()));
+            return None;
+        }
+
+        self.command_palette_open = false;
+        self.command_palette_query.clear();
+        self.command_palette_results.clear();
+        self.command_palette_selected = 0;
+        Some(entry.action)
+    }
+
+    pub fn command_palette_results(&self) -> &[CommandEntry] {
+        &self.command_palette_results
+    }
+
+    /// Whether `action` would do anything from where the palette was opened.
+    pub fn action_enabled(&self, action: Action) -> bool {
+        let selected_row = self.selected_tree_row();
+        let tab_count = self.terminal_tabs.len();
+        match action {
+            Action::StageToggle
+            | Action::Stage
+            | Action::Unstage
+            | Action::UndoToMainline
+            | Action::DiscardWorktree
+            | Action::ResetToHead => selected_row.is_some(),
+            Action::ToggleDirectory => {
+                !self.tree_filter_active() && selected_row.is_some_and(|row| row.is_directory())
+            }
+            Action::ToggleAllDirectories => !self.tree_filter_active(),
+            Action::SidebarNarrow | Action::SidebarWide => self.settings.sidebar_visible,
+            Action::TerminalDockShrink | Action::TerminalDockGrow => {
+                self.settings.terminal_dock.is_docked()
+            }
+            Action::OpenTaskPicker => !self.tasks().is_empty(),
+            Action::ScrollLeft | Action::ScrollRight => !self.settings.wrap_lines,
+            Action::GitSwitchBranch | Action::GitDeleteBranch => {
+                self.git_panel_open && self.selected_branch().is_some_and(|branch| !branch.current)
+            }
+            Action::TerminalCloseTab
+            | Action::TerminalRenameTab
+            | Action::TerminalCopyMode
+            | Action::TerminalSearch
+            | Action::TerminalSearchBackward
+            | Action::TerminalExport
+            | Action::TerminalExportAnsi => tab_count > 0,
+            Action::TerminalNextTab | Action::TerminalPrevTab => tab_count > 1,
+            // These only mean something once copy mode or search is running.
+            Action::TerminalInteractiveMode
+            | Action::TerminalCursorUp
+            | Action::TerminalCursorDown
+            | Action::TerminalCursorLeft
+            | Action::TerminalCursorRight
+            | Action::TerminalSelectionAnchor
+            | Action::TerminalYank
+            | Action::TerminalSearchNext
+            | Action::TerminalSearchPrev
+            | Action::TerminalSearchToggleRegex
+            | Action::TerminalSearchToggleCase
+            | Action::TerminalNextRef
+            | Action::TerminalOpenRef
+            | Action::OpenCommandPalette => false,
+            // These only act while the terminal, task picker or settings has
+            // the keys.
+            Action::TerminalHide
+            | Action::TerminalScrollUp
+            | Action::TerminalScrollDown
+            | Action::TerminalSelectTab
+            | Action::SettingDecrease
+            | Action::SettingIncrease => false,
+            _ => true,
+        }
+    }
+
+    fn update_command_palette_results(&mut self) {
+        // The branch panel's commit key repeats `OpenCommitPrompt`.
+        let actions = Action::all()
+            .filter(|action| !matches!(action, Action::OpenCommandPalette | Action::GitCommit))
+            .collect::<Vec<_>>();
+        let ranked = fuzzy::rank(
+            &self.command_palette_query,
+            actions.iter().map(|action| action.description()),
+        );
+
+        self.command_palette_results = ranked
+            .into_iter()
+            .map(|(idx, found)| {
+                let action = actions[idx];
+                CommandEntry {
+                    action,
+                    keys: self.keymap.keys(action),
+                    enabled: self.action_enabled(action),
+                    positions: found.positions,
+                }
+            })
+            .collect();
+        self.command_palette_selected = self
+            .command_palette_selected
+            .min(self.command_palette_results.len().saturating_sub(1));
+    }
+}
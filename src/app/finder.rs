@@ -0,0 +1,245 @@
+use anyhow::Result;
+
+use crate::fuzzy;
+
+use super::{App, PaneFocus, TreeFileEntry};
+
+#[derive(Debug, Clone)]
+pub struct FinderResult {
+    pub entry: TreeFileEntry,
+    pub positions: Vec<usize>,
+}
+
+impl App {
+    pub fn open_finder(&mut self) {
+        self.settings_open = false;
+        self.help_open = false;
+        self.tree_filter_editing = false;
+        self.finder_open = true;
+        self.finder_query.clear();
+        self.finder_selected = 0;
+        self.update_finder_results();
+        self.set_status_info("Find file: type to filter, Enter to jump");
+    }
+
+    pub fn close_finder(&mut self) {
+        if self.finder_open {
+            self.finder_open = false;
+            self.finder_query.clear();
+            self.finder_results.clear();
+            self.finder_selected = 0;
+            self.set_status_info("Find cancelled");
+        }
+    }
+
+    pub fn finder_input_append(&mut self, ch: char) {
+        self.finder_query.push(ch);
+        self.finder_selected = 0;
+        self.update_finder_results();
+    }
+
+    pub fn finder_input_backspace(&mut self) {
+        self.finder_query.pop();
+        self.finder_selected = 0;
+        self.update_finder_results();
+    }
+
+    pub fn finder_move_selection(&mut self, delta: isize) {
+        let len = self.finder_results.len();
+        if len == 0 {
+            self.finder_selected = 0;
+            return;
+        }
+
+        let current = self.finder_selected.min(len - 1);
+        self.finder_selected = if delta < 0 {
+            current.saturating_sub(delta.unsigned_abs())
+        } else {
+            (current + delta as usize).min(len - 1)
+        };
+    }
+
+    pub fn submit_finder(&mut self) -> Result<()> {
+        let Some(path) = self
+            .finder_results
+            .get(self.finder_selected)
+            .map(|result| result.entry.path.clone())
+        else {
+            self.set_status_warn(format!("No file matches `{}`", self.finder_query));
+            return Ok(());
+        };
+
+        self.finder_open = false;
+        self.finder_query.clear();
+        self.finder_results.clear();
+        self.finder_selected = 0;
+
+        if !self.tree_files.iter().any(|entry| entry.path == path) {
+            self.tree_filter.clear();
+            self.apply_tree_filter(Some(path.clone()));
+        }
+
+        self.select_tree_path(&path)?;
+        self.set_status_info(format!("Jumped to {path}"));
+        Ok(())
+    }
+
+    pub fn finder_results(&self) -> &[FinderResult] {
+        &self.finder_results
+    }
+
+    pub fn open_tree_filter(&mut self) {
+        self.tree_filter_editing = true;
+        if self.has_sidebar() {
+            self.pane_focus = PaneFocus::Sidebar;
+        }
+        self.set_status_info("Filter sidebar: type to narrow, Enter keep, Esc clear");
+    }
+
+    pub fn tree_filter_append(&mut self, ch: char) -> Result<()> {
+        self.tree_filter.push(ch);
+        self.reapply_tree_filter()
+    }
+
+    pub fn tree_filter_backspace(&mut self) -> Result<()> {
+        self.tree_filter.pop();
+        self.reapply_tree_filter()
+    }
+
+    pub fn submit_tree_filter(&mut self) {
+        self.tree_filter_editing = false;
+        if self.tree_filter_active() {
+            self.set_status_info(format!(
+                "Filter `{}`: {} of {} files",
+                self.tree_filter.trim(),
+                self.tree_files.len(),
+                self.change_files.len()
+            ));
+        } else {
+            self.tree_filter.clear();
+            self.set_status_info("Filter cleared");
+        }
+    }
+
+    pub fn clear_tree_filter(&mut self) -> Result<()> {
+        self.tree_filter_editing = false;
+        if self.tree_filter.is_empty() {
+            return Ok(());
+        }
+
+        self.tree_filter.clear();
+        self.reapply_tree_filter()?;
+        self.set_status_info("Filter cleared");
+        Ok(())
+    }
+
+    pub fn tree_filter_active(&self) -> bool {
+        !self.tree_filter.trim().is_empty()
+    }
+
+    pub fn tree_file_total(&self) -> usize {
+        self.change_files.len()
+    }
+
+    pub fn select_tree_path(&mut self, path: &str) -> Result<bool> {
+        self.reveal_tree_path(path);
+        let section = self.selected_tree_section();
+        let Some(idx) = self.tree_row_index(path, section.as_deref()) else {
+            return Ok(false);
+        };
+
+        self.tree_selected = Some(idx);
+        self.load_current_diff()?;
+        self.sync_scrolls();
+        Ok(true)
+    }
+
+    /// Selects `path` in the sidebar (clearing a filter that hides it) and
+    /// scrolls its diff to `line`.
+    pub fn jump_to_file_line(&mut self, path: &str, line: usize) -> Result<()> {
+        if !self.tree_files.iter().any(|entry| entry.path == path) {
+            self.tree_filter.clear();
+            self.apply_tree_filter(Some(path.to_owned()));
+        }
+
+        if !self.select_tree_path(path)? {
+            self.set_status_warn(format!("{path} is not in the sidebar"));
+            return Ok(());
+        }
+
+        self.pane_focus = PaneFocus::Diff;
+        if self.scroll_diff_to_line(line) {
+            self.set_status_info(format!("Jumped to {path}:{line}"));
+        } else {
+            self.set_status_info(format!(
+                "Jumped to {path}; line {line} is outside the changed hunks"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Maps a path printed by a tool (relative to the repo, `./`-prefixed,
+    /// absolute, or relative to a subdirectory) onto a changed file.
+    pub(super) fn resolve_changed_path(&self, raw: &str) -> Option<String> {
+        let root = self.repo_root.to_string_lossy();
+        let trimmed = raw
+            .strip_prefix(root.as_ref())
+            .map(|rest| rest.trim_start_matches('/'))
+            .unwrap_or(raw);
+        let trimmed = trimmed.trim_start_matches("./");
+
+        if let Some(entry) = self.change_files.iter().find(|entry| entry.path == trimmed) {
+            return Some(entry.path.clone());
+        }
+
+        let suffix = format!("/{trimmed}");
+        self.change_files
+            .iter()
+            .find(|entry| entry.path.ends_with(&suffix))
+            .map(|entry| entry.path.clone())
+    }
+
+    pub(super) fn filtered_tree_files(&self) -> Vec<TreeFileEntry> {
+        if !self.tree_filter_active() {
+            return self.change_files.clone();
+        }
+
+        self.change_files
+            .iter()
+            .filter(|entry| fuzzy::fuzzy_match(&self.tree_filter, &entry.path).is_some())
+            .cloned()
+            .collect()
+    }
+
+    pub(super) fn reapply_tree_filter(&mut self) -> Result<()> {
+        let previous = self.selected_tree_path().map(ToOwned::to_owned);
+        if let Some(path) = previous.as_deref() {
+            self.reveal_tree_path(path);
+        }
+        self.apply_tree_filter(previous.clone());
+        self.normalize_focus();
+        if self.selected_tree_path().map(ToOwned::to_owned) != previous {
+            self.load_current_diff()?;
+        }
+        self.sync_scrolls();
+        Ok(())
+    }
+
+    fn update_finder_results(&mut self) {
+        let ranked = fuzzy::rank(
+            &self.finder_query,
+            self.change_files.iter().map(|entry| entry.path.as_str()),
+        );
+
+        self.finder_results = ranked
+            .into_iter()
+            .map(|(idx, found)| FinderResult {
+                entry: self.change_files[idx].clone(),
+                positions: found.positions,
+            })
+            .collect();
+        self.finder_selected = self
+            .finder_selected
+            .min(self.finder_results.len().saturating_sub(1));
+    }
+}
@@ -0,0 +1,316 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::host;
+use crate::settings::{
+    self, AUTO_SPLIT_MIN_WIDTH_MAX, AUTO_SPLIT_MIN_WIDTH_MIN, SIDEBAR_WIDTH_MAX, SIDEBAR_WIDTH_MIN,
+    TERMINAL_DOCK_SIZE_MAX, TERMINAL_DOCK_SIZE_MIN, ThemeMode,
+};
+
+use super::App;
+use super::util::shift_and_clamp_u16;
+
+const SETTINGS_FIELD_COUNT: usize = 14;
+const SETTINGS_WRITE_DEBOUNCE: Duration = Duration::from_millis(400);
+
+impl App {
+    pub fn toggle_settings_panel(&mut self) {
+        self.help_open = false;
+        self.hide_terminal_panel();
+        self.git_panel_open = false;
+        self.reset_git_panel_state();
+        self.settings_open = !self.settings_open;
+        if self.settings_open {
+            self.set_status_info("Settings open");
+        } else {
+            self.finish_close_settings_panel();
+        }
+    }
+
+    pub fn close_settings_panel(&mut self) {
+        if self.settings_open {
+            self.settings_open = false;
+            self.finish_close_settings_panel();
+        }
+    }
+
+    pub fn move_settings_selection(&mut self, delta: isize) {
+        if SETTINGS_FIELD_COUNT == 0 {
+            self.settings_selected = 0;
+            return;
+        }
+
+        let current = self.settings_selected.min(SETTINGS_FIELD_COUNT - 1);
+        self.settings_selected = if delta < 0 {
+            current.saturating_sub(delta.unsigned_abs())
+        } else {
+            (current + delta as usize).min(SETTINGS_FIELD_COUNT - 1)
+        };
+    }
+
+    pub fn adjust_selected_setting(&mut self, delta: isize) -> Result<()> {
+        match self.settings_selected {
+            0 => {
+                self.settings.diff_view_mode = self.settings.diff_view_mode.cycle(delta);
+                self.mark_settings_dirty();
+                self.set_status_info(format!(
+                    "Diff view: {}",
+                    self.settings.diff_view_mode.label()
+                ));
+            }
+            1 => {
+                self.settings.sidebar_visible = !self.settings.sidebar_visible;
+                self.mark_settings_dirty();
+                self.set_status_info(if self.settings.sidebar_visible {
+                    String::from("Sidebar shown")
+                } else {
+                    String::from("Sidebar hidden")
+                });
+            }
+            2 => {
+                self.settings.sidebar_position = self.settings.sidebar_position.cycle(delta);
+                self.mark_settings_dirty();
+                self.set_status_info(format!(
+                    "Sidebar side: {}",
+                    self.settings.sidebar_position.label()
+                ));
+            }
+            3 => {
+                self.settings.sidebar_width = shift_and_clamp_u16(
+                    self.settings.sidebar_width,
+                    delta,
+                    2,
+                    SIDEBAR_WIDTH_MIN,
+                    SIDEBAR_WIDTH_MAX,
+                );
+                self.mark_settings_dirty();
+                self.set_status_info(format!("Sidebar width: {}", self.settings.sidebar_width));
+            }
+            4 => {
+                self.settings.auto_split_min_width = shift_and_clamp_u16(
+                    self.settings.auto_split_min_width,
+                    delta,
+                    4,
+                    AUTO_SPLIT_MIN_WIDTH_MIN,
+                    AUTO_SPLIT_MIN_WIDTH_MAX,
+                );
+                self.mark_settings_dirty();
+                self.set_status_info(format!(
+                    "Auto split min width: {}",
+                    self.settings.auto_split_min_width
+                ));
+            }
+            5 => {
+                self.settings.cycle_theme(delta);
+                self.mark_settings_dirty();
+                self.set_status_info(format!("Theme: {}", self.settings.theme_label()));
+            }
+            6 => {
+                self.settings.confirm_undo_to_mainline = !self.settings.confirm_undo_to_mainline;
+                self.mark_settings_dirty();
+                self.set_status_info(if self.settings.confirm_undo_to_mainline {
+                    String::from("Undo confirmation: enabled")
+                } else {
+                    String::from("Undo confirmation: disabled")
+                });
+            }
+            7 => {
+                self.settings.sidebar_sort = self.settings.sidebar_sort.cycle(delta);
+                self.mark_settings_dirty();
+                self.reorder_tree_files()?;
+                self.set_status_info(format!(
+                    "Sidebar sort: {}",
+                    self.settings.sidebar_sort.label()
+                ));
+            }
+            8 => {
+                self.settings.sidebar_grouping = self.settings.sidebar_grouping.cycle(delta);
+                self.mark_settings_dirty();
+                self.reorder_tree_files()?;
+                self.set_status_info(format!(
+                    "Sidebar grouping: {}",
+                    self.settings.sidebar_grouping.label()
+                ));
+            }
+            9 => {
+                self.settings.terminal_dock = self.settings.terminal_dock.cycle(delta);
+                self.mark_settings_dirty();
+                if !self.settings.terminal_dock.is_docked() {
+                    self.close_terminal();
+                }
+                self.set_status_info(format!(
+                    "Terminal dock: {}",
+                    self.settings.terminal_dock.label()
+                ));
+            }
+            10 => {
+                self.settings.terminal_dock_size = shift_and_clamp_u16(
+                    self.settings.terminal_dock_size,
+                    delta,
+                    5,
+                    TERMINAL_DOCK_SIZE_MIN,
+                    TERMINAL_DOCK_SIZE_MAX,
+                );
+                self.mark_settings_dirty();
+                self.set_status_info(format!(
+                    "Terminal dock size: {}%",
+                    self.settings.terminal_dock_size
+                ));
+            }
+            11 => {
+                self.settings.host_notify = self.settings.host_notify.cycle(delta);
+                self.mark_settings_dirty();
+                self.set_status_info(format!(
+                    "Notify host terminal: {}",
+                    self.settings.host_notify.label()
+                ));
+            }
+            12 => {
+                self.settings.theme_mode = self.settings.theme_mode.cycle(delta);
+                self.mark_settings_dirty();
+                // Asking the terminal now would race the event loop for
+                // input, so a background unknown since startup comes from
+                // `$COLORFGBG` or stays unknown.
+                if self.settings.theme_mode == ThemeMode::Auto && self.host_background.is_none() {
+                    self.host_background = host::env_background();
+                }
+                self.set_status_info(format!("Theme mode: {}", self.theme_mode_label()));
+            }
+            13 => self.toggle_wrap_lines(),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn set_host_background(&mut self, background: Option<(u8, u8, u8)>) {
+        self.host_background = background;
+    }
+
+    /// Whether to draw with the light palette and syntax theme.
+    pub fn light_theme(&self) -> bool {
+        match self.settings.theme_mode {
+            ThemeMode::Dark => false,
+            ThemeMode::Light => true,
+            ThemeMode::Auto => self.host_background.is_some_and(host::is_light),
+        }
+    }
+
+    fn theme_mode_label(&self) -> String {
+        match (self.settings.theme_mode, self.host_background) {
+            (ThemeMode::Auto, Some(_)) => {
+                format!(
+                    "Auto ({})",
+                    if self.light_theme() { "Light" } else { "Dark" }
+                )
+            }
+            (ThemeMode::Auto, None) => String::from("Auto (unknown, Dark)"),
+            (mode, _) => mode.label().to_owned(),
+        }
+    }
+
+    pub fn settings_rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "Diff Layout",
+                self.settings.diff_view_mode.label().to_owned(),
+            ),
+            (
+                "Sidebar Visible",
+                yes_no_label(self.settings.sidebar_visible).to_owned(),
+            ),
+            (
+                "Sidebar Side",
+                self.settings.sidebar_position.label().to_owned(),
+            ),
+            ("Sidebar Width", self.settings.sidebar_width.to_string()),
+            (
+                "Auto Split Min Width",
+                self.settings.auto_split_min_width.to_string(),
+            ),
+            ("Theme", self.settings.theme_label()),
+            (
+                "Confirm Undo",
+                yes_no_label(self.settings.confirm_undo_to_mainline).to_owned(),
+            ),
+            (
+                "Sidebar Sort",
+                self.settings.sidebar_sort.label().to_owned(),
+            ),
+            (
+                "Sidebar Grouping",
+                self.settings.sidebar_grouping.label().to_owned(),
+            ),
+            (
+                "Terminal Dock",
+                self.settings.terminal_dock.label().to_owned(),
+            ),
+            (
+                "Terminal Dock Size",
+                format!("{}%", self.settings.terminal_dock_size),
+            ),
+            (
+                "Notify Host Terminal",
+                self.settings.host_notify.label().to_owned(),
+            ),
+            ("Theme Mode", self.theme_mode_label()),
+            (
+                "Wrap Lines",
+                yes_no_label(self.settings.wrap_lines).to_owned(),
+            ),
+        ]
+    }
+
+    pub fn flush_pending_settings(&mut self) -> Result<()> {
+        self.flush_settings_if_dirty()
+    }
+
+    pub(super) fn mark_settings_dirty(&mut self) {
+        self.settings.normalize();
+        self.settings_dirty = true;
+        self.last_settings_change = Some(Instant::now());
+    }
+
+    pub(super) fn flush_settings_if_due(&mut self) -> Result<bool> {
+        if !self.settings_dirty {
+            return Ok(false);
+        }
+
+        let Some(changed_at) = self.last_settings_change else {
+            return Ok(false);
+        };
+
+        if changed_at.elapsed() < SETTINGS_WRITE_DEBOUNCE {
+            return Ok(false);
+        }
+
+        settings::save(&self.settings)?;
+        self.settings_dirty = false;
+        self.last_settings_change = None;
+        Ok(true)
+    }
+
+    pub(super) fn flush_settings_if_dirty(&mut self) -> Result<()> {
+        if !self.settings_dirty {
+            return Ok(());
+        }
+
+        settings::save(&self.settings)?;
+        self.settings_dirty = false;
+        self.last_settings_change = None;
+        Ok(())
+    }
+
+    fn finish_close_settings_panel(&mut self) {
+        if let Err(error) = self.flush_settings_if_dirty() {
+            self.set_status_error(error);
+            return;
+        }
+        self.set_status_info("Settings closed");
+    }
+}
+
+fn yes_no_label(value: bool) -> &'static str {
+    if value { "Yes" } else { "No" }
+}
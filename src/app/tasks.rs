@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+
+use crate::keymap::Action;
+use crate::settings;
+
+use super::App;
+
+/// How the most recent run of a task went, as shown in the picker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskRunState {
+    Running,
+    Passed,
+    Failed,
+}
+
+impl TaskRunState {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Which config file a task was defined in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskSource {
+    Global,
+    Repo,
+}
+
+impl TaskSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Repo => "repo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskEntry {
+    pub name: String,
+    pub command: String,
+    pub source: TaskSource,
+}
+
+impl App {
+    /// Configured tasks sorted by name. The repository's `.dif.toml` only
+    /// contributes once the repo is trusted, and then wins over the global
+    /// config when both define the same name.
+    pub fn tasks(&self) -> Vec<TaskEntry> {
+        let mut tasks = self
+            .settings
+            .tasks
+            .iter()
+            .map(|(name, command)| (name.clone(), (command.clone(), TaskSource::Global)))
+            .collect::<BTreeMap<_, _>>();
+        if self.repo_config_trusted() {
+            tasks.extend(
+                self.repo_config
+                    .tasks
+                    .iter()
+                    .map(|(name, command)| (name.clone(), (command.clone(), TaskSource::Repo))),
+            );
+        }
+        tasks
+            .into_iter()
+            .map(|(name, (command, source))| TaskEntry {
+                name,
+                command,
+                source,
+            })
+            .collect()
+    }
+
+    pub fn task_run_state(&self, name: &str) -> Option<TaskRunState> {
+        let tab = self
+            .terminal_tabs
+            .iter()
+            .find(|tab| tab.task_name() == Some(name))?;
+        if tab.is_running() {
+            return Some(TaskRunState::Running);
+        }
+        match tab.exit_success() {
+            Some(true) => Some(TaskRunState::Passed),
+            Some(false) => Some(TaskRunState::Failed),
+            None => None,
+        }
+    }
+
+    pub fn open_task_picker(&mut self) {
+        if self.tasks().is_empty() {
+            if !self.repo_config.tasks.is_empty() {
+                self.set_status_warn(format!(
+                    "{} defines tasks, but this repo is not trusted; {} to trust it",
+                    settings::REPO_CONFIG_FILE,
+                    self.keymap.key(Action::TrustRepoConfig)
+                ));
+            } else {
+                self.set_status_warn(
+                    "No tasks configured; add a [tasks] table to config.toml or .dif.toml",
+                );
+            }
+            return;
+        }
+
+        self.settings_open = false;
+        self.help_open = false;
+        self.task_picker_open = true;
+        self.task_picker_selected = 0;
+        self.set_status_info("Run task: pick one and press Enter");
+    }
+
+    pub fn close_task_picker(&mut self) {
+        if self.task_picker_open {
+            self.task_picker_open = false;
+            self.set_status_info("Task picker closed");
+        }
+    }
+
+    pub fn move_task_picker_selection(&mut self, delta: isize) {
+        let len = self.tasks().len();
+        if len == 0 {
+            self.task_picker_selected = 0;
+            return;
+        }
+
+        let current = self.task_picker_selected.min(len - 1);
+        self.task_picker_selected = if delta < 0 {
+            current.saturating_sub(delta.unsigned_abs())
+        } else {
+            (current + delta as usize).min(len - 1)
+        };
+    }
+
+    pub fn run_selected_task(&mut self) -> Result<()> {
+        self.run_task_at(self.task_picker_selected)
+    }
+
+    pub fn run_task_at(&mut self, idx: usize) -> Result<()> {
+        let Some(task) = self.tasks().into_iter().nth(idx) else {
+            bail!("no task at position {}", idx + 1);
+        };
+
+        self.task_picker_open = false;
+        self.launch_task_tab(&task.name, &task.command)
+    }
+}
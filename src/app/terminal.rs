@@ -0,0 +1,513 @@
+use std::time::Instant;
+
+use anyhow::{Context, Result, anyhow};
+use crossterm::event::KeyEvent;
+use regex::Regex;
+
+use crate::keymap::Action;
+use crate::settings::ShellSettings;
+use crate::terminal::{self, RowText, TerminalSession};
+
+use super::util::contains;
+use super::{App, PaneFocus};
+
+mod export;
+mod notify;
+mod refs;
+mod search;
+mod selection;
+mod tabs;
+
+pub use refs::TerminalLinkSpan;
+pub use search::TerminalSearchState;
+
+const DEFAULT_TAB_NAME: &str = "shell";
+const DEFAULT_TERMINAL_ROWS: u16 = 24;
+const DEFAULT_TERMINAL_COLS: u16 = 80;
+
+/// Why a tab that is out of sight wants to be looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabAttention {
+    Bell,
+    Finished,
+    Failed,
+}
+
+/// One named PTY session plus the scrollback and copy-mode state that
+/// belongs to it, so switching tabs keeps each tab where it was left.
+pub struct TerminalTab {
+    pub name: String,
+    task: Option<String>,
+    /// The tmux session backing this tab when shells persist across restarts.
+    persistent: Option<String>,
+    session: Option<TerminalSession>,
+    scrollback: usize,
+    copy_mode: bool,
+    search_open: bool,
+    search_query: String,
+    last_search: String,
+    search_backward: bool,
+    search_regex: bool,
+    search_case_sensitive: bool,
+    /// Compiled from the prompt while typing, otherwise from `last_search`.
+    search_pattern: Option<Regex>,
+    search_error: Option<String>,
+    search_position: Option<(usize, usize)>,
+    cursor_row: usize,
+    cursor_col: usize,
+    selection_anchor: Option<(usize, usize)>,
+    bells_seen: usize,
+    attention: Option<TabAttention>,
+    mouse_press: Option<(usize, usize)>,
+    last_click: Option<(Instant, usize, usize)>,
+    /// Copy mode was entered by a mouse drag and ends with the button release.
+    mouse_copy: bool,
+}
+
+impl TerminalTab {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            task: None,
+            persistent: None,
+            session: None,
+            scrollback: 0,
+            copy_mode: false,
+            search_open: false,
+            search_query: String::new(),
+            last_search: String::new(),
+            search_backward: false,
+            search_regex: false,
+            search_case_sensitive: false,
+            search_pattern: None,
+            search_error: None,
+            search_position: None,
+            cursor_row: 0,
+            cursor_col: 0,
+            selection_anchor: None,
+            bells_seen: 0,
+            attention: None,
+            mouse_press: None,
+            last_click: None,
+            mouse_copy: false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.session
+            .as_ref()
+            .is_some_and(|session| !session.is_exited())
+    }
+
+    /// The configured task this tab runs, if it is not an interactive shell.
+    pub fn task_name(&self) -> Option<&str> {
+        self.task.as_deref()
+    }
+
+    /// `Some(true)` once the session exited cleanly, `Some(false)` when it
+    /// failed, `None` while it is still running.
+    pub fn exit_success(&self) -> Option<bool> {
+        self.session
+            .as_ref()
+            .and_then(TerminalSession::exit_success)
+    }
+
+    /// Set while the tab rang the bell or exited out of sight; cleared once
+    /// it is shown.
+    pub fn attention(&self) -> Option<TabAttention> {
+        self.attention
+    }
+
+    fn finished_task(&self) -> Option<&str> {
+        self.task_name().filter(|_| !self.is_running())
+    }
+
+    fn close_search(&mut self) {
+        self.search_open = false;
+        self.search_query.clear();
+    }
+
+    fn reset_navigation(&mut self) {
+        self.scrollback = 0;
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+    }
+}
+
+impl App {
+    pub fn open_terminal(&mut self) -> Result<()> {
+        self.settings_open = false;
+        self.help_open = false;
+        self.git_panel_open = false;
+        self.reset_git_panel_state();
+        self.hide_terminal_panel();
+        self.terminal_open = true;
+        if self.settings.terminal_dock.is_docked() {
+            self.pane_focus = PaneFocus::Terminal;
+        }
+
+        if self.terminal_tabs.is_empty() {
+            self.terminal_tabs
+                .push(TerminalTab::new(DEFAULT_TAB_NAME.to_owned()));
+            self.terminal_active = 0;
+        }
+
+        let tab = self.active_terminal_tab_mut();
+        tab.copy_mode = false;
+        tab.selection_anchor = None;
+        tab.reset_navigation();
+
+        let session = self.ensure_live_terminal_session()?;
+        session.set_scrollback(0);
+        session.pump_output();
+        let scrollback = session.scrollback();
+        self.active_terminal_tab_mut().scrollback = scrollback;
+
+        self.set_status_info(format!(
+            "Terminal open (interactive): {}",
+            self.terminal_tab_summary()
+        ));
+        Ok(())
+    }
+
+    /// Runs `command` for the task `name` in its own tab, reusing the tab from
+    /// a previous run, and shows the terminal.
+    pub(super) fn launch_task_tab(&mut self, name: &str, command: &str) -> Result<()> {
+        let existing = self
+            .terminal_tabs
+            .iter()
+            .position(|tab| tab.task_name() == Some(name));
+        if let Some(idx) = existing
+            && self.terminal_tabs[idx].is_running()
+        {
+            self.terminal_active = idx;
+            self.open_terminal()?;
+            self.set_status_warn(format!("Task `{name}` is already running"));
+            return Ok(());
+        }
+
+        let idx = existing.unwrap_or_else(|| {
+            let mut tab = TerminalTab::new(name.to_owned());
+            tab.task = Some(name.to_owned());
+            self.terminal_tabs.push(tab);
+            self.terminal_tabs.len() - 1
+        });
+        let shell = terminal::task_shell(&self.effective_shell(), command);
+        let session = self
+            .start_terminal_session(name, &shell)
+            .with_context(|| format!("failed to start task `{name}`"))?;
+
+        let tab = &mut self.terminal_tabs[idx];
+        tab.session = Some(session);
+        tab.reset_navigation();
+        self.terminal_active = idx;
+        self.open_terminal()?;
+        self.set_status_info(format!("Running task `{name}`: {command}"));
+        Ok(())
+    }
+
+    /// Hides the terminal overlay or docked pane; every tab keeps running in
+    /// the background.
+    pub fn close_terminal(&mut self) {
+        if self.terminal_open {
+            self.hide_terminal_panel();
+            self.terminal_open = false;
+            self.terminal_rename_open = false;
+            self.terminal_rename_input.clear();
+            self.terminal_view_rows = 0;
+            self.terminal_view_cols = 0;
+
+            let running = self
+                .terminal_tabs
+                .iter()
+                .filter(|tab| tab.is_running())
+                .count();
+            if running == 0 {
+                self.set_status_info("Terminal closed");
+            } else {
+                self.set_status_info(format!(
+                    "Terminal hidden; {running} tab{} still running",
+                    if running == 1 { "" } else { "s" }
+                ));
+            }
+            if let Err(error) = self.refresh() {
+                self.set_status_error(error);
+            }
+        }
+    }
+
+    /// Leaves the terminal with the close chord: the overlay is hidden, while a
+    /// docked pane stays on screen and hands keyboard focus back to the diff.
+    pub fn leave_terminal(&mut self) {
+        if !self.settings.terminal_dock.is_docked() {
+            self.close_terminal();
+            return;
+        }
+
+        self.hide_terminal_panel();
+        self.set_status_info(format!(
+            "Terminal unfocused; Tab or {} to return, {} to hide",
+            self.keymap.key(Action::OpenTerminal),
+            self.keymap.key(Action::ToggleTerminalPane)
+        ));
+    }
+
+    pub fn toggle_terminal_pane(&mut self) -> Result<()> {
+        if self.terminal_open {
+            self.close_terminal();
+            Ok(())
+        } else {
+            self.open_terminal()
+        }
+    }
+
+    /// True when the terminal covers the screen as a popup rather than
+    /// sharing it as a docked pane.
+    pub fn terminal_overlay_open(&self) -> bool {
+        self.terminal_open && !self.settings.terminal_dock.is_docked()
+    }
+
+    /// True when keys and pastes should go to the terminal.
+    pub fn terminal_has_focus(&self) -> bool {
+        self.terminal_overlay_open()
+            || (self.terminal_open && self.pane_focus == PaneFocus::Terminal)
+    }
+
+    pub fn is_in_terminal_dock(&self, column: u16, row: u16) -> bool {
+        contains(self.layout.terminal_dock, column, row)
+    }
+
+    /// The shell configuration new tabs launch with: the global `[shell]`
+    /// settings with the repository's `.dif.toml` layered on top. Until the
+    /// repo is trusted only its `persist` choice is taken.
+    pub fn effective_shell(&self) -> ShellSettings {
+        if self.repo_config_trusted() {
+            self.settings.shell.overlay(&self.repo_config.shell)
+        } else {
+            self.settings
+                .shell
+                .overlay(&self.repo_config.shell.without_commands())
+        }
+    }
+
+    pub fn terminal_send_key(&mut self, key: KeyEvent) -> Result<()> {
+        self.with_live_terminal_session(|session| session.send_key(key))
+    }
+
+    pub fn terminal_send_text(&mut self, text: &str) -> Result<()> {
+        self.with_live_terminal_session(|session| session.send_text(text))
+    }
+
+    pub fn terminal_resize(&mut self, rows: u16, cols: u16) -> Result<()> {
+        if let Some(session) = self.active_terminal_session_mut() {
+            session.resize(rows, cols)?;
+        }
+        Ok(())
+    }
+
+    pub fn terminal_screen(&self) -> Option<&vt100::Screen> {
+        self.active_terminal_tab()
+            .and_then(|tab| tab.session.as_ref())
+            .map(TerminalSession::screen)
+    }
+
+    pub fn scroll_terminal(&mut self, delta: isize) {
+        let max_row = self.terminal_view_rows.saturating_sub(1);
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+        let Some(session) = tab.session.as_mut() else {
+            return;
+        };
+
+        let target = if delta < 0 {
+            tab.scrollback.saturating_sub(delta.unsigned_abs())
+        } else {
+            tab.scrollback.saturating_add(delta as usize)
+        };
+
+        session.set_scrollback(target);
+        tab.scrollback = session.scrollback();
+        tab.cursor_row = tab.cursor_row.min(max_row);
+    }
+
+    pub fn set_terminal_viewport(&mut self, rows: usize, cols: usize) -> Result<()> {
+        self.terminal_view_rows = rows;
+        self.terminal_view_cols = cols;
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.cursor_row = tab.cursor_row.min(rows.saturating_sub(1));
+            tab.cursor_col = tab.cursor_col.min(cols.saturating_sub(1));
+        }
+        self.terminal_resize(rows as u16, cols as u16)
+    }
+
+    /// Takes the terminal out of the way of another panel. A docked pane stays
+    /// visible and only loses focus.
+    pub(super) fn hide_terminal_panel(&mut self) {
+        if self.settings.terminal_dock.is_docked() {
+            if self.pane_focus == PaneFocus::Terminal {
+                self.pane_focus = PaneFocus::Diff;
+            }
+        } else {
+            self.terminal_open = false;
+        }
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.copy_mode = false;
+            tab.close_search();
+            tab.selection_anchor = None;
+        }
+    }
+
+    fn active_terminal_tab(&self) -> Option<&TerminalTab> {
+        self.terminal_tabs.get(self.terminal_active)
+    }
+
+    fn active_terminal_tab_mut(&mut self) -> &mut TerminalTab {
+        if self.terminal_tabs.is_empty() {
+            self.terminal_tabs
+                .push(TerminalTab::new(DEFAULT_TAB_NAME.to_owned()));
+            self.terminal_active = 0;
+        }
+        self.terminal_active = self.terminal_active.min(self.terminal_tabs.len() - 1);
+        &mut self.terminal_tabs[self.terminal_active]
+    }
+
+    fn active_terminal_session_mut(&mut self) -> Option<&mut TerminalSession> {
+        self.terminal_tabs
+            .get_mut(self.terminal_active)
+            .and_then(|tab| tab.session.as_mut())
+    }
+
+    fn terminal_tab_summary(&self) -> String {
+        let name = self
+            .active_terminal_tab()
+            .map_or("", |tab| tab.name.as_str());
+        format!(
+            "`{name}` ({}/{})",
+            self.terminal_active + 1,
+            self.terminal_tabs.len()
+        )
+    }
+
+    fn next_terminal_tab_name(&self) -> String {
+        (1..)
+            .map(|n| {
+                if n == 1 {
+                    DEFAULT_TAB_NAME.to_owned()
+                } else {
+                    format!("{DEFAULT_TAB_NAME}-{n}")
+                }
+            })
+            .find(|name| !self.terminal_tabs.iter().any(|tab| &tab.name == name))
+            .unwrap_or_else(|| DEFAULT_TAB_NAME.to_owned())
+    }
+
+    /// The visible rows, with the screen column of every char.
+    fn terminal_rows_snapshot(&self) -> Vec<RowText> {
+        let Some(screen) = self.terminal_screen() else {
+            return Vec::new();
+        };
+
+        (0..usize::from(screen.size().0))
+            .map(|row| terminal::row_text(screen, row))
+            .collect()
+    }
+
+    fn terminal_spawn_size(&self) -> (u16, u16) {
+        let rows = match self.terminal_view_rows {
+            0 => DEFAULT_TERMINAL_ROWS,
+            rows => rows as u16,
+        };
+        let cols = match self.terminal_view_cols {
+            0 => DEFAULT_TERMINAL_COLS,
+            cols => cols as u16,
+        };
+        (rows, cols)
+    }
+
+    fn ensure_live_terminal_session(&mut self) -> Result<&mut TerminalSession> {
+        let mut shell = self.effective_shell();
+        let prefix = terminal::persistent_session_prefix(&self.repo_root);
+        let repo_root = self.repo_root.clone();
+        let tab = self.active_terminal_tab_mut();
+
+        // A finished task keeps its session so its output stays readable.
+        if tab.session.is_none() || (!tab.is_running() && tab.task.is_none()) {
+            let name = tab.name.clone();
+            if tab.task.is_none() && shell.persist == Some(true) {
+                let session = tab
+                    .persistent
+                    .get_or_insert_with(|| format!("{prefix}{}", terminal::tmux_safe(&name)))
+                    .clone();
+                let exists = terminal::list_persistent_sessions(&session).contains(&session);
+                shell = terminal::persistent_shell(&shell, &session, &repo_root, exists);
+            }
+
+            let session = self.start_terminal_session(&name, &shell)?;
+            let tab = self.active_terminal_tab_mut();
+            tab.session = Some(session);
+            tab.scrollback = 0;
+        }
+
+        self.active_terminal_tab_mut()
+            .session
+            .as_mut()
+            .ok_or_else(|| anyhow!("terminal session should exist after initialization"))
+    }
+
+    /// Recreates a tab for every tmux session a previous run left behind;
+    /// each attaches when it is first shown. Returns how many were found.
+    pub(super) fn restore_persistent_terminal_tabs(&mut self) -> usize {
+        if self.effective_shell().persist != Some(true) {
+            return 0;
+        }
+
+        let prefix = terminal::persistent_session_prefix(&self.repo_root);
+        let sessions = terminal::list_persistent_sessions(&prefix);
+        for session in &sessions {
+            let mut tab = TerminalTab::new(session[prefix.len()..].to_owned());
+            tab.persistent = Some(session.clone());
+            self.terminal_tabs.push(tab);
+        }
+        sessions.len()
+    }
+
+    /// Spawns a PTY session for the tab `name`, logging it to the transcript
+    /// directory when session logging is on.
+    fn start_terminal_session(&self, name: &str, shell: &ShellSettings) -> Result<TerminalSession> {
+        let (rows, cols) = self.terminal_spawn_size();
+        let mut session = TerminalSession::start(&self.repo_root, rows, cols, shell)?;
+        if self.settings.kitty_keyboard {
+            session.enable_kitty_keyboard();
+        }
+        if self.settings.transcripts.log_sessions {
+            let path = self.transcript_path(name, "log")?;
+            session.start_log(&path)?;
+        }
+        Ok(session)
+    }
+
+    fn with_live_terminal_session<F>(&mut self, action: F) -> Result<()>
+    where
+        F: FnOnce(&mut TerminalSession) -> Result<()>,
+    {
+        if let Some(task) = self
+            .active_terminal_tab()
+            .and_then(TerminalTab::finished_task)
+        {
+            let message = format!(
+                "Task `{task}` has finished; {} runs it again",
+                self.keymap.key(Action::OpenTaskPicker)
+            );
+            self.set_status_info(message);
+            return Ok(());
+        }
+
+        let session = self.ensure_live_terminal_session()?;
+        action(session)?;
+        session.pump_output();
+        let scrollback = session.scrollback();
+        self.active_terminal_tab_mut().scrollback = scrollback;
+        Ok(())
+    }
+}
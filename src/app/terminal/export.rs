@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::app::App;
+use crate::terminal;
+
+impl App {
+    /// Saves the active tab's scrollback and screen to a new file in the
+    /// transcript directory, as plain text or with ANSI colors.
+    pub fn terminal_export_scrollback(&mut self, ansi: bool) -> Result<()> {
+        let Some(name) = self.active_terminal_tab().map(|tab| tab.name.clone()) else {
+            self.set_status_warn("No terminal tab to export");
+            return Ok(());
+        };
+        let Some(session) = self.active_terminal_session_mut() else {
+            self.set_status_warn("No terminal output to export");
+            return Ok(());
+        };
+
+        let transcript = session.transcript(ansi);
+        let path = self.transcript_path(&name, if ansi { "ansi" } else { "txt" })?;
+        terminal::create_private_file(&path)
+            .and_then(|mut file| file.write_all(transcript.as_bytes()))
+            .with_context(|| format!("failed to write transcript `{}`", path.display()))?;
+
+        self.set_status_info(format!(
+            "Saved {} lines to {}",
+            transcript.lines().count(),
+            path.display()
+        ));
+        Ok(())
+    }
+
+    /// A fresh file in the transcript directory named after the tab and the
+    /// current time. The default directory is created private to the user,
+    /// since transcripts often hold secrets.
+    pub(super) fn transcript_path(&self, name: &str, extension: &str) -> Result<PathBuf> {
+        let transcripts = &self.settings.transcripts;
+        let dir = transcripts.resolve_dir(&self.repo_root);
+        if transcripts.configured_dir(&self.repo_root).is_some() {
+            fs::create_dir_all(&dir).with_context(|| {
+                format!("failed to create transcript directory `{}`", dir.display())
+            })?;
+        } else {
+            terminal::ensure_private_dir(&dir)?;
+        }
+
+        let stem = name
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || ch == '-' {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis());
+        Ok(dir.join(format!("{stem}-{stamp}.{extension}")))
+    }
+}
@@ -0,0 +1,184 @@
+use crate::app::App;
+use crate::settings::HostNotify;
+
+use super::TabAttention;
+
+impl App {
+    /// Drains output and exit status from every tab; returns whether anything
+    /// visible changed. Tabs that exit or ring the bell while hidden are
+    /// flagged for the footer badge and, if enabled, the host terminal.
+    pub(in crate::app) fn pump_terminal_tabs(&mut self) -> bool {
+        let mut changed = false;
+        let mut messages = Vec::new();
+        let mut alerts = Vec::new();
+        let mut errors = Vec::new();
+        let mut failures = Vec::new();
+        let mut clipboard = None;
+        let multiple = self.terminal_tabs.len() > 1;
+        let visible = self.terminal_open.then_some(self.terminal_active);
+
+        for (idx, tab) in self.terminal_tabs.iter_mut().enumerate() {
+            let hidden = visible != Some(idx);
+            if !hidden && tab.attention.take().is_some() {
+                changed = true;
+            }
+            let Some(session) = tab.session.as_mut() else {
+                continue;
+            };
+
+            if session.pump_output() {
+                changed = true;
+            }
+
+            if let Some(text) = session.take_clipboard() {
+                clipboard = Some((tab.name.clone(), text));
+            }
+
+            let bells = session.bell_count();
+            if bells > tab.bells_seen {
+                tab.bells_seen = bells;
+                if hidden {
+                    tab.attention.get_or_insert(TabAttention::Bell);
+                    alerts.push(format!("Terminal `{}` rang the bell", tab.name));
+                    changed = true;
+                }
+            }
+
+            let ended = match session.poll_exit_message() {
+                Ok(Some(message)) if tab.task.is_some() => {
+                    if session.exit_success() == Some(false) {
+                        failures.push(format!("Task `{}` failed: {message}", tab.name));
+                        failures.last().cloned()
+                    } else {
+                        messages.push(format!("Task `{}` passed: {message}", tab.name));
+                        messages.last().cloned()
+                    }
+                }
+                Ok(Some(message)) if multiple => {
+                    messages.push(format!("Terminal `{}` ended: {message}", tab.name));
+                    messages.last().cloned()
+                }
+                Ok(Some(message)) => {
+                    messages.push(format!("Terminal session ended: {message}"));
+                    messages.last().cloned()
+                }
+                Ok(None) => None,
+                Err(error) => {
+                    errors.push(error.to_string());
+                    None
+                }
+            };
+            if let Some(message) = ended
+                && hidden
+            {
+                tab.attention = Some(if session.exit_success() == Some(false) {
+                    TabAttention::Failed
+                } else {
+                    TabAttention::Finished
+                });
+                alerts.push(message);
+            }
+
+            if tab.scrollback > 0 {
+                session.set_scrollback(tab.scrollback);
+                tab.scrollback = session.scrollback();
+                changed = true;
+            }
+        }
+
+        if let Some(sequence) = alerts
+            .last()
+            .and_then(|alert| host_notification(self.settings.host_notify, alert))
+        {
+            self.host_notifications.push(sequence);
+        }
+
+        // Applied from least to most severe, so the status line ends up
+        // showing the worst thing that happened this tick.
+        if let Some((name, text)) = clipboard {
+            match arboard::Clipboard::new().and_then(|mut board| board.set_text(text.clone())) {
+                Ok(()) => self.set_status_info(format!(
+                    "`{name}` copied {} chars to the clipboard",
+                    text.chars().count()
+                )),
+                Err(error) => errors.push(format!("failed to copy terminal clipboard: {error}")),
+            }
+            changed = true;
+        }
+
+        if let Some(message) = messages.pop() {
+            self.set_status_info(message);
+            changed = true;
+        }
+
+        // Out-of-sight events stand out from routine status updates.
+        if let Some(alert) = alerts.pop() {
+            self.set_status_warn(alert);
+            changed = true;
+        }
+
+        if let Some(failure) = failures.pop() {
+            self.set_status_error(failure);
+            changed = true;
+        }
+
+        if let Some(error) = errors.pop() {
+            self.set_status_error(error);
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Escape sequences queued for the host terminal since the last call.
+    pub fn take_host_notifications(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.host_notifications)
+    }
+
+    /// Names of hidden tabs that finished or rang the bell.
+    pub fn terminal_tabs_needing_attention(&self) -> Vec<(&str, TabAttention)> {
+        self.terminal_tabs
+            .iter()
+            .filter_map(|tab| {
+                tab.attention
+                    .map(|attention| (tab.name.as_str(), attention))
+            })
+            .collect()
+    }
+}
+
+/// The bytes that make the host terminal notify: a plain bell, or an OSC 9
+/// desktop notification carrying `message`.
+fn host_notification(mode: HostNotify, message: &str) -> Option<String> {
+    match mode {
+        HostNotify::Off => None,
+        HostNotify::Bell => Some(String::from("\x07")),
+        HostNotify::Osc9 => {
+            let text = message
+                .chars()
+                .filter(|ch| !ch.is_control())
+                .collect::<String>();
+            Some(format!("\x1b]9;dif: {text}\x07"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::HostNotify;
+
+    use super::host_notification;
+
+    #[test]
+    fn host_notification_follows_the_setting() {
+        assert_eq!(host_notification(HostNotify::Off, "done"), None);
+        assert_eq!(
+            host_notification(HostNotify::Bell, "done").as_deref(),
+            Some("\x07")
+        );
+        assert_eq!(
+            host_notification(HostNotify::Osc9, "Task `test` passed\x07").as_deref(),
+            Some("\x1b]9;dif: Task `test` passed\x07")
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+
+use crate::app::App;
+use crate::app::util::{FileRef, find_file_refs};
+use crate::keymap::Action;
+use crate::terminal::{self, RowText};
+
+/// Where an OSC 8 hyperlink sits on the visible terminal screen, in screen
+/// columns with `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalLinkSpan {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+    pub url: String,
+}
+
+impl App {
+    /// Jumps to the `path:line` reference under the copy cursor in the
+    /// sidebar and diff, else opens the web link under it, else jumps to the
+    /// first reference on its row.
+    pub fn terminal_open_reference(&mut self) -> Result<()> {
+        let (cursor_row, cursor_col) = self.terminal_cursor();
+        let rows = self.terminal_rows_snapshot();
+        let refs = rows
+            .get(cursor_row)
+            .map(|row| self.changed_file_refs(row))
+            .unwrap_or_default();
+        let under_cursor = refs
+            .iter()
+            .find(|(_, file_ref)| (file_ref.start..file_ref.end).contains(&cursor_col))
+            .cloned();
+
+        if under_cursor.is_none()
+            && let Some(link) = self
+                .terminal_link_spans()
+                .into_iter()
+                .find(|link| link.row == cursor_row && (link.start..link.end).contains(&cursor_col))
+        {
+            if !terminal::is_openable_url(&link.url) {
+                self.set_status_warn(format!(
+                    "Not opening {}: only http, https and mailto links open",
+                    link.url
+                ));
+                return Ok(());
+            }
+            terminal::open_url(&link.url)?;
+            self.set_status_info(format!("Opened {}", link.url));
+            return Ok(());
+        }
+
+        let Some((path, file_ref)) = under_cursor.or_else(|| refs.first().cloned()) else {
+            self.set_status_warn("No changed file:line reference on this row");
+            return Ok(());
+        };
+
+        self.hide_terminal_panel();
+        self.jump_to_file_line(&path, file_ref.line)
+    }
+
+    /// Moves the copy cursor to the next reference to a changed file,
+    /// wrapping around the visible screen.
+    pub fn terminal_next_file_ref(&mut self) {
+        let view_rows = self.terminal_view_rows;
+        let rows = self.terminal_rows_snapshot();
+        if rows.is_empty() {
+            self.set_status_warn("No terminal output to scan");
+            return;
+        }
+
+        let (cursor_row, cursor_col) = self.terminal_cursor();
+        let start_row = cursor_row.min(rows.len() - 1);
+        let found = (start_row..rows.len())
+            .chain(0..=start_row)
+            .enumerate()
+            .find_map(|(pass, row_idx)| {
+                self.changed_file_refs(&rows[row_idx])
+                    .into_iter()
+                    .find(|(_, file_ref)| {
+                        row_idx != start_row
+                            || (pass == 0 && file_ref.start > cursor_col)
+                            || (pass > 0 && file_ref.start <= cursor_col)
+                    })
+                    .map(|(path, file_ref)| (row_idx, path, file_ref))
+            });
+
+        let Some((row_idx, path, file_ref)) = found else {
+            self.set_status_warn("No references to changed files on screen");
+            return;
+        };
+
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.cursor_row = row_idx.min(view_rows.saturating_sub(1));
+            tab.cursor_col = file_ref.start;
+        }
+        self.set_status_info(format!(
+            "{path}:{}; {} opens it",
+            file_ref.line,
+            self.keymap.key(Action::TerminalOpenRef)
+        ));
+    }
+
+    /// Visible cells covered by OSC 8 hyperlinks in the active tab, taken
+    /// from where each link was printed; a link that wraps yields one span
+    /// per row.
+    pub fn terminal_link_spans(&self) -> Vec<TerminalLinkSpan> {
+        let Some(session) = self
+            .active_terminal_tab()
+            .and_then(|tab| tab.session.as_ref())
+        else {
+            return Vec::new();
+        };
+
+        let cols = usize::from(session.screen().size().1);
+        let mut spans = Vec::new();
+        for (first, last, link) in session.visible_hyperlinks() {
+            for row in first..=last {
+                let start = if row == first { link.start.1 } else { 0 };
+                let end = if row == last { link.end.1 } else { cols };
+                if start < end {
+                    spans.push(TerminalLinkSpan {
+                        row,
+                        start,
+                        end,
+                        url: link.url.clone(),
+                    });
+                }
+            }
+        }
+        spans
+    }
+
+    /// References on `row` that resolve to a changed file, placed in screen
+    /// columns like the copy cursor.
+    fn changed_file_refs(&self, row: &RowText) -> Vec<(String, FileRef)> {
+        screen_file_refs(row)
+            .into_iter()
+            .filter_map(|file_ref| {
+                self.resolve_changed_path(&file_ref.path)
+                    .map(|path| (path, file_ref))
+            })
+            .collect()
+    }
+}
+
+/// `path:line` references on `row` with `start` and `end` moved from char
+/// offsets to screen columns.
+fn screen_file_refs(row: &RowText) -> Vec<FileRef> {
+    find_file_refs(&row.text)
+        .into_iter()
+        .map(|file_ref| FileRef {
+            start: row.column(file_ref.start),
+            end: row.column(file_ref.end),
+            ..file_ref
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::terminal;
+
+    use super::screen_file_refs;
+
+    #[test]
+    fn file_refs_are_placed_on_screen_cells() {
+        let mut parser = vt100::Parser::new(2, 40, 0);
+        parser.process("エラー src/app.rs:20:5".as_bytes());
+        let row = terminal::row_text(parser.screen(), 0);
+
+        let refs = screen_file_refs(&row);
+        assert_eq!(refs.len(), 1);
+        assert_eq!((refs[0].start, refs[0].end), (7, 22));
+        assert_eq!((refs[0].line, refs[0].column), (20, Some(5)));
+    }
+}
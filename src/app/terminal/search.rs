@@ -0,0 +1,279 @@
+use regex::Regex;
+
+use crate::app::App;
+use crate::app::util::{build_search_pattern, find_pattern_matches};
+use crate::terminal::RowText;
+
+use super::TerminalTab;
+
+/// What the terminal frame shows about search: the prompt while typing, then
+/// the last query with its match counter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalSearchState {
+    pub editing: bool,
+    pub query: String,
+    pub backward: bool,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub error: Option<String>,
+    /// `(current, total)` after the last jump.
+    pub position: Option<(usize, usize)>,
+}
+
+impl TerminalTab {
+    fn compile_search(&mut self) {
+        let query = if self.search_open {
+            self.search_query.trim()
+        } else {
+            self.last_search.as_str()
+        };
+        self.search_position = None;
+        if query.is_empty() {
+            self.search_pattern = None;
+            self.search_error = None;
+            return;
+        }
+
+        match build_search_pattern(query, self.search_regex, self.search_case_sensitive) {
+            Ok(pattern) => {
+                self.search_pattern = Some(pattern);
+                self.search_error = None;
+            }
+            Err(error) => {
+                self.search_pattern = None;
+                self.search_error = Some(error.to_string());
+            }
+        }
+    }
+}
+
+impl App {
+    pub fn terminal_search_open(&self) -> bool {
+        self.active_terminal_tab()
+            .is_some_and(|tab| tab.search_open)
+    }
+
+    /// Opens the search prompt; `backward` makes Enter and `n` search up.
+    pub fn terminal_open_search(&mut self, backward: bool) {
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.search_open = true;
+            tab.search_backward = backward;
+            tab.search_query = tab.last_search.clone();
+            tab.search_error = None;
+        }
+    }
+
+    pub fn terminal_cancel_search(&mut self) {
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.close_search();
+            tab.compile_search();
+        }
+        self.set_status_info("Search cancelled");
+    }
+
+    pub fn terminal_search_append(&mut self, ch: char) {
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.search_query.push(ch);
+            tab.compile_search();
+        }
+    }
+
+    pub fn terminal_search_backspace(&mut self) {
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.search_query.pop();
+            tab.compile_search();
+        }
+    }
+
+    pub fn terminal_toggle_search_regex(&mut self) {
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.search_regex = !tab.search_regex;
+            tab.compile_search();
+        }
+    }
+
+    pub fn terminal_toggle_search_case(&mut self) {
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.search_case_sensitive = !tab.search_case_sensitive;
+            tab.compile_search();
+        }
+    }
+
+    /// Runs the typed query, or repeats the last one, in the search direction.
+    pub fn terminal_search_next(&mut self) {
+        self.run_terminal_search(false);
+    }
+
+    /// Repeats the last search against its direction (`N`).
+    pub fn terminal_search_prev(&mut self) {
+        self.run_terminal_search(true);
+    }
+
+    /// The prompt or last search of the active tab, for the frame to show.
+    pub fn terminal_search_state(&self) -> Option<TerminalSearchState> {
+        let tab = self.active_terminal_tab()?;
+        if !tab.search_open && tab.last_search.is_empty() {
+            return None;
+        }
+
+        Some(TerminalSearchState {
+            editing: tab.search_open,
+            query: if tab.search_open {
+                tab.search_query.clone()
+            } else {
+                tab.last_search.clone()
+            },
+            backward: tab.search_backward,
+            regex: tab.search_regex,
+            case_sensitive: tab.search_case_sensitive,
+            error: tab.search_error.clone(),
+            position: tab.search_position,
+        })
+    }
+
+    /// Visible cells matching the active search, as `(row, start, end)`
+    /// screen columns with `end` exclusive.
+    pub fn terminal_search_highlights(&self) -> Vec<(usize, usize, usize)> {
+        let Some(pattern) = self
+            .active_terminal_tab()
+            .filter(|tab| tab.copy_mode)
+            .and_then(|tab| tab.search_pattern.as_ref())
+        else {
+            return Vec::new();
+        };
+
+        self.terminal_rows_snapshot()
+            .iter()
+            .enumerate()
+            .flat_map(|(row, text)| {
+                cell_matches(text, pattern)
+                    .into_iter()
+                    .map(move |(start, end)| (row, start, end))
+            })
+            .collect()
+    }
+
+    /// Searches the whole scrollback, not just the screen, and scrolls the
+    /// match into view. `reverse` flips the prompt's direction.
+    fn run_terminal_search(&mut self, reverse: bool) {
+        let view_rows = self.terminal_view_rows.max(1);
+        let view_cols = self.terminal_view_cols;
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+
+        if tab.search_open {
+            let query = tab.search_query.trim().to_owned();
+            tab.close_search();
+            if query.is_empty() {
+                tab.compile_search();
+                self.set_status_warn("Search query is empty");
+                return;
+            }
+            tab.last_search = query;
+            tab.compile_search();
+        }
+
+        let query = tab.last_search.clone();
+        if query.is_empty() {
+            self.set_status_warn("Search query is empty");
+            return;
+        }
+        if let Some(error) = tab.search_error.clone() {
+            self.set_status_warn(format!("Invalid regex `{query}`: {error}"));
+            return;
+        }
+        let Some(pattern) = tab.search_pattern.clone() else {
+            return;
+        };
+        let Some(session) = tab.session.as_mut() else {
+            self.set_status_warn("No terminal output to search");
+            return;
+        };
+
+        let rows = session.all_rows();
+        let history = session.history_len();
+        let matches = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(line, text)| {
+                cell_matches(text, &pattern)
+                    .into_iter()
+                    .map(move |(start, _)| (line, start))
+            })
+            .collect::<Vec<_>>();
+
+        if matches.is_empty() {
+            tab.search_position = None;
+            self.set_status_warn(format!("No match for `{query}`"));
+            return;
+        }
+
+        let top = history - tab.scrollback.min(history);
+        let here = (top + tab.cursor_row, tab.cursor_col);
+        let backward = tab.search_backward != reverse;
+        let idx = if backward {
+            matches
+                .iter()
+                .rposition(|found| *found < here)
+                .unwrap_or(matches.len() - 1)
+        } else {
+            matches.iter().position(|found| *found > here).unwrap_or(0)
+        };
+        let (line, col) = matches[idx];
+
+        let top = if (top..top + view_rows).contains(&line) {
+            top
+        } else {
+            line.saturating_sub(view_rows / 2).min(history)
+        };
+        session.set_scrollback(history - top);
+        tab.scrollback = session.scrollback();
+        tab.cursor_row = line - top;
+        tab.cursor_col = col.min(view_cols.saturating_sub(1));
+        tab.search_position = Some((idx + 1, matches.len()));
+
+        let wrapped = if backward {
+            matches[idx] > here
+        } else {
+            matches[idx] < here
+        };
+        self.set_status_info(format!(
+            "Match {}/{} for `{query}`{}",
+            idx + 1,
+            matches.len(),
+            if wrapped { " (wrapped)" } else { "" }
+        ));
+    }
+}
+
+/// Matches of `pattern` in `row` as `(start, end)` screen columns, `end`
+/// exclusive, so wide characters before a match do not shift it.
+fn cell_matches(row: &RowText, pattern: &Regex) -> Vec<(usize, usize)> {
+    find_pattern_matches(&row.text, pattern)
+        .into_iter()
+        .map(|(start, end)| (row.column(start), row.column(end)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::util::build_search_pattern;
+    use crate::terminal;
+
+    use super::cell_matches;
+
+    #[test]
+    fn matches_land_on_screen_cells_after_wide_chars() {
+        let mut parser = vt100::Parser::new(2, 30, 0);
+        parser.process("日本語 error: 失敗 error".as_bytes());
+        let row = terminal::row_text(parser.screen(), 0);
+        let pattern = build_search_pattern("error", false, false).expect("literal query");
+
+        // Each CJK char takes two cells, so the first match starts at
+        // column 7 rather than char 4.
+        assert_eq!(cell_matches(&row, &pattern), vec![(7, 12), (19, 24)]);
+        let wide = build_search_pattern("失敗", false, false).expect("literal query");
+        assert_eq!(cell_matches(&row, &wide), vec![(14, 18)]);
+    }
+}
@@ -0,0 +1,260 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+use crate::app::util::{contains, order_positions};
+use crate::app::{App, PaneFocus};
+use crate::terminal::{self, TerminalSession};
+
+const MOUSE_SCROLL_STEP: isize = 3;
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+impl App {
+    pub fn terminal_copy_mode(&self) -> bool {
+        self.active_terminal_tab().is_some_and(|tab| tab.copy_mode)
+    }
+
+    pub fn terminal_enter_copy_mode(&mut self) {
+        let last_row = self.terminal_view_rows.saturating_sub(1);
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+        tab.copy_mode = true;
+        tab.mouse_copy = false;
+        tab.close_search();
+        tab.selection_anchor = None;
+        tab.cursor_row = last_row;
+        tab.cursor_col = 0;
+        self.set_status_info("Copy mode");
+    }
+
+    pub fn terminal_exit_copy_mode(&mut self) {
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+        if tab.copy_mode {
+            tab.copy_mode = false;
+            tab.mouse_copy = false;
+            tab.close_search();
+            tab.selection_anchor = None;
+            self.set_status_info("Terminal interactive mode");
+        }
+    }
+
+    pub fn terminal_move_cursor(&mut self, row_delta: isize, col_delta: isize) {
+        let max_row = self.terminal_view_rows.saturating_sub(1);
+        let max_col = self.terminal_view_cols.saturating_sub(1);
+
+        if row_delta < 0 {
+            for _ in 0..row_delta.unsigned_abs() {
+                let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+                    return;
+                };
+                if tab.cursor_row > 0 {
+                    tab.cursor_row -= 1;
+                } else {
+                    self.scroll_terminal(1);
+                }
+            }
+        } else {
+            for _ in 0..row_delta as usize {
+                let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+                    return;
+                };
+                if tab.cursor_row < max_row {
+                    tab.cursor_row += 1;
+                } else if tab.scrollback > 0 {
+                    self.scroll_terminal(-1);
+                }
+            }
+        }
+
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+        if col_delta < 0 {
+            tab.cursor_col = tab.cursor_col.saturating_sub(col_delta.unsigned_abs());
+        } else if col_delta > 0 {
+            tab.cursor_col = tab
+                .cursor_col
+                .saturating_add(col_delta as usize)
+                .min(max_col);
+        }
+    }
+
+    /// True when `mouse` belongs to the terminal: it is over the output area or
+    /// continues a press that started there.
+    pub fn terminal_wants_mouse(&self, mouse: &MouseEvent) -> bool {
+        self.terminal_open
+            && (contains(self.layout.terminal_output, mouse.column, mouse.row)
+                || self
+                    .active_terminal_tab()
+                    .is_some_and(|tab| tab.mouse_press.is_some()))
+    }
+
+    /// Mouse input over the terminal output. Programs that enabled mouse
+    /// reporting get the event (Shift bypasses them); otherwise the wheel
+    /// scrolls, dragging selects and copies, and a double click copies a word.
+    pub fn terminal_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        let area = self.layout.terminal_output;
+        if area.width == 0 || area.height == 0 {
+            return Ok(());
+        }
+        if self.settings.terminal_dock.is_docked() && matches!(mouse.kind, MouseEventKind::Down(_))
+        {
+            self.pane_focus = PaneFocus::Terminal;
+        }
+
+        let col = mouse.column.clamp(area.x, area.x + area.width - 1) - area.x;
+        let row = mouse.row.clamp(area.y, area.y + area.height - 1) - area.y;
+
+        let forward = !self.terminal_copy_mode()
+            && !mouse.modifiers.contains(KeyModifiers::SHIFT)
+            && self
+                .active_terminal_tab()
+                .and_then(|tab| tab.session.as_ref())
+                .is_some_and(TerminalSession::wants_mouse);
+        if forward {
+            return self.with_live_terminal_session(|session| session.send_mouse(mouse, col, row));
+        }
+
+        let pos = (row as usize, col as usize);
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll_terminal(MOUSE_SCROLL_STEP),
+            MouseEventKind::ScrollDown => self.scroll_terminal(-MOUSE_SCROLL_STEP),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let now = Instant::now();
+                let tab = self.active_terminal_tab_mut();
+                let double = tab.last_click.is_some_and(|(at, last_row, last_col)| {
+                    now.duration_since(at) <= DOUBLE_CLICK_INTERVAL && (last_row, last_col) == pos
+                });
+                if double {
+                    tab.last_click = None;
+                    return self.terminal_copy_word_at(pos);
+                }
+
+                tab.last_click = Some((now, pos.0, pos.1));
+                tab.mouse_press = Some(pos);
+                if tab.copy_mode {
+                    (tab.cursor_row, tab.cursor_col) = pos;
+                    tab.selection_anchor = None;
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let tab = self.active_terminal_tab_mut();
+                let Some(press) = tab.mouse_press else {
+                    return Ok(());
+                };
+                if !tab.copy_mode {
+                    tab.copy_mode = true;
+                    tab.mouse_copy = true;
+                    tab.close_search();
+                }
+                tab.selection_anchor.get_or_insert(press);
+                (tab.cursor_row, tab.cursor_col) = pos;
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                let tab = self.active_terminal_tab_mut();
+                if tab.mouse_press.take().is_some() && tab.selection_anchor.is_some() {
+                    self.finish_mouse_copy()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Copies the whitespace-delimited word at `pos`, as a double click does.
+    fn terminal_copy_word_at(&mut self, (row, col): (usize, usize)) -> Result<()> {
+        let Some((start, end)) = self
+            .terminal_screen()
+            .and_then(|screen| terminal::word_bounds(screen, row, col))
+        else {
+            return Ok(());
+        };
+
+        let tab = self.active_terminal_tab_mut();
+        tab.mouse_press = None;
+        if !tab.copy_mode {
+            tab.copy_mode = true;
+            tab.mouse_copy = true;
+        }
+        tab.selection_anchor = Some((row, start));
+        (tab.cursor_row, tab.cursor_col) = (row, end);
+        self.finish_mouse_copy()
+    }
+
+    /// Yanks the mouse selection and drops back to the shell when the mouse
+    /// was what entered copy mode.
+    fn finish_mouse_copy(&mut self) -> Result<()> {
+        let result = self.terminal_yank_selection();
+        let tab = self.active_terminal_tab_mut();
+        if tab.mouse_copy {
+            tab.copy_mode = false;
+            tab.mouse_copy = false;
+            tab.selection_anchor = None;
+        }
+        result
+    }
+
+    pub fn terminal_toggle_selection_anchor(&mut self) {
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+        if tab.selection_anchor.is_some() {
+            tab.selection_anchor = None;
+            self.set_status_info("Selection cleared");
+        } else {
+            tab.selection_anchor = Some((tab.cursor_row, tab.cursor_col));
+            self.set_status_info("Selection anchor set");
+        }
+    }
+
+    pub fn terminal_yank_selection(&mut self) -> Result<()> {
+        let Some(((start_row, start_col), (end_row, end_col))) = self.terminal_selection_bounds()
+        else {
+            self.set_status_warn("No selection anchor; press v first");
+            return Ok(());
+        };
+
+        let Some(screen) = self.terminal_screen() else {
+            self.set_status_warn("Nothing to copy");
+            return Ok(());
+        };
+
+        let out = terminal::selection_text(screen, (start_row, start_col), (end_row, end_col));
+        if out.is_empty() {
+            self.set_status_warn("Selection is empty");
+            return Ok(());
+        }
+
+        let mut clipboard =
+            arboard::Clipboard::new().context("failed to access system clipboard")?;
+        clipboard
+            .set_text(out.clone())
+            .context("failed to copy selection to clipboard")?;
+
+        self.set_status_info(format!("Copied {} chars", out.chars().count()));
+        if let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) {
+            tab.selection_anchor = None;
+        }
+        Ok(())
+    }
+
+    pub fn terminal_cursor(&self) -> (usize, usize) {
+        self.active_terminal_tab()
+            .map_or((0, 0), |tab| (tab.cursor_row, tab.cursor_col))
+    }
+
+    pub fn terminal_selection_rows(&self) -> Option<(usize, usize)> {
+        self.terminal_selection_bounds()
+            .map(|((start_row, _), (end_row, _))| (start_row, end_row))
+    }
+
+    fn terminal_selection_bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let tab = self.active_terminal_tab()?;
+        tab.selection_anchor
+            .map(|anchor| order_positions(anchor, (tab.cursor_row, tab.cursor_col)))
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+
+use crate::app::App;
+use crate::terminal;
+
+use super::TerminalTab;
+
+impl App {
+    pub fn new_terminal_tab(&mut self) -> Result<()> {
+        let name = self.next_terminal_tab_name();
+        self.terminal_tabs.push(TerminalTab::new(name.clone()));
+        self.terminal_active = self.terminal_tabs.len() - 1;
+        self.ensure_live_terminal_session()?;
+        self.set_status_info(format!("Opened terminal tab `{name}`"));
+        Ok(())
+    }
+
+    /// Closes the active tab and kills its session, persistent or not.
+    pub fn close_terminal_tab(&mut self) {
+        let Some(tab) = self.terminal_tabs.get(self.terminal_active) else {
+            return;
+        };
+
+        // Kill the tmux session before dropping the tab, so a failure leaves
+        // both in place to retry. A session that already ended needs no kill.
+        if let Some(session) = &tab.persistent
+            && terminal::list_persistent_sessions(session).contains(session)
+            && let Err(error) = terminal::kill_persistent_session(session)
+        {
+            self.set_status_error(error);
+            return;
+        }
+
+        let removed = self.terminal_tabs.remove(self.terminal_active);
+        self.terminal_rename_open = false;
+        self.terminal_rename_input.clear();
+        if self.terminal_tabs.is_empty() {
+            self.terminal_active = 0;
+            self.close_terminal();
+            self.set_status_info(format!("Closed terminal tab `{}`", removed.name));
+            return;
+        }
+
+        self.terminal_active = self.terminal_active.min(self.terminal_tabs.len() - 1);
+        self.set_status_info(format!(
+            "Closed terminal tab `{}`; now on `{}`",
+            removed.name,
+            self.active_terminal_tab()
+                .map_or("", |tab| tab.name.as_str())
+        ));
+    }
+
+    pub fn cycle_terminal_tab(&mut self, delta: isize) {
+        let len = self.terminal_tabs.len();
+        if len < 2 {
+            return;
+        }
+
+        let shift = delta.rem_euclid(len as isize) as usize;
+        self.select_terminal_tab((self.terminal_active + shift) % len);
+    }
+
+    pub fn select_terminal_tab(&mut self, idx: usize) {
+        if idx >= self.terminal_tabs.len() || idx == self.terminal_active {
+            return;
+        }
+
+        self.terminal_active = idx;
+        self.terminal_rename_open = false;
+        self.terminal_rename_input.clear();
+        // Reattached tabs connect to their session once they are shown.
+        if self.terminal_open
+            && self
+                .active_terminal_tab()
+                .is_some_and(|tab| tab.session.is_none())
+            && let Err(error) = self.ensure_live_terminal_session()
+        {
+            self.set_status_error(error);
+            return;
+        }
+        let rows = self.terminal_view_rows;
+        let cols = self.terminal_view_cols;
+        if let Err(error) = self.terminal_resize(rows as u16, cols as u16) {
+            self.set_status_error(error);
+            return;
+        }
+        self.set_status_info(format!("Terminal tab: {}", self.terminal_tab_summary()));
+    }
+
+    pub fn terminal_tabs(&self) -> &[TerminalTab] {
+        &self.terminal_tabs
+    }
+
+    pub fn terminal_active_tab_index(&self) -> usize {
+        self.terminal_active
+    }
+
+    pub fn start_terminal_tab_rename(&mut self) {
+        let Some(name) = self.active_terminal_tab().map(|tab| tab.name.clone()) else {
+            return;
+        };
+        self.terminal_rename_open = true;
+        self.terminal_rename_input = name;
+        self.set_status_info("Rename tab: type a name, Enter save, Esc cancel");
+    }
+
+    pub fn terminal_rename_append(&mut self, ch: char) {
+        if !ch.is_control() {
+            self.terminal_rename_input.push(ch);
+        }
+    }
+
+    pub fn terminal_rename_backspace(&mut self) {
+        self.terminal_rename_input.pop();
+    }
+
+    pub fn submit_terminal_tab_rename(&mut self) {
+        let name = self.terminal_rename_input.trim().to_owned();
+        self.terminal_rename_open = false;
+        self.terminal_rename_input.clear();
+        if name.is_empty() {
+            self.set_status_warn("Tab name cannot be empty");
+            return;
+        }
+
+        let prefix = terminal::persistent_session_prefix(&self.repo_root);
+        let Some(tab) = self.terminal_tabs.get_mut(self.terminal_active) else {
+            return;
+        };
+        if let Some(session) = tab.persistent.as_mut() {
+            let renamed = format!("{prefix}{}", terminal::tmux_safe(&name));
+            // A restored tab keeps its tmux session before it attaches, while
+            // a new tab only gets one once it attaches, so ask tmux.
+            let exists = tab.session.is_some()
+                || terminal::list_persistent_sessions(&prefix).contains(session);
+            if exists && let Err(error) = terminal::rename_persistent_session(session, &renamed) {
+                self.set_status_error(error);
+                return;
+            }
+            *session = renamed;
+        }
+        tab.name = name.clone();
+        self.set_status_info(format!("Renamed terminal tab to `{name}`"));
+    }
+
+    pub fn cancel_terminal_tab_rename(&mut self) {
+        if self.terminal_rename_open {
+            self.terminal_rename_open = false;
+            self.terminal_rename_input.clear();
+            self.set_status_info("Rename cancelled");
+        }
+    }
+}
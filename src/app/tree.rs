@@ -0,0 +1,262 @@
+use anyhow::Result;
+
+use crate::git::{self, LineStat};
+
+use super::{App, TreeFileEntry};
+
+mod build;
+mod group;
+mod selection;
+
+use build::{build_tree_rows, header_paths};
+use group::StatusGroup;
+pub(crate) use group::sort_tree_files;
+
+/// Group header paths start with `/`, which git never reports for a file,
+/// so they cannot collide with directory rows in the collapse set.
+const GROUP_PATH_PREFIX: char = '/';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeRowKind {
+    Directory,
+    Group,
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeRow {
+    pub kind: TreeRowKind,
+    /// Full repo-relative path; directories have no trailing slash and group
+    /// headers use a `/`-prefixed key.
+    pub path: String,
+    /// Display label; single-child directory chains are joined (`src/app`).
+    pub label: String,
+    pub depth: usize,
+    /// Index into `App::tree_files` for file rows.
+    pub file_index: Option<usize>,
+    /// Indices into `App::tree_files` of every file under a header row.
+    pub members: Vec<usize>,
+    pub collapsed: bool,
+    pub file_count: usize,
+    pub staged_count: usize,
+    pub unstaged_count: usize,
+    pub staged_stat: LineStat,
+    pub unstaged_stat: LineStat,
+}
+
+impl TreeRow {
+    pub fn is_directory(&self) -> bool {
+        self.kind == TreeRowKind::Directory
+    }
+
+    /// Directory and group rows own the rows below them.
+    pub fn is_header(&self) -> bool {
+        matches!(self.kind, TreeRowKind::Directory | TreeRowKind::Group)
+    }
+
+    /// Name used in status messages: `dir/` for directories, the label for groups.
+    pub fn display_name(&self) -> String {
+        match self.kind {
+            TreeRowKind::Directory => format!("{}/", self.path),
+            TreeRowKind::Group => self.label.clone(),
+            TreeRowKind::File => self.path.clone(),
+        }
+    }
+
+    /// Whether stage on this header picks up unstaged edits. The Staged group
+    /// lists its files for their staged side only.
+    pub(super) fn acts_on_unstaged(&self) -> bool {
+        self.status_group() != Some(StatusGroup::Staged)
+    }
+
+    /// Whether unstage on this header picks up staged edits; not from the
+    /// Unstaged and Untracked groups.
+    pub(super) fn acts_on_staged(&self) -> bool {
+        matches!(self.status_group(), None | Some(StatusGroup::Staged))
+    }
+
+    /// The status group a header stands for, when grouped by status.
+    fn status_group(&self) -> Option<StatusGroup> {
+        if self.kind != TreeRowKind::Group {
+            return None;
+        }
+        self.path
+            .strip_prefix(GROUP_PATH_PREFIX)
+            .and_then(StatusGroup::from_key)
+    }
+
+    fn header(kind: TreeRowKind, path: String, label: String, depth: usize) -> Self {
+        Self {
+            kind,
+            path,
+            label,
+            depth,
+            file_index: None,
+            members: Vec::new(),
+            collapsed: false,
+            file_count: 0,
+            staged_count: 0,
+            unstaged_count: 0,
+            staged_stat: LineStat::default(),
+            unstaged_stat: LineStat::default(),
+        }
+    }
+
+    fn file(entry: &TreeFileEntry, file_index: usize, label: String, depth: usize) -> Self {
+        Self {
+            kind: TreeRowKind::File,
+            path: entry.path.clone(),
+            label,
+            depth,
+            file_index: Some(file_index),
+            members: Vec::new(),
+            collapsed: false,
+            file_count: 1,
+            staged_count: usize::from(entry.staged),
+            unstaged_count: usize::from(entry.unstaged),
+            staged_stat: entry.staged_stat,
+            unstaged_stat: entry.unstaged_stat,
+        }
+    }
+
+    fn add_member(&mut self, entry: &TreeFileEntry, file_index: usize) {
+        self.members.push(file_index);
+        self.file_count += 1;
+        self.staged_count += usize::from(entry.staged);
+        self.unstaged_count += usize::from(entry.unstaged);
+        self.staged_stat.accumulate(entry.staged_stat);
+        self.unstaged_stat.accumulate(entry.unstaged_stat);
+    }
+}
+
+impl App {
+    pub fn toggle_selected_directory(&mut self) -> Result<()> {
+        if self.tree_filter_active() {
+            self.set_status_warn("Directories stay expanded while a filter is active");
+            return Ok(());
+        }
+
+        let Some(row) = self.selected_tree_row().cloned() else {
+            self.set_status_warn("No directory selected");
+            return Ok(());
+        };
+
+        let header = if row.is_header() {
+            row
+        } else {
+            let Some(parent) = self.parent_header_row(self.tree_selected.unwrap_or(0)) else {
+                self.set_status_warn("Selected file is not inside a directory");
+                return Ok(());
+            };
+            parent
+        };
+
+        let collapsed = if self.collapsed_dirs.remove(&header.path) {
+            false
+        } else {
+            self.collapsed_dirs.insert(header.path.clone());
+            true
+        };
+
+        self.rebuild_tree_rows_keeping(Some(header.path.clone()))?;
+        self.set_status_info(format!(
+            "{} {}",
+            if collapsed { "Collapsed" } else { "Expanded" },
+            header.display_name()
+        ));
+        Ok(())
+    }
+
+    pub fn toggle_all_directories(&mut self) -> Result<()> {
+        if self.tree_filter_active() {
+            self.set_status_warn("Directories stay expanded while a filter is active");
+            return Ok(());
+        }
+
+        let previous = self.selected_tree_path().map(ToOwned::to_owned);
+        let headers = header_paths(&self.tree_files, self.settings.sidebar_grouping);
+        let collapse = !headers
+            .iter()
+            .any(|path| self.collapsed_dirs.contains(path));
+        if collapse {
+            self.collapsed_dirs.extend(headers);
+        } else {
+            self.collapsed_dirs.retain(|path| !headers.contains(path));
+        }
+
+        let section = self.selected_tree_section();
+        let preferred = previous.map(|path| self.visible_ancestor_path(&path, section.as_deref()));
+        self.rebuild_tree_rows_keeping(preferred)?;
+        self.set_status_info(if collapse {
+            "Collapsed all directories"
+        } else {
+            "Expanded all directories"
+        });
+        Ok(())
+    }
+
+    pub(super) fn stage_selected_directory(&mut self, dir: &TreeRow) -> Result<()> {
+        let paths = self.header_member_paths(dir, |entry| entry.unstaged && dir.acts_on_unstaged());
+        if paths.is_empty() {
+            self.set_status_warn(format!("{} has no unstaged changes", dir.display_name()));
+            return Ok(());
+        }
+
+        let refs = paths.iter().map(String::as_str).collect::<Vec<_>>();
+        git::stage_paths(&self.repo_root, &refs)?;
+        self.refresh()?;
+        self.set_status_info(format!(
+            "Staged {} files in {}",
+            paths.len(),
+            dir.display_name()
+        ));
+        Ok(())
+    }
+
+    pub(super) fn unstage_selected_directory(&mut self, dir: &TreeRow) -> Result<()> {
+        let paths = self.header_member_paths(dir, |entry| entry.staged && dir.acts_on_staged());
+        if paths.is_empty() {
+            self.set_status_warn(format!("{} has no staged changes", dir.display_name()));
+            return Ok(());
+        }
+
+        let refs = paths.iter().map(String::as_str).collect::<Vec<_>>();
+        git::unstage_paths(&self.repo_root, &refs)?;
+        self.refresh()?;
+        self.set_status_info(format!(
+            "Unstaged {} files in {}",
+            paths.len(),
+            dir.display_name()
+        ));
+        Ok(())
+    }
+
+    /// Re-sorts the change list after the sort or grouping setting changed,
+    /// keeping the current selection.
+    pub(super) fn reorder_tree_files(&mut self) -> Result<()> {
+        sort_tree_files(&mut self.change_files, self.settings.sidebar_sort);
+        self.reapply_tree_filter()
+    }
+
+    pub(super) fn rebuild_tree_rows(&mut self) {
+        self.tree_rows = build_tree_rows(
+            &self.tree_files,
+            &self.collapsed_dirs,
+            self.tree_filter_active(),
+            self.settings.sidebar_grouping,
+        );
+    }
+
+    fn header_member_paths<F>(&self, header: &TreeRow, keep: F) -> Vec<String>
+    where
+        F: Fn(&TreeFileEntry) -> bool,
+    {
+        header
+            .members
+            .iter()
+            .filter_map(|idx| self.tree_files.get(*idx))
+            .filter(|entry| keep(entry))
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,283 @@
+//! Turning the change list into sidebar rows: the directory tree, or one
+//! header per group with its files beneath.
+
+use std::collections::BTreeSet;
+
+use crate::settings::SidebarGrouping;
+
+use super::group::group_files;
+use super::{GROUP_PATH_PREFIX, TreeFileEntry, TreeRow, TreeRowKind};
+
+#[derive(Default)]
+struct DirNode {
+    dirs: Vec<(String, DirNode)>,
+    files: Vec<usize>,
+}
+
+impl DirNode {
+    fn insert(&mut self, segments: &[&str], file_index: usize) {
+        match segments {
+            [] => {}
+            [_] => self.files.push(file_index),
+            [dir, rest @ ..] => {
+                let idx = match self.dirs.iter().position(|(name, _)| name == dir) {
+                    Some(idx) => idx,
+                    None => {
+                        self.dirs.push(((*dir).to_owned(), DirNode::default()));
+                        self.dirs.len() - 1
+                    }
+                };
+                self.dirs[idx].1.insert(rest, file_index);
+            }
+        }
+    }
+}
+
+/// Builds the visible sidebar rows for `files`.
+///
+/// In directory mode directories come first and appear in the order their
+/// first file was seen; other groupings emit one header per group with the
+/// files listed flat beneath it. Files keep their input order within a
+/// directory or group. Headers listed in `collapsed` hide their children
+/// unless `expand_all` is set.
+pub(super) fn build_tree_rows(
+    files: &[TreeFileEntry],
+    collapsed: &BTreeSet<String>,
+    expand_all: bool,
+    grouping: SidebarGrouping,
+) -> Vec<TreeRow> {
+    let mut rows = Vec::new();
+
+    if grouping == SidebarGrouping::Directory {
+        let mut root = DirNode::default();
+        for (idx, entry) in files.iter().enumerate() {
+            let segments = entry.path.split('/').collect::<Vec<_>>();
+            root.insert(&segments, idx);
+        }
+        push_node_rows(&root, "", 0, files, collapsed, expand_all, &mut rows);
+        return rows;
+    }
+
+    for (key, label, members) in group_files(files, grouping) {
+        let path = format!("{GROUP_PATH_PREFIX}{key}");
+        let mut header = TreeRow::header(TreeRowKind::Group, path, label, 0);
+        for &file_index in &members {
+            header.add_member(&files[file_index], file_index);
+        }
+        header.collapsed = !expand_all && collapsed.contains(&header.path);
+        let show_children = !header.collapsed;
+        let top_level = grouping == SidebarGrouping::TopLevel && !key.ends_with('/');
+        rows.push(header);
+
+        if show_children {
+            for file_index in members {
+                let entry = &files[file_index];
+                let label = match entry.path.split_once('/') {
+                    Some((_, rest)) if top_level => rest.to_owned(),
+                    _ => entry.path.clone(),
+                };
+                rows.push(TreeRow::file(entry, file_index, label, 1));
+            }
+        }
+    }
+
+    rows
+}
+
+/// Lists every header path the tree can show, ignoring collapse state.
+pub(super) fn header_paths(files: &[TreeFileEntry], grouping: SidebarGrouping) -> Vec<String> {
+    build_tree_rows(files, &BTreeSet::new(), true, grouping)
+        .into_iter()
+        .filter(TreeRow::is_header)
+        .map(|row| row.path)
+        .collect()
+}
+
+fn push_node_rows(
+    node: &DirNode,
+    prefix: &str,
+    depth: usize,
+    files: &[TreeFileEntry],
+    collapsed: &BTreeSet<String>,
+    expand_all: bool,
+    rows: &mut Vec<TreeRow>,
+) {
+    for (name, child) in &node.dirs {
+        let mut label = name.clone();
+        let mut dir = child;
+        while dir.files.is_empty() && dir.dirs.len() == 1 {
+            let (next_name, next) = &dir.dirs[0];
+            label.push('/');
+            label.push_str(next_name);
+            dir = next;
+        }
+
+        let path = join_path(prefix, &label);
+        let mut row = TreeRow::header(TreeRowKind::Directory, path.clone(), label, depth);
+        row.collapsed = !expand_all && collapsed.contains(&path);
+        add_node_members(dir, files, &mut row);
+        let is_collapsed = row.collapsed;
+        rows.push(row);
+
+        if !is_collapsed {
+            push_node_rows(dir, &path, depth + 1, files, collapsed, expand_all, rows);
+        }
+    }
+
+    for &file_index in &node.files {
+        let entry = &files[file_index];
+        let label = entry
+            .path
+            .rsplit('/')
+            .next()
+            .unwrap_or(entry.path.as_str())
+            .to_owned();
+        rows.push(TreeRow::file(entry, file_index, label, depth));
+    }
+}
+
+fn add_node_members(node: &DirNode, files: &[TreeFileEntry], row: &mut TreeRow) {
+    for &file_index in &node.files {
+        row.add_member(&files[file_index], file_index);
+    }
+    for (_, child) in &node.dirs {
+        add_node_members(child, files, row);
+    }
+}
+
+fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{build_tree_rows, header_paths};
+    use crate::app::TreeFileEntry;
+    use crate::app::tree::{TreeRow, TreeRowKind};
+    use crate::git::LineStat;
+    use crate::settings::SidebarGrouping;
+
+    fn entry(path: &str, staged: bool, unstaged: bool) -> TreeFileEntry {
+        TreeFileEntry {
+            path: path.to_owned(),
+            staged,
+            unstaged,
+            ..TreeFileEntry::default()
+        }
+    }
+
+    fn snapshot(rows: &[TreeRow]) -> Vec<String> {
+        rows.iter()
+            .map(|row| {
+                let marker = match row.kind {
+                    TreeRowKind::Directory | TreeRowKind::Group if row.collapsed => "+",
+                    TreeRowKind::Directory | TreeRowKind::Group => "-",
+                    TreeRowKind::File => " ",
+                };
+                format!(
+                    "{}{marker}{} ({})",
+                    "  ".repeat(row.depth),
+                    row.label,
+                    row.file_count
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn builds_nested_rows_with_compacted_directories() {
+        let files = vec![
+            entry("README.md", false, true),
+            entry("src/app/finder.rs", true, false),
+            entry("src/app/tree.rs", false, true),
+            entry("src/ui/deep/nested/widget.rs", true, true),
+        ];
+
+        let rows = build_tree_rows(&files, &BTreeSet::new(), false, SidebarGrouping::Directory);
+
+        assert_eq!(
+            snapshot(&rows),
+            vec![
+                "-src (3)",
+                "  -app (2)",
+                "     finder.rs (1)",
+                "     tree.rs (1)",
+                "  -ui/deep/nested (1)",
+                "     widget.rs (1)",
+                " README.md (1)",
+            ]
+        );
+        assert_eq!(rows[0].staged_count, 2);
+        assert_eq!(rows[0].unstaged_count, 2);
+        assert_eq!(rows[4].path, "src/ui/deep/nested");
+        assert_eq!(rows[5].file_index, Some(3));
+    }
+
+    #[test]
+    fn directory_rows_sum_line_stats_per_side() {
+        let mut first = entry("src/a.rs", true, true);
+        first.staged_stat = LineStat {
+            added: 4,
+            removed: 1,
+            ..LineStat::default()
+        };
+        first.unstaged_stat = LineStat {
+            added: 2,
+            removed: 0,
+            ..LineStat::default()
+        };
+        let mut second = entry("src/b.rs", false, true);
+        second.unstaged_stat = LineStat {
+            added: 10,
+            removed: 3,
+            ..LineStat::default()
+        };
+
+        let rows = build_tree_rows(
+            &[first, second],
+            &BTreeSet::new(),
+            false,
+            SidebarGrouping::Directory,
+        );
+
+        assert_eq!(
+            (rows[0].staged_stat.added, rows[0].staged_stat.removed),
+            (4, 1)
+        );
+        assert_eq!(
+            (rows[0].unstaged_stat.added, rows[0].unstaged_stat.removed),
+            (12, 3)
+        );
+    }
+
+    #[test]
+    fn collapsed_directories_hide_children_unless_expanded() {
+        let files = vec![
+            entry("src/a.rs", false, true),
+            entry("src/b.rs", false, true),
+        ];
+        let collapsed = BTreeSet::from([String::from("src")]);
+
+        let rows = build_tree_rows(&files, &collapsed, false, SidebarGrouping::Directory);
+        assert_eq!(snapshot(&rows), vec!["+src (2)"]);
+
+        let expanded = build_tree_rows(&files, &collapsed, true, SidebarGrouping::Directory);
+        assert_eq!(expanded.len(), 3);
+    }
+
+    #[test]
+    fn lists_all_directory_paths() {
+        let files = vec![entry("a/b/c.rs", false, true), entry("a/d.rs", false, true)];
+
+        assert_eq!(
+            header_paths(&files, SidebarGrouping::Directory),
+            vec!["a", "a/b"]
+        );
+    }
+}
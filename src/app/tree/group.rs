@@ -0,0 +1,291 @@
+//! Sidebar sort orders and the non-directory groupings.
+
+use std::cmp::Reverse;
+
+use crate::settings::{SidebarGrouping, SidebarSort};
+
+use super::TreeFileEntry;
+
+/// The headers of the status grouping. Each one stands for a side of the
+/// index, so staging from a header only touches that side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(in crate::app) enum StatusGroup {
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+impl StatusGroup {
+    const ALL: [Self; 3] = [Self::Staged, Self::Unstaged, Self::Untracked];
+
+    /// Group key; the digit keeps the headers in this order.
+    fn key(self) -> &'static str {
+        match self {
+            Self::Staged => "status/0-staged",
+            Self::Unstaged => "status/1-unstaged",
+            Self::Untracked => "status/2-untracked",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Staged => "Staged",
+            Self::Unstaged => "Unstaged",
+            Self::Untracked => "Untracked",
+        }
+    }
+
+    pub(super) fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|group| group.key() == key)
+    }
+}
+
+/// Orders files inside each directory or group according to `sort`.
+///
+/// Size, time, and status orders fall back to the path so refreshes keep a
+/// stable order for equal keys.
+pub(crate) fn sort_tree_files(files: &mut [TreeFileEntry], sort: SidebarSort) {
+    match sort {
+        SidebarSort::Path => files.sort_by(|a, b| a.path.cmp(&b.path)),
+        SidebarSort::ChangeSize => {
+            files.sort_by(|a, b| {
+                change_size(b)
+                    .cmp(&change_size(a))
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+        }
+        SidebarSort::Modified => {
+            files.sort_by(|a, b| {
+                Reverse(a.modified)
+                    .cmp(&Reverse(b.modified))
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+        }
+        SidebarSort::Status => {
+            files.sort_by(|a, b| {
+                status_rank(a)
+                    .cmp(&status_rank(b))
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+        }
+    }
+}
+
+/// Splits files into `(key, label, members)` groups in first-seen order,
+/// except status groups which always read staged, unstaged, untracked.
+pub(super) fn group_files(
+    files: &[TreeFileEntry],
+    grouping: SidebarGrouping,
+) -> Vec<(String, String, Vec<usize>)> {
+    let mut groups: Vec<(String, String, Vec<usize>)> = Vec::new();
+    for (idx, entry) in files.iter().enumerate() {
+        let keys = match grouping {
+            SidebarGrouping::Status => status_groups(entry)
+                .into_iter()
+                .map(|group| (group.key().to_owned(), group.label().to_owned()))
+                .collect(),
+            SidebarGrouping::Extension => vec![match file_extension(&entry.path) {
+                Some(ext) => (format!("ext/{ext}"), format!(".{ext}")),
+                None => (String::from("ext/"), String::from("(no extension)")),
+            }],
+            // Directory grouping is built by `build_tree_rows`.
+            SidebarGrouping::TopLevel | SidebarGrouping::Directory => {
+                vec![match entry.path.split_once('/') {
+                    Some((top, _)) => (format!("top/{top}"), format!("{top}/")),
+                    None => (String::from("top/"), String::from("(root)")),
+                }]
+            }
+        };
+
+        for (key, label) in keys {
+            match groups.iter_mut().find(|(existing, _, _)| *existing == key) {
+                Some((_, _, members)) => members.push(idx),
+                None => groups.push((key, label, vec![idx])),
+            }
+        }
+    }
+
+    if grouping == SidebarGrouping::Status {
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    groups
+}
+
+fn file_extension(path: &str) -> Option<&str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => Some(ext),
+        _ => None,
+    }
+}
+
+fn change_size(entry: &TreeFileEntry) -> usize {
+    [entry.staged_stat, entry.unstaged_stat]
+        .iter()
+        .map(|stat| stat.added.saturating_add(stat.removed))
+        .sum()
+}
+
+/// Sort key for the status order: 0 = staged only, 1 = has unstaged tracked
+/// edits, 2 = untracked.
+fn status_rank(entry: &TreeFileEntry) -> u8 {
+    if entry.untracked {
+        2
+    } else if entry.unstaged {
+        1
+    } else {
+        0
+    }
+}
+
+/// Status groups `entry` is listed under. A file with both staged and
+/// unstaged edits shows up in both, since either side can be acted on.
+fn status_groups(entry: &TreeFileEntry) -> Vec<StatusGroup> {
+    if entry.untracked {
+        return vec![StatusGroup::Untracked];
+    }
+    let mut groups = Vec::new();
+    if entry.staged || !entry.unstaged {
+        groups.push(StatusGroup::Staged);
+    }
+    if entry.unstaged {
+        groups.push(StatusGroup::Unstaged);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::sort_tree_files;
+    use crate::app::TreeFileEntry;
+    use crate::app::tree::build::build_tree_rows;
+    use crate::app::tree::{TreeRow, TreeRowKind};
+    use crate::settings::{SidebarGrouping, SidebarSort};
+
+    fn entry(path: &str, staged: bool, unstaged: bool) -> TreeFileEntry {
+        TreeFileEntry {
+            path: path.to_owned(),
+            staged,
+            unstaged,
+            ..TreeFileEntry::default()
+        }
+    }
+
+    fn snapshot(rows: &[TreeRow]) -> Vec<String> {
+        rows.iter()
+            .map(|row| {
+                let marker = match row.kind {
+                    TreeRowKind::Directory | TreeRowKind::Group if row.collapsed => "+",
+                    TreeRowKind::Directory | TreeRowKind::Group => "-",
+                    TreeRowKind::File => " ",
+                };
+                format!(
+                    "{}{marker}{} ({})",
+                    "  ".repeat(row.depth),
+                    row.label,
+                    row.file_count
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_status_in_fixed_order() {
+        let mut untracked = entry("new.txt", false, true);
+        untracked.untracked = true;
+        let files = vec![
+            untracked,
+            entry("src/lib.rs", false, true),
+            entry("docs/guide.md", true, false),
+        ];
+
+        let rows = build_tree_rows(&files, &BTreeSet::new(), false, SidebarGrouping::Status);
+
+        assert_eq!(
+            snapshot(&rows),
+            vec![
+                "-Staged (1)",
+                "   docs/guide.md (1)",
+                "-Unstaged (1)",
+                "   src/lib.rs (1)",
+                "-Untracked (1)",
+                "   new.txt (1)",
+            ]
+        );
+        assert_eq!(rows[0].path, "/status/0-staged");
+        assert_eq!(rows[2].members, vec![1]);
+    }
+
+    #[test]
+    fn lists_partly_staged_files_under_staged_and_unstaged() {
+        let files = vec![
+            entry("src/lib.rs", true, true),
+            entry("docs/guide.md", true, false),
+        ];
+
+        let rows = build_tree_rows(&files, &BTreeSet::new(), false, SidebarGrouping::Status);
+
+        assert_eq!(
+            snapshot(&rows),
+            vec![
+                "-Staged (2)",
+                "   src/lib.rs (1)",
+                "   docs/guide.md (1)",
+                "-Unstaged (1)",
+                "   src/lib.rs (1)",
+            ]
+        );
+        assert_eq!(rows[0].members, vec![0, 1]);
+        assert_eq!(rows[3].members, vec![0]);
+    }
+
+    #[test]
+    fn groups_by_top_level_directory_and_extension() {
+        let files = vec![
+            entry("src/app/tree.rs", false, true),
+            entry("Cargo.toml", false, true),
+            entry("src/main.rs", false, true),
+        ];
+
+        let top = build_tree_rows(&files, &BTreeSet::new(), false, SidebarGrouping::TopLevel);
+        assert_eq!(
+            snapshot(&top),
+            vec![
+                "-src/ (2)",
+                "   app/tree.rs (1)",
+                "   main.rs (1)",
+                "-(root) (1)",
+                "   Cargo.toml (1)",
+            ]
+        );
+
+        let collapsed = BTreeSet::from([String::from("/ext/rs")]);
+        let ext = build_tree_rows(&files, &collapsed, false, SidebarGrouping::Extension);
+        assert_eq!(
+            snapshot(&ext),
+            vec!["+.rs (2)", "-.toml (1)", "   Cargo.toml (1)"]
+        );
+    }
+
+    #[test]
+    fn sorts_by_change_size_then_path() {
+        let mut small = entry("b.rs", false, true);
+        small.unstaged_stat.added = 2;
+        let mut big = entry("c.rs", true, true);
+        big.staged_stat.added = 5;
+        big.unstaged_stat.removed = 5;
+        let mut tied = entry("a.rs", false, true);
+        tied.unstaged_stat.added = 2;
+        let mut files = vec![small, big, tied];
+
+        sort_tree_files(&mut files, SidebarSort::ChangeSize);
+
+        let order = files
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["c.rs", "a.rs", "b.rs"]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Finding and keeping the selected sidebar row across rebuilds.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use super::build::build_tree_rows;
+use super::{App, TreeRow, TreeRowKind};
+
+fn is_under_dir(path: &str, dir: &str) -> bool {
+    path.len() > dir.len() && path.starts_with(dir) && path.as_bytes()[dir.len()] == b'/'
+}
+
+/// Pairs each row with the group header it sits under (itself for a header).
+fn with_sections(rows: &[TreeRow]) -> impl Iterator<Item = (usize, &TreeRow, Option<&str>)> {
+    rows.iter().enumerate().scan(None, |section, (idx, row)| {
+        if row.kind == TreeRowKind::Group {
+            *section = Some(row.path.as_str());
+        }
+        Some((idx, row, *section))
+    })
+}
+
+impl App {
+    pub fn selected_tree_row(&self) -> Option<&TreeRow> {
+        self.tree_selected.and_then(|idx| self.tree_rows.get(idx))
+    }
+
+    /// The group header the selected row sits under. A partly staged file
+    /// grouped by status is listed under both Staged and Unstaged, and path
+    /// lookups use this to stay on the same copy.
+    pub(in crate::app) fn selected_tree_section(&self) -> Option<String> {
+        let selected = self.tree_selected?;
+        with_sections(&self.tree_rows)
+            .find(|(idx, _, _)| *idx == selected)
+            .and_then(|(_, _, section)| section.map(ToOwned::to_owned))
+    }
+
+    /// The row showing `path`, preferring the one under `section`.
+    pub(in crate::app) fn tree_row_index(
+        &self,
+        path: &str,
+        section: Option<&str>,
+    ) -> Option<usize> {
+        let matches = with_sections(&self.tree_rows)
+            .filter(|(_, row, _)| row.path == path)
+            .collect::<Vec<_>>();
+        matches
+            .iter()
+            .find(|(_, _, row_section)| *row_section == section)
+            .or(matches.first())
+            .map(|(idx, _, _)| *idx)
+    }
+
+    /// Expands every collapsed header that hides `path`, keeping the
+    /// selected row.
+    pub(in crate::app) fn reveal_tree_path(&mut self, path: &str) {
+        let expanded = build_tree_rows(
+            &self.tree_files,
+            &BTreeSet::new(),
+            true,
+            self.settings.sidebar_grouping,
+        );
+        let hiding = expanded
+            .iter()
+            .filter(|row| self.header_contains(row, path))
+            .map(|row| row.path.as_str())
+            .collect::<Vec<_>>();
+
+        let before = self.collapsed_dirs.len();
+        self.collapsed_dirs
+            .retain(|header| !hiding.contains(&header.as_str()));
+        if self.collapsed_dirs.len() != before {
+            let section = self.selected_tree_section();
+            let selected = self.selected_tree_row().map(|row| row.path.clone());
+            self.rebuild_tree_rows();
+            if let Some(idx) =
+                selected.and_then(|path| self.tree_row_index(&path, section.as_deref()))
+            {
+                self.tree_selected = Some(idx);
+            }
+        }
+    }
+
+    /// Returns the row path that currently shows `path`: the path itself when
+    /// visible, otherwise the closest collapsed ancestor header. Rows under
+    /// `section` win over the other copy of a partly staged file.
+    pub(in crate::app) fn visible_ancestor_path(
+        &self,
+        path: &str,
+        section: Option<&str>,
+    ) -> String {
+        let rows = build_tree_rows(
+            &self.tree_files,
+            &self.collapsed_dirs,
+            self.tree_filter_active(),
+            self.settings.sidebar_grouping,
+        );
+        with_sections(&rows)
+            .filter(|(_, row, _)| row.path == path || self.header_contains(row, path))
+            .max_by_key(|(_, row, row_section)| (*row_section == section, row.depth))
+            .map(|(_, row, _)| row.path.clone())
+            .unwrap_or_else(|| path.to_owned())
+    }
+
+    fn header_contains(&self, row: &TreeRow, path: &str) -> bool {
+        if !row.is_header() {
+            return false;
+        }
+        if row.is_directory() && is_under_dir(path, &row.path) {
+            return true;
+        }
+        row.members.iter().any(|idx| {
+            self.tree_files
+                .get(*idx)
+                .is_some_and(|entry| entry.path == path)
+        })
+    }
+
+    pub(super) fn rebuild_tree_rows_keeping(
+        &mut self,
+        preferred_path: Option<String>,
+    ) -> Result<()> {
+        let previous_file = self.selected_tree_file().map(|entry| entry.path.clone());
+        let section = self.selected_tree_section();
+        self.rebuild_tree_rows();
+        if let Some(path) = preferred_path
+            && let Some(idx) = self.tree_row_index(&path, section.as_deref())
+        {
+            self.tree_selected = Some(idx);
+        }
+        self.normalize_focus();
+        self.sync_scrolls();
+
+        if self.selected_tree_file().map(|entry| entry.path.clone()) != previous_file {
+            self.load_current_diff()?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn parent_header_row(&self, idx: usize) -> Option<TreeRow> {
+        let depth = self.tree_rows.get(idx)?.depth;
+        self.tree_rows[..idx]
+            .iter()
+            .rev()
+            .find(|row| row.is_header() && row.depth < depth)
+            .cloned()
+    }
+}
@@ -0,0 +1,89 @@
+use crate::settings;
+
+use super::App;
+
+impl App {
+    /// Whether the repository's `.dif.toml` may pick commands to run.
+    pub fn repo_config_trusted(&self) -> bool {
+        self.settings
+            .is_repo_trusted(&self.repo_trust_key, &self.repo_config.sha256)
+    }
+
+    /// Asks before trusting this repository's `.dif.toml`, or stops trusting
+    /// it straight away when it already is.
+    pub fn toggle_repo_trust(&mut self) {
+        if self.repo_config_trusted() {
+            self.settings
+                .set_repo_trusted(&self.repo_trust_key, &self.repo_config.sha256, false);
+            self.mark_settings_dirty();
+            self.set_status_info(format!(
+                "No longer trusting {}; its commands are ignored",
+                settings::REPO_CONFIG_FILE
+            ));
+            return;
+        }
+
+        let commands = self.repo_config_commands();
+        if commands.is_empty() {
+            self.set_status_info(format!(
+                "{} sets no commands; nothing to trust",
+                settings::REPO_CONFIG_FILE
+            ));
+            return;
+        }
+
+        self.pending_repo_trust = true;
+        self.set_status_warn(format!(
+            "Trust {} to run {}? Press Enter/y to confirm, n/Esc to cancel",
+            self.repo_trust_key,
+            commands.join(", ")
+        ));
+    }
+
+    pub fn has_pending_repo_trust(&self) -> bool {
+        self.pending_repo_trust
+    }
+
+    pub fn confirm_repo_trust(&mut self) {
+        if !std::mem::take(&mut self.pending_repo_trust) {
+            return;
+        }
+
+        self.settings
+            .set_repo_trusted(&self.repo_trust_key, &self.repo_config.sha256, true);
+        self.mark_settings_dirty();
+        self.set_status_info(format!(
+            "Trusted {}; new terminal tabs use its settings",
+            settings::REPO_CONFIG_FILE
+        ));
+    }
+
+    pub fn cancel_repo_trust(&mut self) {
+        if std::mem::take(&mut self.pending_repo_trust) {
+            self.set_status_info("Repo left untrusted");
+        }
+    }
+
+    /// What the repository's `.dif.toml` would run, for the trust prompt.
+    fn repo_config_commands(&self) -> Vec<String> {
+        let shell = &self.repo_config.shell;
+        let mut commands = Vec::new();
+        if let Some(program) = &shell.program {
+            commands.push(format!("shell `{program}`"));
+        }
+        if let Some(args) = &shell.args {
+            commands.push(format!("shell args `{}`", args.join(" ")));
+        }
+        if !shell.env.is_empty() {
+            let names = shell.env.keys().cloned().collect::<Vec<_>>();
+            commands.push(format!("env {}", names.join(" ")));
+        }
+        if let Some(command) = &shell.initial_command {
+            commands.push(format!("`{command}` on start"));
+        }
+        for (name, command) in &self.repo_config.tasks {
+            commands.push(format!("task {name} `{command}`"));
+        }
+        commands
+    }
+}
@@ -1,4 +1,5 @@
 use ratatui::layout::Rect;
+use regex::{Regex, RegexBuilder};
 
 pub(crate) fn contains(rect: Rect, x: u16, y: u16) -> bool {
     x >= rect.x
@@ -51,26 +52,125 @@ pub(crate) fn order_positions(
     }
 }
 
-pub(crate) fn find_query_in_line(line: &str, query: &str, start_col: usize) -> Option<usize> {
-    if query.is_empty() {
-        return None;
+/// Compiles a terminal search query. Plain queries match literally; either
+/// kind ignores case unless `case_sensitive` is set.
+pub(crate) fn build_search_pattern(
+    query: &str,
+    regex: bool,
+    case_sensitive: bool,
+) -> Result<Regex, regex::Error> {
+    let source = if regex {
+        query.to_owned()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+/// Non-empty matches of `pattern` in `line` as `(start, end)` char columns,
+/// `end` exclusive.
+pub(crate) fn find_pattern_matches(line: &str, pattern: &Regex) -> Vec<(usize, usize)> {
+    let char_col = |byte: usize| line[..byte].chars().count();
+    pattern
+        .find_iter(line)
+        .filter(|found| !found.is_empty())
+        .map(|found| (char_col(found.start()), char_col(found.end())))
+        .collect()
+}
+
+/// A `path:line[:col]` reference found in terminal output. `start` and `end`
+/// are char columns, `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileRef {
+    pub path: String,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Finds `path:line[:col]` references such as compiler diagnostics, test
+/// failures, or grep hits. The path must look like a file (contain `.` or
+/// `/`) and URLs are skipped.
+pub(crate) fn find_file_refs(line: &str) -> Vec<FileRef> {
+    const DELIMITERS: &[char] = &[
+        '"', '\'', '(', ')', '[', ']', '{', '}', '<', '>', ',', ';', '`',
+    ];
+
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut refs = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        if chars[idx].is_whitespace() || DELIMITERS.contains(&chars[idx]) {
+            idx += 1;
+            continue;
+        }
+
+        let start = idx;
+        while idx < chars.len() && !chars[idx].is_whitespace() && !DELIMITERS.contains(&chars[idx])
+        {
+            idx += 1;
+        }
+        if let Some(file_ref) = parse_file_ref(&chars[start..idx], start) {
+            refs.push(file_ref);
+        }
     }
 
-    let line_chars = line.chars().collect::<Vec<_>>();
-    let query_chars = query.chars().collect::<Vec<_>>();
+    refs
+}
 
-    if query_chars.len() > line_chars.len() {
+fn parse_file_ref(token: &[char], offset: usize) -> Option<FileRef> {
+    let colon = token.iter().position(|ch| *ch == ':')?;
+    let path = token[..colon].iter().collect::<String>();
+    if path.is_empty() || !(path.contains('.') || path.contains('/')) {
+        return None;
+    }
+    if token[colon..].starts_with(&[':', '/', '/']) {
         return None;
     }
 
-    let from = start_col.min(line_chars.len());
-    for idx in from..=line_chars.len().saturating_sub(query_chars.len()) {
-        if line_chars[idx..idx + query_chars.len()] == query_chars[..] {
-            return Some(idx);
+    let digits = |from: usize| {
+        token[from..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_digit())
+            .count()
+    };
+
+    let line_start = colon + 1;
+    let line_len = digits(line_start);
+    if line_len == 0 {
+        return None;
+    }
+    let line = token[line_start..line_start + line_len]
+        .iter()
+        .collect::<String>()
+        .parse()
+        .ok()?;
+
+    let mut end = line_start + line_len;
+    let mut column = None;
+    if token.get(end) == Some(&':') {
+        let col_len = digits(end + 1);
+        if col_len > 0 {
+            column = token[end + 1..end + 1 + col_len]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .ok();
+            end += 1 + col_len;
         }
     }
 
-    None
+    Some(FileRef {
+        path,
+        line,
+        column,
+        start: offset,
+        end: offset + end,
+    })
 }
 
 pub(crate) fn clamp_text_cursor(text: &str, cursor: usize) -> usize {
@@ -183,11 +283,56 @@ mod tests {
     use ratatui::layout::Rect;
 
     use super::{
-        clamp_text_cursor, contains, ensure_visible, find_query_in_line, move_text_cursor_down,
-        move_text_cursor_end, move_text_cursor_home, move_text_cursor_up, next_text_cursor,
-        prev_text_cursor, shift_and_clamp_u16,
+        FileRef, build_search_pattern, clamp_text_cursor, contains, ensure_visible, find_file_refs,
+        find_pattern_matches, move_text_cursor_down, move_text_cursor_end, move_text_cursor_home,
+        move_text_cursor_up, next_text_cursor, prev_text_cursor, shift_and_clamp_u16,
     };
 
+    #[test]
+    fn search_patterns_honor_regex_and_case_flags() {
+        let line = "wärn: Error[E0308] then error again";
+
+        let plain = build_search_pattern("error", false, false).expect("valid pattern");
+        assert_eq!(find_pattern_matches(line, &plain), vec![(6, 11), (24, 29)]);
+
+        let sensitive = build_search_pattern("error", false, true).expect("valid pattern");
+        assert_eq!(find_pattern_matches(line, &sensitive), vec![(24, 29)]);
+
+        let literal = build_search_pattern("[E0308]", false, true).expect("valid pattern");
+        assert_eq!(find_pattern_matches(line, &literal), vec![(11, 18)]);
+
+        let regex = build_search_pattern(r"E\d+", true, true).expect("valid pattern");
+        assert_eq!(find_pattern_matches(line, &regex), vec![(12, 17)]);
+
+        assert!(build_search_pattern("(", true, true).is_err());
+    }
+
+    #[test]
+    fn finds_file_refs_in_diagnostics_and_grep_output() {
+        let refs = find_file_refs("   --> src/app.rs:20:5");
+        assert_eq!(
+            refs,
+            vec![FileRef {
+                path: String::from("src/app.rs"),
+                line: 20,
+                column: Some(5),
+                start: 7,
+                end: 22,
+            }]
+        );
+
+        let refs = find_file_refs("src/ui/mod.rs:42:    let palette = (tests/a.py:7)");
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].path, "src/ui/mod.rs");
+        assert_eq!(refs[0].line, 42);
+        assert_eq!(refs[0].column, None);
+        assert_eq!(refs[1].path, "tests/a.py");
+        assert_eq!(refs[1].line, 7);
+
+        assert!(find_file_refs("see http://localhost:8080/path").is_empty());
+        assert!(find_file_refs("error: 3 warnings, time 12:30").is_empty());
+    }
+
     #[test]
     fn contains_checks_inside_and_outside_bounds() {
         let rect = Rect::new(2, 3, 4, 2);
@@ -215,13 +360,6 @@ mod tests {
         assert_eq!(shift_and_clamp_u16(10, -5, 4, 8, 20), 8);
     }
 
-    #[test]
-    fn finds_query_from_start_column() {
-        assert_eq!(find_query_in_line("abcdef", "cd", 0), Some(2));
-        assert_eq!(find_query_in_line("abcdef", "cd", 3), None);
-        assert_eq!(find_query_in_line("abcdef", "", 0), None);
-    }
-
     #[test]
     fn text_cursor_navigation_respects_char_boundaries() {
         let text = "aé🙂b";
@@ -1,3 +1,7 @@
+use std::borrow::Cow;
+
+use unicode_width::UnicodeWidthChar;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CellKind {
     Context,
@@ -113,18 +117,150 @@ pub fn parse_unified_diff(diff_text: &str) -> Vec<DiffRow> {
     rows
 }
 
-pub fn unified_line_count(rows: &[DiffRow]) -> usize {
+/// Text columns each side of the diff wraps at when soft wrapping is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrapWidths {
+    pub old: usize,
+    pub new: usize,
+}
+
+/// Columns between tab stops when diff text is drawn.
+pub const TAB_WIDTH: usize = 4;
+
+/// Terminal columns `ch` covers: two for wide CJK and emoji, none for
+/// combining marks and control characters.
+pub fn char_width(ch: char) -> usize {
+    ch.width().unwrap_or(0)
+}
+
+/// `text` with each tab replaced by spaces up to the next tab stop, given
+/// that it starts `start_col` columns into its line.
+pub fn expand_tabs(text: &str, start_col: usize) -> Cow<'_, str> {
+    if !text.contains('\t') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut expanded = String::with_capacity(text.len() + TAB_WIDTH);
+    let mut col = start_col;
+    for ch in text.chars() {
+        if ch == '\t' {
+            let spaces = TAB_WIDTH - col % TAB_WIDTH;
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+            col += spaces;
+        } else {
+            expanded.push(ch);
+            col += char_width(ch);
+        }
+    }
+    Cow::Owned(expanded)
+}
+
+/// Terminal columns a line of diff text covers once its tabs are expanded.
+pub fn display_width(text: &str) -> usize {
+    expand_tabs(text, 0).chars().map(char_width).sum()
+}
+
+/// Screen lines `text` takes when wrapped every `width` columns. A wide
+/// character that does not fit at the end of a line moves to the next one.
+pub fn wrapped_height(text: &str, width: usize) -> usize {
+    let width = width.max(1);
+    let mut lines = 1;
+    let mut used = 0;
+    for ch in expand_tabs(text, 0).chars() {
+        let ch_width = char_width(ch);
+        if used > 0 && used + ch_width > width {
+            lines += 1;
+            used = 0;
+        }
+        used += ch_width;
+    }
+    lines
+}
+
+/// Screen lines `row` takes: one in the split view, one per side of a
+/// replacement in the unified view, more for each side that wraps.
+pub fn row_display_height(row: &DiffRow, unified: bool, wrap: Option<WrapWidths>) -> usize {
+    let height = |cell: &DiffCell, old_side: bool| match wrap {
+        Some(widths) => wrapped_height(&cell.text, if old_side { widths.old } else { widths.new }),
+        None => 1,
+    };
+
+    if !unified {
+        let old = row.old.as_ref().map_or(1, |cell| height(cell, true));
+        let new = row.new.as_ref().map_or(1, |cell| height(cell, false));
+        return old.max(new);
+    }
+
+    match (&row.old, &row.new) {
+        (Some(old), Some(new)) if old.kind == CellKind::Removed && new.kind == CellKind::Added => {
+            height(old, true) + height(new, false)
+        }
+        (Some(old), Some(new))
+            if (old.kind == CellKind::Meta || new.kind == CellKind::Meta)
+                && !old.text.is_empty() =>
+        {
+            height(old, true)
+        }
+        (_, Some(new)) => height(new, false),
+        (Some(old), None) => height(old, true),
+        (None, None) => 0,
+    }
+}
+
+pub fn display_line_count(rows: &[DiffRow], unified: bool, wrap: Option<WrapWidths>) -> usize {
     rows.iter()
-        .map(|row| match (&row.old, &row.new) {
-            (Some(old), Some(new))
-                if old.kind == CellKind::Removed && new.kind == CellKind::Added =>
-            {
-                2
+        .map(|row| row_display_height(row, unified, wrap))
+        .sum()
+}
+
+/// Display offset of the first row at or after new-side line `line`, counted
+/// the way the split (one per row) or unified (removed and added on their
+/// own lines) view lays rows out, wrapped lines included.
+pub fn display_offset_for_line(
+    rows: &[DiffRow],
+    line: usize,
+    unified: bool,
+    wrap: Option<WrapWidths>,
+) -> Option<usize> {
+    let mut offset = 0;
+    for row in rows {
+        if let Some(new) = &row.new
+            && new.kind != CellKind::Meta
+            && new.line_no.is_some_and(|no| no >= line)
+        {
+            // The unified view puts the removed side of a replacement first.
+            let removed_lines = match &row.old {
+                Some(old)
+                    if unified && old.kind == CellKind::Removed && new.kind == CellKind::Added =>
+                {
+                    wrap.map_or(1, |widths| wrapped_height(&old.text, widths.old))
+                }
+                _ => 0,
+            };
+            return Some(offset + removed_lines);
+        }
+
+        offset += row_display_height(row, unified, wrap);
+    }
+
+    None
+}
+
+/// Digits needed for the largest line number on one side.
+pub fn line_number_width(rows: &[DiffRow], old_side: bool) -> usize {
+    let max_line = rows
+        .iter()
+        .filter_map(|row| {
+            if old_side {
+                row.old.as_ref().and_then(|cell| cell.line_no)
+            } else {
+                row.new.as_ref().and_then(|cell| cell.line_no)
             }
-            (None, None) => 0,
-            _ => 1,
         })
-        .sum()
+        .max()
+        .unwrap_or(1);
+
+    max_line.to_string().len().max(1)
 }
 
 fn flush_change_run(
@@ -172,7 +308,38 @@ fn normalize_line_no(raw: usize) -> Option<usize> {
 
 #[cfg(test)]
 mod tests {
-    use super::{CellKind, DiffCell, DiffRow, parse_unified_diff, unified_line_count};
+    use super::{
+        CellKind, DiffCell, DiffRow, WrapWidths, display_line_count, display_offset_for_line,
+        display_width, parse_unified_diff, wrapped_height,
+    };
+
+    #[test]
+    fn finds_display_offset_for_new_side_line() {
+        let input = "@@ -1,3 +1,3 @@\n-old_a\n+new_a\n keep_b\n keep_c";
+        let rows = parse_unified_diff(input);
+        let first_body = rows
+            .iter()
+            .position(|row| row.new.as_ref().is_some_and(|cell| cell.line_no == Some(1)))
+            .expect("line 1 should be present");
+
+        assert_eq!(
+            display_offset_for_line(&rows, 1, false, None),
+            Some(first_body)
+        );
+        assert_eq!(
+            display_offset_for_line(&rows, 1, true, None),
+            Some(first_body + 1)
+        );
+        assert_eq!(
+            display_offset_for_line(&rows, 2, false, None),
+            Some(first_body + 1)
+        );
+        assert_eq!(
+            display_offset_for_line(&rows, 2, true, None),
+            Some(first_body + 2)
+        );
+        assert_eq!(display_offset_for_line(&rows, 99, false, None), None);
+    }
 
     #[test]
     fn aligns_replaced_line_blocks() {
@@ -241,6 +408,35 @@ mod tests {
             },
         ];
 
-        assert_eq!(unified_line_count(&rows), 3);
+        assert_eq!(display_line_count(&rows, true, None), 3);
+    }
+
+    #[test]
+    fn measures_text_in_terminal_columns() {
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("a\tb"), 5);
+        assert_eq!(display_width("\t\t"), 8);
+
+        assert_eq!(wrapped_height("日本語", 6), 1);
+        assert_eq!(wrapped_height("日本語", 5), 2);
+        assert_eq!(wrapped_height("a日本語b", 5), 2);
+        assert_eq!(wrapped_height("\tx", 4), 2);
+        assert_eq!(wrapped_height("", 4), 1);
+    }
+
+    #[test]
+    fn counts_wrapped_lines_per_view() {
+        let input = "@@ -1,2 +1,2 @@\n-0123456789\n+0123456789abcdefghij\n keep\n";
+        let rows = parse_unified_diff(input);
+        let wrap = Some(WrapWidths { old: 4, new: 8 });
+
+        // Split: the taller side sets the replacement's height (3 vs 3), then
+        // the context line.
+        assert_eq!(display_line_count(&rows, false, wrap), 3 + 1);
+        // Unified: the removed and added sides stack.
+        assert_eq!(display_line_count(&rows, true, wrap), 3 + 3 + 1);
+        assert_eq!(display_offset_for_line(&rows, 1, true, wrap), Some(3));
+        assert_eq!(display_offset_for_line(&rows, 2, false, wrap), Some(3));
     }
 }
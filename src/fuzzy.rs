@@ -0,0 +1,233 @@
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_BASENAME: i64 = 6;
+const BONUS_CONSECUTIVE: i64 = 12;
+const BONUS_FIRST_CHAR: i64 = 8;
+const PENALTY_GAP: i64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices in the candidate that matched the query, in order.
+    pub positions: Vec<usize>,
+}
+
+/// Scores `candidate` against `query` as an ordered subsequence match.
+///
+/// Matching is case-insensitive unless the query contains an uppercase
+/// character. Matches on path/word boundaries, inside the file name, and
+/// consecutive runs score higher; gaps between matched characters cost a
+/// little. Returns `None` when the query is not a subsequence.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let query = query
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .map(|ch| fold_case(ch, case_sensitive))
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let original = candidate.chars().collect::<Vec<_>>();
+    let text = original
+        .iter()
+        .map(|ch| fold_case(*ch, case_sensitive))
+        .collect::<Vec<_>>();
+    if query.len() > text.len() || !is_subsequence(&query, &text) {
+        return None;
+    }
+
+    let basename_start = original
+        .iter()
+        .rposition(|ch| *ch == '/')
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    let bonuses = (0..text.len())
+        .map(|idx| position_bonus(&original, idx, basename_start))
+        .collect::<Vec<_>>();
+
+    let rows = query.len();
+    let cols = text.len();
+    let mut scores = vec![None::<i64>; rows * cols];
+    let mut back = vec![usize::MAX; rows * cols];
+
+    for (qi, qch) in query.iter().enumerate() {
+        // Best `score[qi - 1][k] + k * PENALTY_GAP` over k < j - 1, used to
+        // apply a linear gap penalty without an inner loop.
+        let mut best_gapped: Option<(i64, usize)> = None;
+
+        for j in 0..cols {
+            if qi > 0 && j >= 2 {
+                let k = j - 2;
+                if let Some(prev) = scores[(qi - 1) * cols + k] {
+                    let candidate = prev + k as i64 * PENALTY_GAP;
+                    if best_gapped.is_none_or(|(best, _)| candidate > best) {
+                        best_gapped = Some((candidate, k));
+                    }
+                }
+            }
+
+            if text[j] != *qch {
+                continue;
+            }
+
+            let base = SCORE_MATCH + bonuses[j];
+            if qi == 0 {
+                let first = if j == 0 { BONUS_FIRST_CHAR } else { 0 };
+                scores[j] = Some(base + first);
+                continue;
+            }
+
+            let mut best: Option<(i64, usize)> = None;
+            if j >= 1
+                && let Some(prev) = scores[(qi - 1) * cols + j - 1]
+            {
+                best = Some((prev + base + BONUS_CONSECUTIVE, j - 1));
+            }
+            if let Some((gapped, k)) = best_gapped {
+                let candidate = gapped - (j as i64 - 1) * PENALTY_GAP + base;
+                if best.is_none_or(|(score, _)| candidate > score) {
+                    best = Some((candidate, k));
+                }
+            }
+
+            if let Some((score, prev)) = best {
+                scores[qi * cols + j] = Some(score);
+                back[qi * cols + j] = prev;
+            }
+        }
+    }
+
+    let last_row = (rows - 1) * cols;
+    let (mut end, score) = (0..cols)
+        .filter_map(|j| scores[last_row + j].map(|score| (j, score)))
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+    let mut positions = vec![0; rows];
+    for qi in (0..rows).rev() {
+        positions[qi] = end;
+        if qi > 0 {
+            end = back[qi * cols + end];
+        }
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Ranks candidates against `query`, best match first.
+///
+/// Ties are broken by shorter candidates, then by the original order, so an
+/// empty query keeps the input order intact.
+pub fn rank<'a, I>(query: &str, candidates: I) -> Vec<(usize, FuzzyMatch)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut ranked = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, candidate)| {
+            fuzzy_match(query, candidate).map(|found| (idx, candidate.chars().count(), found))
+        })
+        .collect::<Vec<_>>();
+
+    if !query.trim().is_empty() {
+        ranked.sort_by(|a, b| {
+            b.2.score
+                .cmp(&a.2.score)
+                .then(a.1.cmp(&b.1))
+                .then(a.0.cmp(&b.0))
+        });
+    }
+
+    ranked
+        .into_iter()
+        .map(|(idx, _, found)| (idx, found))
+        .collect()
+}
+
+fn fold_case(ch: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        ch
+    } else {
+        ch.to_lowercase().next().unwrap_or(ch)
+    }
+}
+
+fn is_subsequence(query: &[char], text: &[char]) -> bool {
+    let mut remaining = query.iter().peekable();
+    for ch in text {
+        if remaining.peek() == Some(&ch) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+fn position_bonus(text: &[char], idx: usize, basename_start: usize) -> i64 {
+    let mut bonus = 0;
+    if idx >= basename_start {
+        bonus += BONUS_BASENAME;
+    }
+
+    let boundary = match idx.checked_sub(1).map(|prev| text[prev]) {
+        None => true,
+        Some(prev) => {
+            matches!(prev, '/' | '_' | '-' | '.' | ' ')
+                || (prev.is_lowercase() && text[idx].is_uppercase())
+        }
+    };
+    if boundary {
+        bonus += BONUS_BOUNDARY;
+    }
+
+    bonus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_match, rank};
+
+    #[test]
+    fn matches_ordered_subsequence_case_insensitively() {
+        let found = fuzzy_match("apmd", "src/app/mod.rs").expect("should match");
+        assert_eq!(found.positions.len(), 4);
+        assert!(fuzzy_match("dma", "src/app/mod.rs").is_none());
+        assert!(fuzzy_match("APP", "src/app/mod.rs").is_none());
+        assert!(fuzzy_match("app", "src/APP/mod.rs").is_some());
+    }
+
+    #[test]
+    fn empty_query_matches_everything_without_positions() {
+        let found = fuzzy_match("", "anything").expect("empty query should match");
+        assert_eq!(found.score, 0);
+        assert!(found.positions.is_empty());
+    }
+
+    #[test]
+    fn prefers_consecutive_runs_in_file_name() {
+        let found = fuzzy_match("diff", "src/ui/diff.rs").expect("should match");
+        assert_eq!(found.positions, vec![7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn ranks_basename_and_boundary_matches_first() {
+        let candidates = ["docs/settings/input.md", "src/input.rs", "src/ui/modal.rs"];
+        let ranked = rank("input", candidates.iter().copied());
+        let order = ranked.iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+
+        assert_eq!(order, vec![1, 0]);
+    }
+
+    #[test]
+    fn empty_query_keeps_original_order() {
+        let candidates = ["b", "a", "c"];
+        let ranked = rank("", candidates.iter().copied());
+        let order = ranked.iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+
+        assert_eq!(order, vec![0, 1, 2]);
+    }
+}
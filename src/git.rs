@@ -1,10 +1,16 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 
 use anyhow::{Context, Result, bail};
 
+/// Git treats a file as binary when a NUL byte appears in its first 8000 bytes.
+const BINARY_SNIFF_BYTES: usize = 8000;
+/// Untracked files larger than this are not read to count their lines.
+const LINE_COUNT_MAX_BYTES: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnstagedKind {
     Tracked,
@@ -29,6 +35,29 @@ pub struct RepoStatus {
     pub staged: Vec<String>,
 }
 
+/// Added/removed line counts for one file, as reported by `git diff --numstat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineStat {
+    pub added: usize,
+    pub removed: usize,
+    pub binary: bool,
+    /// Too large to count; `added` is zero rather than the real number.
+    pub uncounted: bool,
+}
+
+impl LineStat {
+    pub fn accumulate(&mut self, other: LineStat) {
+        self.added = self.added.saturating_add(other.added);
+        self.removed = self.removed.saturating_add(other.removed);
+        self.binary |= other.binary;
+        self.uncounted |= other.uncounted;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && !self.binary && !self.uncounted
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiffMode {
     UnstagedTracked,
@@ -63,6 +92,68 @@ pub fn status(repo_root: &Path) -> Result<RepoStatus> {
     Ok(parse_porcelain_status(&output.stdout))
 }
 
+/// Line counts for every changed file on one side, from a single
+/// `git diff --numstat` call.
+pub fn numstat(repo_root: &Path, staged: bool) -> Result<BTreeMap<String, LineStat>> {
+    let mut args = vec!["diff"];
+    if staged {
+        args.push("--cached");
+    }
+    args.extend(["--numstat", "--no-renames", "-z"]);
+
+    let output = run_git(repo_root, &args)?;
+    if !output.status.success() {
+        let side = if staged { "staged" } else { "unstaged" };
+        bail!(git_error(&format!("count {side} line changes"), &output));
+    }
+
+    Ok(parse_numstat(&output.stdout))
+}
+
+/// Counts an untracked file's lines as additions, mirroring what its diff
+/// against `/dev/null` would report. Files over `LINE_COUNT_MAX_BYTES` are
+/// only sniffed for binary content and left uncounted. Directories and
+/// unreadable paths have no stat.
+pub fn untracked_line_stat(repo_root: &Path, path: &str) -> Option<LineStat> {
+    let full_path = repo_root.join(path);
+    let meta = fs::metadata(&full_path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+
+    let uncounted = meta.len() > LINE_COUNT_MAX_BYTES;
+    let bytes = if uncounted {
+        let mut head = Vec::new();
+        fs::File::open(&full_path)
+            .ok()?
+            .take(BINARY_SNIFF_BYTES as u64)
+            .read_to_end(&mut head)
+            .ok()?;
+        head
+    } else {
+        fs::read(&full_path).ok()?
+    };
+    if bytes.iter().take(BINARY_SNIFF_BYTES).any(|byte| *byte == 0) {
+        return Some(LineStat {
+            binary: true,
+            ..LineStat::default()
+        });
+    }
+    if uncounted {
+        return Some(LineStat {
+            uncounted: true,
+            ..LineStat::default()
+        });
+    }
+
+    let newlines = bytes.iter().filter(|byte| **byte == b'\n').count();
+    let trailing = usize::from(bytes.last().is_some_and(|byte| *byte != b'\n'));
+    Some(LineStat {
+        added: newlines + trailing,
+        ..LineStat::default()
+    })
+}
+
 pub fn list_local_branches(repo_root: &Path) -> Result<Vec<BranchEntry>> {
     let output = run_git(
         repo_root,
@@ -110,31 +201,68 @@ pub fn diff_for_file(repo_root: &Path, path: &str, mode: DiffMode) -> Result<Str
     Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
+/// The whole old and new file behind a diff: `HEAD` and index for staged
+/// changes, index and worktree for unstaged ones. A side is `None` when the
+/// file does not exist there, is binary, or is over `max_bytes`.
+pub fn file_versions(
+    repo_root: &Path,
+    path: &str,
+    mode: DiffMode,
+    max_bytes: usize,
+) -> (Option<String>, Option<String>) {
+    let blob = |spec: String| {
+        let output = run_git(repo_root, &["cat-file", "blob", &spec]).ok()?;
+        output.status.success().then_some(output.stdout)
+    };
+    let worktree = || fs::read(repo_root.join(path)).ok();
+
+    let (old, new) = match mode {
+        DiffMode::Staged => (blob(format!("HEAD:{path}")), blob(format!(":{path}"))),
+        DiffMode::UnstagedTracked => (blob(format!(":{path}")), worktree()),
+        DiffMode::Untracked => (None, worktree()),
+    };
+    let text = |bytes: Vec<u8>| {
+        let binary = bytes.iter().take(BINARY_SNIFF_BYTES).any(|byte| *byte == 0);
+        (!binary && bytes.len() <= max_bytes).then(|| String::from_utf8_lossy(&bytes).into_owned())
+    };
+    (old.and_then(text), new.and_then(text))
+}
+
 pub fn stage_file(repo_root: &Path, path: &str) -> Result<()> {
+    stage_paths(repo_root, &[path])
+}
+
+pub fn stage_paths(repo_root: &Path, paths: &[&str]) -> Result<()> {
+    let label = describe_paths(paths);
     let output = Command::new("git")
         .current_dir(repo_root)
         .args(["add", "--"])
-        .arg(path)
+        .args(paths)
         .output()
-        .with_context(|| format!("failed to stage `{path}`"))?;
+        .with_context(|| format!("failed to stage {label}"))?;
 
     if !output.status.success() {
-        bail!(git_error(&format!("stage `{path}`"), &output));
+        bail!(git_error(&format!("stage {label}"), &output));
     }
 
     Ok(())
 }
 
 pub fn unstage_file(repo_root: &Path, path: &str) -> Result<()> {
+    unstage_paths(repo_root, &[path])
+}
+
+pub fn unstage_paths(repo_root: &Path, paths: &[&str]) -> Result<()> {
+    let label = describe_paths(paths);
     let output = Command::new("git")
         .current_dir(repo_root)
         .args(["restore", "--staged", "--"])
-        .arg(path)
+        .args(paths)
         .output()
-        .with_context(|| format!("failed to unstage `{path}`"))?;
+        .with_context(|| format!("failed to unstage {label}"))?;
 
     if !output.status.success() {
-        bail!(git_error(&format!("unstage `{path}`"), &output));
+        bail!(git_error(&format!("unstage {label}"), &output));
     }
 
     Ok(())
@@ -287,6 +415,62 @@ pub fn undo_file_to_mainline(repo_root: &Path, path: &str, was_untracked: bool)
     ));
 }
 
+/// Drops unstaged edits by restoring `path` from the index; staged changes
+/// are kept. Untracked files have no index entry, so they are removed.
+pub fn discard_worktree_changes(repo_root: &Path, path: &str, was_untracked: bool) -> Result<()> {
+    if was_untracked {
+        return remove_untracked(repo_root, path);
+    }
+
+    let output = run_git(repo_root, &["restore", "--worktree", "--", path])?;
+    if !output.status.success() {
+        bail!(git_error(&format!("discard changes to `{path}`"), &output));
+    }
+
+    Ok(())
+}
+
+/// Restores both index and working tree of `path` from `HEAD`, which
+/// deletes a file `HEAD` does not have. Untracked files are removed.
+pub fn reset_file_to_head(repo_root: &Path, path: &str, was_untracked: bool) -> Result<()> {
+    if was_untracked {
+        return remove_untracked(repo_root, path);
+    }
+
+    let output = run_git(
+        repo_root,
+        &[
+            "restore",
+            "--source",
+            "HEAD",
+            "--staged",
+            "--worktree",
+            "--",
+            path,
+        ],
+    )?;
+    if !output.status.success() {
+        bail!(git_error(&format!("reset `{path}` to HEAD"), &output));
+    }
+
+    Ok(())
+}
+
+/// Whether `HEAD` has `path`; false before the first commit too.
+pub fn exists_in_head(repo_root: &Path, path: &str) -> Result<bool> {
+    let output = run_git(repo_root, &["cat-file", "-e", &format!("HEAD:{path}")])?;
+    Ok(output.status.success())
+}
+
+fn remove_untracked(repo_root: &Path, path: &str) -> Result<()> {
+    let output = run_git(repo_root, &["clean", "-f", "--", path])?;
+    if !output.status.success() {
+        bail!(git_error(&format!("remove untracked `{path}`"), &output));
+    }
+
+    Ok(())
+}
+
 fn run_git(repo_root: &Path, args: &[&str]) -> Result<Output> {
     Command::new("git")
         .current_dir(repo_root)
@@ -440,6 +624,44 @@ fn parse_porcelain_status(raw: &[u8]) -> RepoStatus {
     RepoStatus { unstaged, staged }
 }
 
+fn parse_numstat(raw: &[u8]) -> BTreeMap<String, LineStat> {
+    let mut stats = BTreeMap::new();
+
+    for record in parse_nul_terminated(raw) {
+        let mut fields = record.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if path.is_empty() {
+            continue;
+        }
+
+        let stat = match (added.parse::<usize>(), removed.parse::<usize>()) {
+            (Ok(added), Ok(removed)) => LineStat {
+                added,
+                removed,
+                ..LineStat::default()
+            },
+            _ => LineStat {
+                binary: true,
+                ..LineStat::default()
+            },
+        };
+        stats.insert(path.to_owned(), stat);
+    }
+
+    stats
+}
+
+fn describe_paths(paths: &[&str]) -> String {
+    match paths {
+        [single] => format!("`{single}`"),
+        _ => format!("{} files", paths.len()),
+    }
+}
+
 fn null_device_path() -> &'static str {
     if cfg!(windows) { "NUL" } else { "/dev/null" }
 }
@@ -462,7 +684,8 @@ fn has_meaningful_commit_content(message: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        UnstagedKind, has_meaningful_commit_content, parse_branch_listing, parse_porcelain_status,
+        LineStat, UnstagedKind, has_meaningful_commit_content, parse_branch_listing, parse_numstat,
+        parse_porcelain_status,
     };
 
     #[test]
@@ -489,6 +712,23 @@ mod tests {
         assert!(status.unstaged.is_empty());
     }
 
+    #[test]
+    fn parses_numstat_counts_and_binary_entries() {
+        let raw = b"12\t3\tsrc/app.rs\x000\t7\tgone.txt\x00-\t-\tlogo.png\x00";
+        let stats = parse_numstat(raw);
+
+        assert_eq!(
+            stats.get("src/app.rs"),
+            Some(&LineStat {
+                added: 12,
+                removed: 3,
+                ..LineStat::default()
+            })
+        );
+        assert!(stats.get("logo.png").is_some_and(|stat| stat.binary));
+        assert_eq!(stats.get("gone.txt").map(|stat| stat.removed), Some(7));
+    }
+
     #[test]
     fn parses_branch_listing_with_head_markers() {
         let raw = b"*\tmain\n \tfeature/login\n";
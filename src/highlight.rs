@@ -1,5 +1,9 @@
-use std::collections::BTreeMap;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
 use std::path::Path;
+use std::rc::Rc;
 
 use anyhow::{Context, Result};
 use ratatui::style::{Color, Style};
@@ -8,20 +12,164 @@ use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::highlighting::{Style as SyntectStyle, Theme};
 use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
 
-use crate::settings::AppTheme;
+use crate::settings::{AppSettings, AppTheme, CustomTheme};
+
+/// Files larger than this are highlighted from the diff's own lines instead.
+pub const MAX_SOURCE_BYTES: usize = 512 * 1024;
+
+/// Used when syntect cannot parse a line.
+const PLAIN_TEXT_RGB: (u8, u8, u8) = (224, 228, 236);
+
+/// Highlighted files kept around, enough for both sides of a few diffs.
+const FILE_CACHE_SIZE: usize = 8;
 
 pub struct Highlighter {
     syntax_set: SyntaxSet,
-    ocean_theme: Theme,
-    eighties_theme: Theme,
-    solarized_theme: Theme,
-    monokai_theme: Theme,
+    ocean_theme: ThemePair,
+    eighties_theme: ThemePair,
+    solarized_theme: ThemePair,
+    monokai_theme: ThemePair,
+    /// `.tmTheme` files from `[themes]`, by theme name.
+    custom_themes: BTreeMap<String, Theme>,
+    /// `[syntaxes]` globs and the syntax each one picks.
+    syntax_globs: Vec<(String, String)>,
+    /// Most recently used first.
+    file_cache: RefCell<Vec<(FileKey, Rc<HighlightedFile>)>>,
+    /// The app refresh the cache was last pruned for.
+    cache_generation: Cell<u64>,
+}
+
+/// One side of the selected diff as text to highlight: the whole file, so
+/// highlighting can start from line 1 instead of mid-hunk, or just the
+/// diff's lines when the whole file is unavailable.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    hash: u64,
+    text: String,
+    /// File line number of each line of `text`; `None` when `text` is the
+    /// whole file.
+    line_numbers: Option<Vec<usize>>,
+}
+
+impl SourceFile {
+    pub fn new(text: String) -> Self {
+        Self::build(text, None)
+    }
+
+    /// Stitches numbered lines from the diff together in file order.
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = (usize, &'a str)>) -> Self {
+        let mut text = String::new();
+        let mut line_numbers = Vec::new();
+        for (line_no, line) in lines {
+            text.push_str(line);
+            text.push('\n');
+            line_numbers.push(line_no);
+        }
+        Self::build(text, Some(line_numbers))
+    }
+
+    fn build(text: String, line_numbers: Option<Vec<usize>>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        line_numbers.hash(&mut hasher);
+        Self {
+            hash: hasher.finish(),
+            text,
+            line_numbers,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The file's line 1, for shebang detection; `None` when the diff's
+    /// lines start further down, since their first line is no shebang.
+    pub fn first_line(&self) -> Option<&str> {
+        match &self.line_numbers {
+            Some(numbers) if numbers.first() != Some(&1) => None,
+            _ => self.text.lines().next(),
+        }
+    }
 }
 
-pub struct LineHighlighter<'a> {
-    syntax_set: &'a SyntaxSet,
-    highlighter: HighlightLines<'a>,
+/// A run of text and its syntax color.
+type Segment = ((u8, u8, u8), String);
+
+/// A syntax color and the background it sits on.
+type ColorPair = ((u8, u8, u8), (u8, u8, u8));
+
+/// Syntax colors for every line of a file, computed once per blob and theme.
+#[derive(Debug)]
+pub struct HighlightedFile {
+    lines: Vec<Vec<Segment>>,
+    line_numbers: Option<Vec<usize>>,
+    /// Contrast-adjusted colors by (syntax color, row background); a theme
+    /// only has a handful of each.
+    adjusted: RefCell<HashMap<ColorPair, (u8, u8, u8)>>,
+}
+
+impl HighlightedFile {
+    /// Spans for 1-based `line_no`, or `None` when the file has something
+    /// other than `text` there.
+    pub fn spans(
+        &self,
+        line_no: usize,
+        text: &str,
+        background_rgb: (u8, u8, u8),
+    ) -> Option<Vec<Span<'static>>> {
+        let idx = match &self.line_numbers {
+            Some(numbers) => numbers.binary_search(&line_no).ok()?,
+            None => line_no.checked_sub(1)?,
+        };
+        let segments = self.lines.get(idx)?;
+        let mut rest = text;
+        for (_, segment) in segments {
+            rest = rest.strip_prefix(segment.as_str())?;
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+
+        if segments.is_empty() {
+            return Some(vec![Span::raw(String::new())]);
+        }
+        let mut adjusted = self.adjusted.borrow_mut();
+        Some(
+            segments
+                .iter()
+                .map(|(color, segment)| {
+                    let fg = *adjusted
+                        .entry((*color, background_rgb))
+                        .or_insert_with(|| ensure_contrast(*color, background_rgb));
+                    Span::styled(
+                        segment.clone(),
+                        Style::default().fg(Color::Rgb(fg.0, fg.1, fg.2)),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileKey {
+    path: Option<String>,
+    hash: u64,
+    theme: ThemeKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ThemeKey {
+    Custom(String),
+    Builtin(AppTheme, bool),
+}
+
+struct ThemePair {
+    dark: Theme,
+    light: Theme,
 }
 
 impl Highlighter {
@@ -34,25 +182,25 @@ impl Highlighter {
             .cloned()
             .context("syntect default themes should not be empty")?;
 
-        let ocean_theme = pick_theme(
-            &themes,
+        let pair = |dark: &[&str], light: &[&str]| ThemePair {
+            dark: pick_theme(&themes, dark, &fallback),
+            light: pick_theme(&themes, light, &fallback),
+        };
+        let ocean_theme = pair(
             &["base16-ocean.dark", "base16-ocean.light"],
-            &fallback,
+            &["base16-ocean.light", "InspiredGitHub"],
         );
-        let eighties_theme = pick_theme(
-            &themes,
+        let eighties_theme = pair(
             &["base16-eighties.dark", "base16-eighties.light"],
-            &fallback,
+            &["base16-eighties.light", "InspiredGitHub"],
         );
-        let solarized_theme = pick_theme(
-            &themes,
+        let solarized_theme = pair(
             &["Solarized (dark)", "Solarized (light)"],
-            &fallback,
+            &["Solarized (light)", "InspiredGitHub"],
         );
-        let monokai_theme = pick_theme(
-            &themes,
+        let monokai_theme = pair(
             &["Monokai Extended", "Monokai Extended Bright"],
-            &fallback,
+            &["Monokai Extended Light", "InspiredGitHub"],
         );
 
         Ok(Self {
@@ -61,70 +209,240 @@ impl Highlighter {
             eighties_theme,
             solarized_theme,
             monokai_theme,
+            custom_themes: BTreeMap::new(),
+            syntax_globs: Vec::new(),
+            file_cache: RefCell::new(Vec::new()),
+            cache_generation: Cell::new(0),
         })
     }
 
-    pub fn begin<'a>(&'a self, path: Option<&str>, app_theme: AppTheme) -> LineHighlighter<'a> {
-        let syntax = self.syntax_for_path(path);
-        LineHighlighter {
-            syntax_set: &self.syntax_set,
-            highlighter: HighlightLines::new(syntax, self.theme_for(app_theme)),
+    /// Adds the `.sublime-syntax` files under `dir` to the defaults and takes
+    /// the `[syntaxes]` glob mappings, returning a message for each folder or
+    /// mapping that cannot be used.
+    pub fn load_syntaxes(
+        &mut self,
+        dir: Option<&Path>,
+        globs: &BTreeMap<String, String>,
+    ) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+            let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+            match builder.add_from_folder(dir, true) {
+                Ok(()) => self.syntax_set = builder.build(),
+                Err(error) => problems.push(format!("cannot load `{}`: {error}", dir.display())),
+            }
+        }
+
+        self.syntax_globs.clear();
+        for (glob, syntax) in globs {
+            if self.find_syntax(syntax).is_some() {
+                self.syntax_globs.push((glob.clone(), syntax.clone()));
+            } else {
+                problems.push(format!("{glob}: no syntax called `{syntax}`"));
+            }
+        }
+        problems
+    }
+
+    /// Loads the `syntax_theme` of each custom theme, returning a message for
+    /// each one that fails; those fall back to their inherited theme.
+    pub fn load_custom_themes(&mut self, themes: &BTreeMap<String, CustomTheme>) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, custom) in themes {
+            let Some(path) = custom.syntax_theme_path() else {
+                continue;
+            };
+            match ThemeSet::get_theme(&path) {
+                Ok(theme) => {
+                    self.custom_themes.insert(name.clone(), theme);
+                }
+                Err(error) => {
+                    problems.push(format!("{name}: cannot load `{}`: {error}", path.display()))
+                }
+            }
+        }
+        problems
+    }
+
+    /// Highlights all of `source` from its first line, so a hunk that starts
+    /// inside a block comment or multi-line string is colored as such. The
+    /// result is cached by path, contents and theme, so redraws only look
+    /// lines up.
+    pub fn highlight_file(
+        &self,
+        path: Option<&str>,
+        source: &SourceFile,
+        settings: &AppSettings,
+        light: bool,
+    ) -> Rc<HighlightedFile> {
+        let syntax = self.syntax_for(path, source.first_line());
+        let (theme, theme_key) = self.active_theme(settings, light);
+        let key = FileKey {
+            path: path.map(ToOwned::to_owned),
+            hash: source.hash,
+            theme: theme_key,
+        };
+
+        let mut cache = self.file_cache.borrow_mut();
+        if let Some(idx) = cache.iter().position(|(cached, _)| *cached == key) {
+            let entry = cache.remove(idx);
+            let file = Rc::clone(&entry.1);
+            cache.insert(0, entry);
+            return file;
+        }
+
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let lines = LinesWithEndings::from(&source.text)
+            .map(|line| {
+                let text = line.trim_end_matches(['\n', '\r']);
+                match highlighter.highlight_line(line, &self.syntax_set) {
+                    Ok(ranges) => trim_line_ending(
+                        ranges
+                            .into_iter()
+                            .map(|(style, segment)| (color_from_syntect(style), segment))
+                            .collect(),
+                        text.len(),
+                    ),
+                    Err(_) => vec![(PLAIN_TEXT_RGB, text.to_owned())],
+                }
+            })
+            .collect();
+
+        let file = Rc::new(HighlightedFile {
+            lines,
+            line_numbers: source.line_numbers.clone(),
+            adjusted: RefCell::new(HashMap::new()),
+        });
+        cache.insert(0, (key, Rc::clone(&file)));
+        cache.truncate(FILE_CACHE_SIZE);
+        file
+    }
+
+    /// Once per app refresh, drops cached files other than `sources`, which
+    /// are what the refreshed diff shows.
+    pub fn prune_cache(&self, generation: u64, sources: &[&SourceFile]) {
+        if self.cache_generation.replace(generation) == generation {
+            return;
         }
+        self.file_cache
+            .borrow_mut()
+            .retain(|(key, _)| sources.iter().any(|source| source.hash == key.hash));
     }
 
-    fn theme_for(&self, theme: AppTheme) -> &Theme {
-        match theme {
+    fn active_theme(&self, settings: &AppSettings, light: bool) -> (&Theme, ThemeKey) {
+        if let Some((name, theme)) = settings
+            .active_custom_theme()
+            .and_then(|(name, _)| Some((name, self.custom_themes.get(name)?)))
+        {
+            return (theme, ThemeKey::Custom(name.to_owned()));
+        }
+        let base = settings.base_theme();
+        (self.theme_for(base, light), ThemeKey::Builtin(base, light))
+    }
+
+    fn theme_for(&self, theme: AppTheme, light: bool) -> &Theme {
+        let pair = match theme {
             AppTheme::Ocean => &self.ocean_theme,
             AppTheme::Eighties => &self.eighties_theme,
             AppTheme::Solarized => &self.solarized_theme,
             AppTheme::Monokai => &self.monokai_theme,
-        }
+        };
+        if light { &pair.light } else { &pair.dark }
     }
 
-    fn syntax_for_path(&self, path: Option<&str>) -> &SyntaxReference {
-        let plain = self.syntax_set.find_syntax_plain_text();
-
-        let Some(path) = path else {
-            return plain;
-        };
+    /// The `[syntaxes]` globs win, then the extension or whole file name,
+    /// then the first line (shebangs, modelines).
+    pub fn syntax_for(&self, path: Option<&str>, first_line: Option<&str>) -> &SyntaxReference {
+        if let Some(path) = path {
+            if let Some(syntax) = self
+                .syntax_globs
+                .iter()
+                .find(|(glob, _)| glob_matches(glob, path))
+                .and_then(|(_, syntax)| self.find_syntax(syntax))
+            {
+                return syntax;
+            }
 
-        let file_path = Path::new(path);
-        if let Some(ext) = file_path.extension().and_then(|value| value.to_str())
-            && let Some(syntax) = self.syntax_set.find_syntax_by_extension(ext)
-        {
-            return syntax;
+            let file_path = Path::new(path);
+            let by_name = file_path
+                .extension()
+                .and_then(|value| value.to_str())
+                .and_then(|ext| self.syntax_set.find_syntax_by_extension(ext))
+                .or_else(|| {
+                    file_path
+                        .file_name()
+                        .and_then(|value| value.to_str())
+                        .and_then(|name| self.syntax_set.find_syntax_by_extension(name))
+                });
+            if let Some(syntax) = by_name {
+                return syntax;
+            }
         }
 
-        plain
+        first_line
+            .and_then(|line| self.syntax_set.find_syntax_by_first_line(line))
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
+    }
+
+    /// A syntax by name (`TypeScript`) or by one of its extensions (`ts`).
+    fn find_syntax(&self, name: &str) -> Option<&SyntaxReference> {
+        self.syntax_set
+            .find_syntax_by_name(name)
+            .or_else(|| {
+                self.syntax_set
+                    .syntaxes()
+                    .iter()
+                    .find(|syntax| syntax.name.eq_ignore_ascii_case(name))
+            })
+            .or_else(|| self.syntax_set.find_syntax_by_extension(name))
     }
 }
 
-impl LineHighlighter<'_> {
-    pub fn highlight(&mut self, line: &str, background_rgb: (u8, u8, u8)) -> Vec<Span<'static>> {
-        if line.is_empty() {
-            return vec![Span::raw(String::new())];
-        }
+/// Matches `path` against a `[syntaxes]` glob: `*` and `?` stay within one
+/// path segment, `**` crosses them. Globs without a `/` match the file name.
+fn glob_matches(glob: &str, path: &str) -> bool {
+    let target = if glob.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    wildcard_match(glob.as_bytes(), target.as_bytes())
+}
 
-        match self.highlighter.highlight_line(line, self.syntax_set) {
-            Ok(ranges) => ranges
-                .into_iter()
-                .map(|(style, segment)| {
-                    let fg_rgb = color_from_syntect(style);
-                    let adjusted = ensure_contrast(fg_rgb, background_rgb);
-                    Span::styled(
-                        segment.to_owned(),
-                        Style::default().fg(Color::Rgb(adjusted.0, adjusted.1, adjusted.2)),
-                    )
-                })
-                .collect(),
-            Err(_) => vec![Span::styled(
-                line.to_owned(),
-                Style::default().fg(Color::Rgb(224, 228, 236)),
-            )],
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|skip| wildcard_match(rest, &text[skip..]))
+        }
+        [b'*', rest @ ..] => {
+            let segment = text
+                .iter()
+                .position(|byte| *byte == b'/')
+                .unwrap_or(text.len());
+            (0..=segment).any(|skip| wildcard_match(rest, &text[skip..]))
+        }
+        [b'?', rest @ ..] => {
+            matches!(text.first(), Some(byte) if *byte != b'/') && wildcard_match(rest, &text[1..])
         }
+        [byte, rest @ ..] => text.first() == Some(byte) && wildcard_match(rest, &text[1..]),
     }
 }
 
+/// Drops the line ending syntect was fed, leaving `len` bytes of text.
+fn trim_line_ending(ranges: Vec<((u8, u8, u8), &str)>, len: usize) -> Vec<Segment> {
+    let mut taken = 0;
+    ranges
+        .into_iter()
+        .filter_map(|(color, segment)| {
+            let keep = segment.len().min(len - taken);
+            taken += keep;
+            (keep > 0).then(|| (color, segment[..keep].to_owned()))
+        })
+        .collect()
+}
+
 fn pick_theme(themes: &BTreeMap<String, Theme>, preferred: &[&str], fallback: &Theme) -> Theme {
     preferred
         .iter()
@@ -200,3 +518,135 @@ fn channel_luminance(channel: u8) -> f32 {
         ((normalized + 0.055) / 1.055).powf(2.4)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use std::rc::Rc;
+
+    use super::{Highlighter, SourceFile, glob_matches};
+    use crate::settings::AppSettings;
+
+    #[test]
+    fn globs_match_names_or_whole_paths() {
+        assert!(glob_matches("*.tsx", "web/src/App.tsx"));
+        assert!(glob_matches("Justfile", "tools/Justfile"));
+        assert!(!glob_matches("*.tf", "main.tfvars"));
+        assert!(glob_matches("infra/*.tf", "infra/main.tf"));
+        assert!(!glob_matches("infra/*.tf", "infra/modules/net.tf"));
+        assert!(glob_matches("infra/**/*.tf", "infra/modules/net.tf"));
+        assert!(glob_matches("infra/**/*.tf", "infra/main.tf"));
+        assert!(glob_matches("?akefile", "Makefile"));
+    }
+
+    #[test]
+    fn picks_syntax_from_globs_then_name_then_shebang() {
+        let mut highlighter = Highlighter::new().expect("highlighter should load");
+        let globs = BTreeMap::from([
+            (String::from("Justfile"), String::from("Makefile")),
+            (String::from("*.conf"), String::from("sh")),
+            (String::from("*.nope"), String::from("NoSuchLanguage")),
+        ]);
+        let problems = highlighter.load_syntaxes(None, &globs);
+        assert_eq!(problems, ["*.nope: no syntax called `NoSuchLanguage`"]);
+
+        let name = |path: Option<&str>, first: Option<&str>| {
+            highlighter.syntax_for(path, first).name.clone()
+        };
+        assert_eq!(name(Some("Justfile"), None), "Makefile");
+        assert_eq!(
+            name(Some("etc/app.conf"), None),
+            "Bourne Again Shell (bash)"
+        );
+        assert_eq!(name(Some("src/lib.rs"), None), "Rust");
+        assert_eq!(name(Some("build/Makefile"), None), "Makefile");
+        assert_eq!(
+            name(Some("bin/deploy"), Some("#!/usr/bin/env python3\n")),
+            "Python"
+        );
+        assert_eq!(name(Some("notes"), Some("plain words\n")), "Plain Text");
+
+        // Each side of a diff detects its syntax from its own line 1.
+        let old = SourceFile::new(String::from("#!/bin/bash\necho hi\n"));
+        let new = SourceFile::from_lines([(1, "#!/usr/bin/env python3"), (2, "print(1)")]);
+        let hunk = SourceFile::from_lines([(5, "#!/usr/bin/env python3")]);
+        assert_eq!(
+            name(Some("bin/run"), old.first_line()),
+            "Bourne Again Shell (bash)"
+        );
+        assert_eq!(name(Some("bin/run"), new.first_line()), "Python");
+        assert_eq!(hunk.first_line(), None);
+    }
+
+    #[test]
+    fn loads_extra_syntaxes_from_a_folder() {
+        let dir = tempfile::tempdir().expect("tempdir should be created");
+        std::fs::write(
+            dir.path().join("Terraform.sublime-syntax"),
+            "%YAML 1.2\n---\nname: Terraform\nfile_extensions: [tf]\nscope: source.terraform\ncontexts:\n  main:\n    - match: '#.*$'\n      scope: comment.line.terraform\n",
+        )
+        .expect("syntax file should be written");
+
+        let mut highlighter = Highlighter::new().expect("highlighter should load");
+        assert_eq!(
+            highlighter.syntax_for(Some("main.tf"), None).name,
+            "Plain Text"
+        );
+        let problems = highlighter.load_syntaxes(Some(dir.path()), &BTreeMap::new());
+
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(
+            highlighter.syntax_for(Some("infra/main.tf"), None).name,
+            "Terraform"
+        );
+        assert_eq!(
+            highlighter.syntax_for(Some("src/lib.rs"), None).name,
+            "Rust"
+        );
+    }
+
+    #[test]
+    fn whole_file_highlighting_keeps_block_comment_state() {
+        let highlighter = Highlighter::new().expect("highlighter should load");
+        let settings = AppSettings::default();
+        let source = SourceFile::new(String::from("/*\n let x = 1;\n*/\nlet y = 2;\n"));
+        let file = highlighter.highlight_file(Some("src/lib.rs"), &source, &settings, false);
+        let background = (0, 0, 0);
+        let color = |line: usize, text: &str| {
+            file.spans(line, text, background)
+                .expect("line should match")[0]
+                .style
+                .fg
+        };
+
+        // Inside the comment `let` takes the comment color, after it the
+        // keyword color; hunk-by-hunk highlighting would color both as code.
+        assert_eq!(color(2, " let x = 1;"), color(1, "/*"));
+        assert_ne!(color(4, "let y = 2;"), color(1, "/*"));
+        assert!(file.spans(4, "let y = 3;", background).is_none());
+        assert!(file.spans(9, "", background).is_none());
+
+        let again = highlighter.highlight_file(Some("src/lib.rs"), &source, &settings, false);
+        assert!(Rc::ptr_eq(&file, &again));
+    }
+
+    #[test]
+    fn diff_line_sources_map_file_line_numbers_and_prune_on_refresh() {
+        let highlighter = Highlighter::new().expect("highlighter should load");
+        let settings = AppSettings::default();
+        let source = SourceFile::from_lines([(40, "fn a() {}"), (41, ""), (97, "fn b() {}")]);
+        let file = highlighter.highlight_file(Some("src/lib.rs"), &source, &settings, false);
+
+        assert!(file.spans(97, "fn b() {}", (0, 0, 0)).is_some());
+        assert!(file.spans(41, "", (0, 0, 0)).is_some());
+        assert!(file.spans(3, "fn b() {}", (0, 0, 0)).is_none());
+
+        let other = SourceFile::new(String::from("fn c() {}\n"));
+        highlighter.highlight_file(Some("src/c.rs"), &other, &settings, false);
+        highlighter.prune_cache(1, &[&source]);
+        let kept = highlighter.highlight_file(Some("src/lib.rs"), &source, &settings, false);
+        assert!(Rc::ptr_eq(&file, &kept));
+        assert_eq!(highlighter.file_cache.borrow().len(), 1);
+    }
+}
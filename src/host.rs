@@ -0,0 +1,168 @@
+//! Questions dif asks the host terminal it runs in.
+
+use std::env;
+use std::time::Duration;
+
+/// Asks the host terminal for its background color with OSC 11. Needs raw
+/// mode and must not race the event loop for input. Waits up to `timeout`
+/// for the device attributes reply that follows, so late answers never reach
+/// the event loop as keys. Falls back to `$COLORFGBG` when the terminal does
+/// not answer.
+///
+/// Only call this at startup, before the event loop reads input: keys typed
+/// during the wait are read along with the replies and dropped, and when the
+/// terminal never answers, pending input is discarded with
+/// `tcflush(TCIFLUSH)`.
+pub fn background_color(timeout: Duration) -> Option<(u8, u8, u8)> {
+    query_background(timeout).or_else(env_background)
+}
+
+/// The background `$COLORFGBG` names, without asking the terminal.
+pub fn env_background() -> Option<(u8, u8, u8)> {
+    env::var("COLORFGBG")
+        .ok()
+        .and_then(|value| colorfgbg_background(&value))
+}
+
+/// Whether text on `background` needs a light theme.
+pub fn is_light(background: (u8, u8, u8)) -> bool {
+    let (r, g, b) = background;
+    let luma = 299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b);
+    luma > 128_000
+}
+
+#[cfg(unix)]
+fn query_background(timeout: Duration) -> Option<(u8, u8, u8)> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    // Every terminal answers the device attributes query that follows, so
+    // its reply ends the wait early when OSC 11 goes unanswered.
+    tty.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 256];
+    while !has_device_attributes(&reply) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+
+        let mut fds = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let millis = remaining.as_millis().clamp(1, i32::MAX as u128) as i32;
+        // SAFETY: `fds` is one valid pollfd for the duration of the call.
+        if unsafe { libc::poll(&mut fds, 1, millis) } <= 0 {
+            break;
+        }
+        match tty.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => reply.extend_from_slice(&buf[..read]),
+        }
+    }
+    if !has_device_attributes(&reply) {
+        // Whatever is still on its way would otherwise be read as keys.
+        // SAFETY: the descriptor stays open for the duration of the call.
+        unsafe { libc::tcflush(tty.as_raw_fd(), libc::TCIFLUSH) };
+    }
+
+    parse_osc11_reply(&reply)
+}
+
+#[cfg(not(unix))]
+fn query_background(_timeout: Duration) -> Option<(u8, u8, u8)> {
+    None
+}
+
+/// True once `reply` holds a full `CSI ? ... c` device attributes answer.
+fn has_device_attributes(reply: &[u8]) -> bool {
+    reply.windows(3).enumerate().any(|(start, window)| {
+        window == b"\x1b[?"
+            && reply[start + 3..]
+                .iter()
+                .find(|byte| !byte.is_ascii_digit() && **byte != b';')
+                == Some(&b'c')
+    })
+}
+
+/// Parses `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` terminated by BEL or ST; each
+/// channel has one to four hex digits.
+fn parse_osc11_reply(reply: &[u8]) -> Option<(u8, u8, u8)> {
+    let text = String::from_utf8_lossy(reply);
+    let start = text.find("\x1b]11;")? + "\x1b]11;".len();
+    let body = &text[start..];
+    let end = body.find(['\x07', '\x1b'])?;
+    let spec = body[..end].strip_prefix("rgb:")?;
+
+    let mut channels = spec.split('/').map(|hex| {
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = match hex.len() {
+            1..=4 => (1u32 << (4 * hex.len())) - 1,
+            _ => return None,
+        };
+        Some(((value * 255 + max / 2) / max) as u8)
+    });
+    let color = (channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(color)
+}
+
+/// `$COLORFGBG` is `fg;bg` (sometimes `fg;default;bg`) in ANSI color
+/// numbers; 7 and 15 are the light backgrounds.
+fn colorfgbg_background(value: &str) -> Option<(u8, u8, u8)> {
+    let bg = value.rsplit(';').next()?.parse::<u8>().ok()?;
+    Some(match bg {
+        7 | 15 => (229, 229, 229),
+        0..=15 => (0, 0, 0),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{colorfgbg_background, has_device_attributes, is_light, parse_osc11_reply};
+
+    #[test]
+    fn parses_osc11_replies_with_either_terminator() {
+        assert_eq!(
+            parse_osc11_reply(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22c"),
+            Some((255, 255, 255))
+        );
+        assert_eq!(
+            parse_osc11_reply(b"\x1b]11;rgb:1e1e/2020/2828\x07"),
+            Some((30, 32, 40))
+        );
+        assert_eq!(
+            parse_osc11_reply(b"\x1b]11;rgb:f/8/0\x07"),
+            Some((255, 136, 0))
+        );
+        assert_eq!(parse_osc11_reply(b"\x1b[?62;22c"), None);
+        assert_eq!(parse_osc11_reply(b"\x1b]11;rgb:ff/ff\x07"), None);
+    }
+
+    #[test]
+    fn device_attributes_reply_ends_the_query() {
+        assert!(has_device_attributes(b"\x1b]11;rgb:0/0/0\x07\x1b[?1;2c"));
+        assert!(!has_device_attributes(b"\x1b]11;rgb:0/0/0\x07\x1b[?1;2"));
+    }
+
+    #[test]
+    fn classifies_backgrounds() {
+        assert!(is_light((253, 246, 227)));
+        assert!(!is_light((0, 43, 54)));
+        assert_eq!(colorfgbg_background("0;15"), Some((229, 229, 229)));
+        assert_eq!(colorfgbg_background("15;default;0"), Some((0, 0, 0)));
+        assert_eq!(colorfgbg_background("garbage"), None);
+    }
+}
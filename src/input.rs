@@ -4,33 +4,7 @@ use crossterm::event::{
 };
 
 use crate::app::{App, GitPanelMode};
-use crate::keymap;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum MainKeyAction {
-    CycleFocusForward,
-    CycleFocusBackward,
-    MoveUp,
-    MoveDown,
-    TogglePaneFocus,
-    PageUp,
-    PageDown,
-    JumpTop,
-    JumpBottom,
-    StageToggle,
-    Stage,
-    Unstage,
-    UndoToMainline,
-    CycleDiffView,
-    ToggleSidebar,
-    SidebarNarrow,
-    SidebarWide,
-    ToggleSettings,
-    ToggleGitPanel,
-    OpenCommitPrompt,
-    OpenTerminal,
-    Refresh,
-}
+use crate::keymap::{Action, KeyContext, Keymap};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SettingsKeyAction {
@@ -56,7 +30,7 @@ pub fn handle_event(app: &mut App, event: Event) -> bool {
         if let Event::Key(key) = &event
             && key.kind == KeyEventKind::Press
         {
-            handle_help_key(app, key.code);
+            handle_help_key(app, *key);
         }
         return true;
     }
@@ -70,14 +44,42 @@ pub fn handle_event(app: &mut App, event: Event) -> bool {
         return true;
     }
 
+    if app.has_pending_repo_trust() {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            handle_pending_repo_trust_key(app, key.code);
+        }
+        return true;
+    }
+
     match event {
         Event::Key(key) if key.kind == KeyEventKind::Press => {
-            if app.terminal_open {
+            if app.command_palette_open {
+                return handle_command_palette_key(app, key);
+            }
+
+            if app.terminal_has_focus() {
                 handle_terminal_key(app, key);
                 return true;
             }
 
-            if key.code == KeyCode::Char(keymap::KEY_QUIT) {
+            if app.finder_open {
+                handle_finder_key(app, key);
+                return true;
+            }
+
+            if app.tree_filter_editing {
+                handle_tree_filter_key(app, key);
+                return true;
+            }
+
+            if app.task_picker_open {
+                handle_task_picker_key(app, key);
+                return true;
+            }
+
+            if app.keymap.matches(Action::Quit, key) {
                 return false;
             }
 
@@ -87,16 +89,20 @@ pub fn handle_event(app: &mut App, event: Event) -> bool {
             }
 
             if app.settings_open {
-                handle_settings_key(app, key.code);
+                handle_settings_key(app, key);
                 return true;
             }
 
-            if let Some(action) = map_main_key(key.code) {
+            if let Some(action) = map_main_key(&app.keymap, key) {
                 run_main_action(app, action);
             }
         }
-        Event::Paste(text) if app.terminal_open => {
-            if app.terminal_search_open {
+        Event::Paste(text) if app.terminal_has_focus() => {
+            if app.terminal_rename_open {
+                for ch in text.chars() {
+                    app.terminal_rename_append(ch);
+                }
+            } else if app.terminal_search_open() {
                 for ch in text.chars() {
                     app.terminal_search_append(ch);
                 }
@@ -105,17 +111,61 @@ pub fn handle_event(app: &mut App, event: Event) -> bool {
                 run_action(app, result);
             }
         }
+        Event::Paste(text) if app.command_palette_open => {
+            for ch in text.chars().filter(|ch| !ch.is_control()) {
+                app.command_palette_append(ch);
+            }
+        }
+        Event::Paste(text) if app.finder_open => {
+            for ch in text.chars().filter(|ch| !ch.is_control()) {
+                app.finder_input_append(ch);
+            }
+        }
+        Event::Paste(text) if app.tree_filter_editing => {
+            for ch in text.chars().filter(|ch| !ch.is_control()) {
+                let result = app.tree_filter_append(ch);
+                run_action(app, result);
+            }
+        }
         Event::Paste(text) if app.git_panel_open => handle_git_panel_paste(app, &text),
-        Event::Mouse(mouse) if app.terminal_open => match mouse.kind {
-            MouseEventKind::ScrollUp => app.scroll_terminal(3),
-            MouseEventKind::ScrollDown => app.scroll_terminal(-3),
-            _ => {}
-        },
-        Event::Mouse(mouse) if !app.settings_open && !app.terminal_open => match mouse.kind {
+        Event::Mouse(_) if app.command_palette_open => {}
+        Event::Mouse(mouse)
+            if !app.settings_open && !app.finder_open && app.terminal_wants_mouse(&mouse) =>
+        {
+            let result = app.terminal_mouse(mouse);
+            run_action(app, result);
+        }
+        Event::Mouse(_) if app.terminal_overlay_open() => {}
+        Event::Mouse(mouse) if !app.settings_open && !app.finder_open => match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
                 let result = app.click(mouse.column, mouse.row);
                 run_action(app, result);
             }
+            MouseEventKind::ScrollUp if app.is_in_terminal_dock(mouse.column, mouse.row) => {
+                app.scroll_terminal(3);
+            }
+            MouseEventKind::ScrollDown if app.is_in_terminal_dock(mouse.column, mouse.row) => {
+                app.scroll_terminal(-3);
+            }
+            MouseEventKind::ScrollLeft if app.is_in_diff(mouse.column, mouse.row) => {
+                app.scroll_diff_horizontal(-1);
+            }
+            MouseEventKind::ScrollRight if app.is_in_diff(mouse.column, mouse.row) => {
+                app.scroll_diff_horizontal(1);
+            }
+            // Terminals without a horizontal wheel send Shift+wheel instead.
+            MouseEventKind::ScrollUp
+                if mouse.modifiers.contains(KeyModifiers::SHIFT)
+                    && app.is_in_diff(mouse.column, mouse.row) =>
+            {
+                app.scroll_diff_horizontal(-1);
+            }
+            MouseEventKind::ScrollDown
+                if mouse.modifiers.contains(KeyModifiers::SHIFT)
+                    && app.is_in_diff(mouse.column, mouse.row) =>
+            {
+                app.scroll_diff_horizontal(1);
+            }
             MouseEventKind::ScrollUp if app.is_in_diff(mouse.column, mouse.row) => {
                 app.scroll_diff(-3);
             }
@@ -130,87 +180,76 @@ pub fn handle_event(app: &mut App, event: Event) -> bool {
     true
 }
 
-fn map_main_key(code: KeyCode) -> Option<MainKeyAction> {
-    match code {
-        KeyCode::Tab => Some(MainKeyAction::CycleFocusForward),
-        KeyCode::BackTab => Some(MainKeyAction::CycleFocusBackward),
-        KeyCode::Up | KeyCode::Char('k') => Some(MainKeyAction::MoveUp),
-        KeyCode::Down | KeyCode::Char('j') => Some(MainKeyAction::MoveDown),
-        KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') => {
-            Some(MainKeyAction::TogglePaneFocus)
-        }
-        KeyCode::PageUp => Some(MainKeyAction::PageUp),
-        KeyCode::PageDown => Some(MainKeyAction::PageDown),
-        KeyCode::Home => Some(MainKeyAction::JumpTop),
-        KeyCode::End => Some(MainKeyAction::JumpBottom),
-        KeyCode::Enter | KeyCode::Char(' ') => Some(MainKeyAction::StageToggle),
-        KeyCode::Char(keymap::KEY_STAGE) => Some(MainKeyAction::Stage),
-        KeyCode::Char(keymap::KEY_UNSTAGE) => Some(MainKeyAction::Unstage),
-        KeyCode::Char(keymap::KEY_UNDO_MAINLINE) => Some(MainKeyAction::UndoToMainline),
-        KeyCode::Char(keymap::KEY_CYCLE_DIFF_VIEW) => Some(MainKeyAction::CycleDiffView),
-        KeyCode::Char(keymap::KEY_TOGGLE_SIDEBAR) => Some(MainKeyAction::ToggleSidebar),
-        KeyCode::Char(keymap::KEY_SIDEBAR_NARROW) => Some(MainKeyAction::SidebarNarrow),
-        KeyCode::Char(keymap::KEY_SIDEBAR_WIDE) => Some(MainKeyAction::SidebarWide),
-        KeyCode::Char(keymap::KEY_OPEN_SETTINGS) => Some(MainKeyAction::ToggleSettings),
-        KeyCode::Char(keymap::KEY_OPEN_GIT_PANEL) => Some(MainKeyAction::ToggleGitPanel),
-        KeyCode::Char(keymap::KEY_OPEN_COMMIT) => Some(MainKeyAction::OpenCommitPrompt),
-        KeyCode::Char(keymap::KEY_OPEN_TERMINAL_PRIMARY)
-        | KeyCode::Char(keymap::KEY_OPEN_TERMINAL_ALT) => Some(MainKeyAction::OpenTerminal),
-        KeyCode::Char(keymap::KEY_REFRESH) => Some(MainKeyAction::Refresh),
-        _ => None,
-    }
+fn map_main_key(keymap: &Keymap, key: KeyEvent) -> Option<Action> {
+    keymap
+        .action(KeyContext::Main, key)
+        .filter(|action| !matches!(action, Action::Quit | Action::ToggleHelp))
 }
 
-fn run_main_action(app: &mut App, action: MainKeyAction) {
+fn run_main_action(app: &mut App, action: Action) {
     match action {
-        MainKeyAction::CycleFocusForward => run_action_with(app, |app| app.cycle_focus_ring(1)),
-        MainKeyAction::CycleFocusBackward => run_action_with(app, |app| app.cycle_focus_ring(-1)),
-        MainKeyAction::MoveUp => {
+        Action::CycleFocusForward => run_action_with(app, |app| app.cycle_focus_ring(1)),
+        Action::CycleFocusBackward => run_action_with(app, |app| app.cycle_focus_ring(-1)),
+        Action::MoveUp => {
             if app.is_diff_focused() {
                 app.scroll_diff(-1);
             } else {
                 run_action_with(app, |app| app.move_selection(-1));
             }
         }
-        MainKeyAction::MoveDown => {
+        Action::MoveDown => {
             if app.is_diff_focused() {
                 app.scroll_diff(1);
             } else {
                 run_action_with(app, |app| app.move_selection(1));
             }
         }
-        MainKeyAction::TogglePaneFocus => app.toggle_pane_focus(),
-        MainKeyAction::PageUp => {
+        Action::TogglePaneFocus => app.toggle_pane_focus(),
+        Action::PageUp => {
             if app.is_diff_focused() {
                 app.scroll_diff_page(-1);
             } else {
                 run_action_with(app, |app| app.move_selection_page(-1));
             }
         }
-        MainKeyAction::PageDown => {
+        Action::PageDown => {
             if app.is_diff_focused() {
                 app.scroll_diff_page(1);
             } else {
                 run_action_with(app, |app| app.move_selection_page(1));
             }
         }
-        MainKeyAction::JumpTop => run_action_with(app, |app| app.jump_focused_area_to_edge(false)),
-        MainKeyAction::JumpBottom => {
-            run_action_with(app, |app| app.jump_focused_area_to_edge(true))
-        }
-        MainKeyAction::StageToggle => run_action_with(app, App::toggle_stage_state),
-        MainKeyAction::Stage => run_action_with(app, App::stage_selected),
-        MainKeyAction::Unstage => run_action_with(app, App::unstage_selected),
-        MainKeyAction::UndoToMainline => run_action_with(app, App::undo_selected_to_mainline),
-        MainKeyAction::CycleDiffView => run_action_with(app, |app| app.cycle_diff_view_mode(1)),
-        MainKeyAction::ToggleSidebar => run_action_with(app, App::toggle_sidebar_visibility),
-        MainKeyAction::SidebarNarrow => run_action_with(app, |app| app.resize_sidebar(-1)),
-        MainKeyAction::SidebarWide => run_action_with(app, |app| app.resize_sidebar(1)),
-        MainKeyAction::ToggleSettings => app.toggle_settings_panel(),
-        MainKeyAction::ToggleGitPanel => run_action_with(app, App::toggle_git_panel),
-        MainKeyAction::OpenCommitPrompt => run_action_with(app, App::open_commit_prompt),
-        MainKeyAction::OpenTerminal => run_action_with(app, App::open_terminal),
-        MainKeyAction::Refresh => run_action_with(app, App::refresh_with_message),
+        Action::JumpTop => run_action_with(app, |app| app.jump_focused_area_to_edge(false)),
+        Action::JumpBottom => run_action_with(app, |app| app.jump_focused_area_to_edge(true)),
+        Action::StageToggle => run_action_with(app, App::toggle_stage_state),
+        Action::Stage => run_action_with(app, App::stage_selected),
+        Action::Unstage => run_action_with(app, App::unstage_selected),
+        Action::UndoToMainline => run_action_with(app, App::undo_selected_to_mainline),
+        Action::DiscardWorktree => run_action_with(app, App::discard_selected_worktree_changes),
+        Action::ResetToHead => run_action_with(app, App::reset_selected_to_head),
+        Action::CycleDiffView => run_action_with(app, |app| app.cycle_diff_view_mode(1)),
+        Action::ToggleWrap => app.toggle_wrap_lines(),
+        Action::ScrollLeft => app.scroll_diff_horizontal(-1),
+        Action::ScrollRight => app.scroll_diff_horizontal(1),
+        Action::ToggleSidebar => run_action_with(app, App::toggle_sidebar_visibility),
+        Action::SidebarNarrow => run_action_with(app, |app| app.resize_sidebar(-1)),
+        Action::SidebarWide => run_action_with(app, |app| app.resize_sidebar(1)),
+        Action::ToggleSettings => app.toggle_settings_panel(),
+        Action::ToggleGitPanel => run_action_with(app, App::toggle_git_panel),
+        Action::OpenCommitPrompt => run_action_with(app, App::open_commit_prompt),
+        Action::OpenTerminal => run_action_with(app, App::open_terminal),
+        Action::ToggleTerminalPane => run_action_with(app, App::toggle_terminal_pane),
+        Action::OpenTaskPicker => app.open_task_picker(),
+        Action::TrustRepoConfig => app.toggle_repo_trust(),
+        Action::TerminalDockShrink => run_action_with(app, |app| app.resize_terminal_dock(-1)),
+        Action::TerminalDockGrow => run_action_with(app, |app| app.resize_terminal_dock(1)),
+        Action::OpenFinder => app.open_finder(),
+        Action::OpenCommandPalette => app.open_command_palette(),
+        Action::FilterTree => app.open_tree_filter(),
+        Action::ToggleDirectory => run_action_with(app, App::toggle_selected_directory),
+        Action::ToggleAllDirectories => run_action_with(app, App::toggle_all_directories),
+        Action::Refresh => run_action_with(app, App::refresh_with_message),
+        _ => {}
     }
 }
 
@@ -224,25 +263,39 @@ fn handle_git_panel_key(app: &mut App, key: KeyEvent) {
 }
 
 fn handle_git_panel_browse_key(app: &mut App, key: KeyEvent) {
+    if let Some(action) = app.keymap.action(KeyContext::GitPanel, key)
+        && run_git_panel_action(app, action)
+    {
+        return;
+    }
+
     match key.code {
-        KeyCode::Esc | KeyCode::Char(keymap::KEY_OPEN_GIT_PANEL) | KeyCode::Char('q') => {
-            app.close_git_panel()
-        }
-        KeyCode::Up | KeyCode::Char('k') => app.move_branch_selection(-1),
-        KeyCode::Down | KeyCode::Char('j') => app.move_branch_selection(1),
-        KeyCode::Enter | KeyCode::Char(keymap::KEY_GIT_SWITCH_BRANCH) => {
-            run_action_with(app, App::switch_to_selected_branch)
-        }
-        KeyCode::Char(keymap::KEY_GIT_CREATE_BRANCH) | KeyCode::Char('a') => {
-            app.open_branch_create_prompt()
-        }
-        KeyCode::Char(keymap::KEY_GIT_DELETE_BRANCH) => app.request_delete_selected_branch(),
-        KeyCode::Char(keymap::KEY_GIT_COMMIT) => run_action_with(app, App::open_commit_prompt),
-        KeyCode::Char(keymap::KEY_REFRESH) => run_action_with(app, App::refresh_with_message),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_git_panel(),
+        KeyCode::Up => app.move_branch_selection(-1),
+        KeyCode::Down => app.move_branch_selection(1),
+        KeyCode::Enter => run_action_with(app, App::switch_to_selected_branch),
         _ => {}
     }
 }
 
+/// Runs a branch panel action; false when `action` is not one.
+fn run_git_panel_action(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::ToggleGitPanel => app.close_git_panel(),
+        Action::MoveUp => app.move_branch_selection(-1),
+        Action::MoveDown => app.move_branch_selection(1),
+        Action::GitSwitchBranch => run_action_with(app, App::switch_to_selected_branch),
+        Action::GitCreateBranch => app.open_branch_create_prompt(),
+        Action::GitDeleteBranch => app.request_delete_selected_branch(),
+        Action::GitCommit => run_action_with(app, App::open_commit_prompt),
+        Action::Refresh => run_action_with(app, App::refresh_with_message),
+        Action::OpenCommandPalette => app.open_command_palette(),
+        _ => return false,
+    }
+
+    true
+}
+
 fn handle_git_panel_create_branch_key(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc => app.cancel_git_prompt(),
@@ -317,6 +370,156 @@ fn normalize_newlines(input: &str) -> String {
     input.replace("\r\n", "\n").replace('\r', "\n")
 }
 
+fn handle_finder_key(app: &mut App, key: KeyEvent) {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+            KeyCode::Char('n') | KeyCode::Char('j') => app.finder_move_selection(1),
+            KeyCode::Char('p') | KeyCode::Char('k') => app.finder_move_selection(-1),
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Esc => app.close_finder(),
+        KeyCode::Enter => run_action_with(app, App::submit_finder),
+        KeyCode::Up => app.finder_move_selection(-1),
+        KeyCode::Down => app.finder_move_selection(1),
+        KeyCode::PageUp => app.finder_move_selection(-10),
+        KeyCode::PageDown => app.finder_move_selection(10),
+        KeyCode::Backspace => app.finder_input_backspace(),
+        KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::ALT) => {
+            app.finder_input_append(ch)
+        }
+        _ => {}
+    }
+}
+
+fn handle_command_palette_key(app: &mut App, key: KeyEvent) -> bool {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+            KeyCode::Char('n') | KeyCode::Char('j') => app.command_palette_move_selection(1),
+            KeyCode::Char('p') | KeyCode::Char('k') => app.command_palette_move_selection(-1),
+            _ => {}
+        }
+        return true;
+    }
+
+    match key.code {
+        KeyCode::Esc => app.close_command_palette(),
+        KeyCode::Enter => {
+            if let Some(action) = app.submit_command_palette() {
+                return run_command(app, action);
+            }
+        }
+        KeyCode::Up => app.command_palette_move_selection(-1),
+        KeyCode::Down => app.command_palette_move_selection(1),
+        KeyCode::PageUp => app.command_palette_move_selection(-10),
+        KeyCode::PageDown => app.command_palette_move_selection(10),
+        KeyCode::Backspace => app.command_palette_backspace(),
+        KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::ALT) => {
+            app.command_palette_append(ch)
+        }
+        _ => {}
+    }
+    true
+}
+
+/// Runs an action picked from the command palette, opening the branch panel
+/// or the terminal first when the action lives there. False means quit.
+fn run_command(app: &mut App, action: Action) -> bool {
+    if app.git_panel_open && !action.applies_in(KeyContext::GitPanel) {
+        app.close_git_panel();
+    }
+
+    match action {
+        Action::Quit => return false,
+        Action::ToggleHelp => app.toggle_help_panel(),
+        _ if app.git_panel_open && run_git_panel_action(app, action) => {}
+        // Switch and delete act on the panel's selected branch, so the
+        // palette only enables them while the panel is open (handled above).
+        Action::GitCreateBranch => {
+            run_action_with(app, App::toggle_git_panel);
+            run_git_panel_action(app, action);
+        }
+        Action::GitCommit => run_action_with(app, App::open_commit_prompt),
+        Action::TerminalNewTab => run_action_with(app, |app| {
+            let had_tabs = !app.terminal_tabs().is_empty();
+            app.open_terminal()?;
+            if had_tabs {
+                app.new_terminal_tab()?;
+            }
+            Ok(())
+        }),
+        Action::TerminalCloseTab => app.close_terminal_tab(),
+        Action::TerminalNextTab | Action::TerminalPrevTab => run_action_with(app, |app| {
+            app.open_terminal()?;
+            app.cycle_terminal_tab(if action == Action::TerminalNextTab {
+                1
+            } else {
+                -1
+            });
+            Ok(())
+        }),
+        Action::TerminalRenameTab => run_action_with(app, |app| {
+            app.open_terminal()?;
+            app.start_terminal_tab_rename();
+            Ok(())
+        }),
+        Action::TerminalCopyMode | Action::TerminalSearch | Action::TerminalSearchBackward => {
+            run_action_with(app, |app| {
+                app.open_terminal()?;
+                app.terminal_enter_copy_mode();
+                if action != Action::TerminalCopyMode {
+                    app.terminal_open_search(action == Action::TerminalSearchBackward);
+                }
+                Ok(())
+            })
+        }
+        Action::TerminalExport => run_action_with(app, |app| app.terminal_export_scrollback(false)),
+        Action::TerminalExportAnsi => {
+            run_action_with(app, |app| app.terminal_export_scrollback(true))
+        }
+        _ => run_main_action(app, action),
+    }
+
+    true
+}
+
+fn handle_tree_filter_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => run_action_with(app, App::clear_tree_filter),
+        KeyCode::Enter => app.submit_tree_filter(),
+        KeyCode::Up => run_action_with(app, |app| app.move_selection(-1)),
+        KeyCode::Down => run_action_with(app, |app| app.move_selection(1)),
+        KeyCode::Backspace => run_action_with(app, App::tree_filter_backspace),
+        KeyCode::Char(ch)
+            if !key.modifiers.contains(KeyModifiers::CONTROL)
+                && !key.modifiers.contains(KeyModifiers::ALT) =>
+        {
+            run_action_with(app, |app| app.tree_filter_append(ch))
+        }
+        _ => {}
+    }
+}
+
+fn handle_task_picker_key(app: &mut App, key: KeyEvent) {
+    match app.keymap.action(KeyContext::Picker, key) {
+        Some(Action::MoveUp) => return app.move_task_picker_selection(-1),
+        Some(Action::MoveDown) => return app.move_task_picker_selection(1),
+        _ => {}
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => app.close_task_picker(),
+        KeyCode::Enter => run_action_with(app, App::run_selected_task),
+        KeyCode::Char(ch @ '1'..='9') => {
+            run_action_with(app, |app| app.run_task_at(ch as usize - '1' as usize))
+        }
+        _ => {}
+    }
+}
+
 fn handle_pending_undo_key(app: &mut App, code: KeyCode) {
     match code {
         KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
@@ -330,8 +533,16 @@ fn handle_pending_undo_key(app: &mut App, code: KeyCode) {
     }
 }
 
-fn handle_settings_key(app: &mut App, code: KeyCode) {
-    if let Some(action) = map_settings_key(code) {
+fn handle_pending_repo_trust_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => app.confirm_repo_trust(),
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => app.cancel_repo_trust(),
+        _ => {}
+    }
+}
+
+fn handle_settings_key(app: &mut App, key: KeyEvent) {
+    if let Some(action) = map_settings_key(&app.keymap, key) {
         match action {
             SettingsKeyAction::Close => app.close_settings_panel(),
             SettingsKeyAction::MoveUp => app.move_settings_selection(-1),
@@ -346,24 +557,38 @@ fn handle_settings_key(app: &mut App, code: KeyCode) {
     }
 }
 
-fn map_settings_key(code: KeyCode) -> Option<SettingsKeyAction> {
-    match code {
-        KeyCode::Esc
-        | KeyCode::Enter
-        | KeyCode::Char(keymap::KEY_SETTINGS_CLOSE)
-        | KeyCode::Char('q') => Some(SettingsKeyAction::Close),
-        KeyCode::Up | KeyCode::Char('k') => Some(SettingsKeyAction::MoveUp),
-        KeyCode::Down | KeyCode::Char('j') => Some(SettingsKeyAction::MoveDown),
-        KeyCode::Left | KeyCode::Char('h') => Some(SettingsKeyAction::AdjustLeft),
-        KeyCode::Right | KeyCode::Char('l') => Some(SettingsKeyAction::AdjustRight),
+fn map_settings_key(keymap: &Keymap, key: KeyEvent) -> Option<SettingsKeyAction> {
+    if keymap.matches(Action::ToggleSettings, key) {
+        return Some(SettingsKeyAction::Close);
+    }
+
+    match keymap.action(KeyContext::Picker, key) {
+        Some(Action::MoveUp) => return Some(SettingsKeyAction::MoveUp),
+        Some(Action::MoveDown) => return Some(SettingsKeyAction::MoveDown),
+        Some(Action::SettingDecrease) => return Some(SettingsKeyAction::AdjustLeft),
+        Some(Action::SettingIncrease) => return Some(SettingsKeyAction::AdjustRight),
+        _ => {}
+    }
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => Some(SettingsKeyAction::Close),
         _ => None,
     }
 }
 
 fn handle_terminal_key(app: &mut App, key: KeyEvent) {
-    if app.terminal_search_open {
-        if is_terminal_close_chord(key) {
-            app.close_terminal();
+    if app.terminal_rename_open {
+        handle_terminal_rename_key(app, key);
+        return;
+    }
+
+    if handle_terminal_tab_chord(app, key) {
+        return;
+    }
+
+    if app.terminal_search_open() {
+        if is_terminal_close_chord(&app.keymap, key) {
+            app.leave_terminal();
             return;
         }
 
@@ -371,9 +596,9 @@ fn handle_terminal_key(app: &mut App, key: KeyEvent) {
         return;
     }
 
-    if app.terminal_copy_mode {
-        if is_terminal_close_chord(key) {
-            app.close_terminal();
+    if app.terminal_copy_mode() {
+        if is_terminal_close_chord(&app.keymap, key) {
+            app.leave_terminal();
             return;
         }
 
@@ -381,28 +606,16 @@ fn handle_terminal_key(app: &mut App, key: KeyEvent) {
         return;
     }
 
-    if is_terminal_close_chord(key) {
-        app.close_terminal();
+    if is_terminal_close_chord(&app.keymap, key) {
+        app.leave_terminal();
         return;
     }
 
-    if key.modifiers.contains(KeyModifiers::SHIFT) {
-        match key.code {
-            KeyCode::Up => {
-                app.scroll_terminal(FAST_TERMINAL_SCROLL_STEP);
-                return;
-            }
-            KeyCode::Down => {
-                app.scroll_terminal(-FAST_TERMINAL_SCROLL_STEP);
-                return;
-            }
-            _ => {}
-        }
+    if handle_terminal_scroll_chord(app, key) {
+        return;
     }
 
-    if key.modifiers.contains(KeyModifiers::ALT)
-        && key.code == KeyCode::Char(keymap::KEY_TERMINAL_COPY_MODE)
-    {
+    if app.keymap.matches(Action::TerminalCopyMode, key) {
         app.terminal_enter_copy_mode();
         return;
     }
@@ -411,47 +624,106 @@ fn handle_terminal_key(app: &mut App, key: KeyEvent) {
     run_action(app, result);
 }
 
-fn handle_terminal_copy_key(app: &mut App, key: KeyEvent) {
-    if key.modifiers.contains(KeyModifiers::SHIFT) {
-        match key.code {
-            KeyCode::Up => {
-                app.scroll_terminal(FAST_TERMINAL_SCROLL_STEP);
-                return;
-            }
-            KeyCode::Down => {
-                app.scroll_terminal(-FAST_TERMINAL_SCROLL_STEP);
-                return;
-            }
-            _ => {}
+fn handle_terminal_tab_chord(app: &mut App, key: KeyEvent) -> bool {
+    match app.keymap.action(KeyContext::Terminal, key) {
+        Some(Action::TerminalNewTab) => {
+            let result = app.new_terminal_tab();
+            run_action(app, result);
         }
+        Some(Action::TerminalCloseTab) => app.close_terminal_tab(),
+        Some(Action::TerminalNextTab) => app.cycle_terminal_tab(1),
+        Some(Action::TerminalPrevTab) => app.cycle_terminal_tab(-1),
+        Some(Action::TerminalRenameTab) => app.start_terminal_tab_rename(),
+        _ => match app.keymap.selected_tab(key) {
+            Some(index) => app.select_terminal_tab(index),
+            None => return false,
+        },
     }
 
+    true
+}
+
+/// Chords match modifiers exactly, so Ctrl+Shift and Alt+Shift arrows still
+/// reach the program.
+fn handle_terminal_scroll_chord(app: &mut App, key: KeyEvent) -> bool {
+    match app.keymap.action(KeyContext::Terminal, key) {
+        Some(Action::TerminalScrollUp) => app.scroll_terminal(FAST_TERMINAL_SCROLL_STEP),
+        Some(Action::TerminalScrollDown) => app.scroll_terminal(-FAST_TERMINAL_SCROLL_STEP),
+        _ => return false,
+    }
+
+    true
+}
+
+fn handle_terminal_rename_key(app: &mut App, key: KeyEvent) {
     match key.code {
-        KeyCode::Esc | KeyCode::Char(keymap::KEY_TERMINAL_INTERACTIVE_MODE) => {
-            app.terminal_exit_copy_mode()
+        KeyCode::Esc => app.cancel_terminal_tab_rename(),
+        KeyCode::Enter => app.submit_terminal_tab_rename(),
+        KeyCode::Backspace => app.terminal_rename_backspace(),
+        KeyCode::Char(ch)
+            if !key.modifiers.contains(KeyModifiers::CONTROL)
+                && !key.modifiers.contains(KeyModifiers::ALT) =>
+        {
+            app.terminal_rename_append(ch);
         }
-        KeyCode::Up | KeyCode::Char('k') => app.terminal_move_cursor(-1, 0),
-        KeyCode::Down | KeyCode::Char('j') => app.terminal_move_cursor(1, 0),
-        KeyCode::Left | KeyCode::Char('h') => app.terminal_move_cursor(0, -1),
-        KeyCode::Right | KeyCode::Char('l') => app.terminal_move_cursor(0, 1),
+        _ => {}
+    }
+}
+
+fn handle_terminal_copy_key(app: &mut App, key: KeyEvent) {
+    if handle_terminal_scroll_chord(app, key) {
+        return;
+    }
+
+    match app.keymap.action(KeyContext::Terminal, key) {
+        Some(Action::TerminalInteractiveMode) => return app.terminal_exit_copy_mode(),
+        Some(Action::TerminalCursorUp) => return app.terminal_move_cursor(-1, 0),
+        Some(Action::TerminalCursorDown) => return app.terminal_move_cursor(1, 0),
+        Some(Action::TerminalCursorLeft) => return app.terminal_move_cursor(0, -1),
+        Some(Action::TerminalCursorRight) => return app.terminal_move_cursor(0, 1),
+        Some(Action::TerminalSelectionAnchor) => return app.terminal_toggle_selection_anchor(),
+        Some(Action::TerminalYank) => {
+            let result = app.terminal_yank_selection();
+            return run_action(app, result);
+        }
+        Some(Action::TerminalSearch) => return app.terminal_open_search(false),
+        Some(Action::TerminalSearchBackward) => return app.terminal_open_search(true),
+        Some(Action::TerminalSearchNext) => return app.terminal_search_next(),
+        Some(Action::TerminalSearchPrev) => return app.terminal_search_prev(),
+        Some(Action::TerminalNextRef) => return app.terminal_next_file_ref(),
+        Some(Action::TerminalExport) => {
+            let result = app.terminal_export_scrollback(false);
+            return run_action(app, result);
+        }
+        Some(Action::TerminalExportAnsi) => {
+            let result = app.terminal_export_scrollback(true);
+            return run_action(app, result);
+        }
+        Some(Action::TerminalOpenRef) => {
+            let result = app.terminal_open_reference();
+            return run_action(app, result);
+        }
+        _ => {}
+    }
+
+    match key.code {
+        KeyCode::Esc => app.terminal_exit_copy_mode(),
         KeyCode::PageUp => app.scroll_terminal(10),
         KeyCode::PageDown => app.scroll_terminal(-10),
         KeyCode::Home => app.scroll_terminal(10_000),
         KeyCode::End => app.scroll_terminal(-10_000),
-        KeyCode::Char(keymap::KEY_TERMINAL_SELECTION_ANCHOR) => {
-            app.terminal_toggle_selection_anchor()
-        }
-        KeyCode::Char(keymap::KEY_TERMINAL_YANK) => {
-            let result = app.terminal_yank_selection();
-            run_action(app, result);
-        }
-        KeyCode::Char(keymap::KEY_TERMINAL_SEARCH) => app.terminal_open_search(),
-        KeyCode::Char(keymap::KEY_TERMINAL_SEARCH_NEXT) => app.terminal_search_next(),
         _ => {}
     }
 }
 
 fn handle_terminal_search_key(app: &mut App, key: KeyEvent) {
+    match app.keymap.action(KeyContext::Terminal, key) {
+        Some(Action::TerminalSearchToggleRegex) => return app.terminal_toggle_search_regex(),
+        Some(Action::TerminalSearchToggleCase) => return app.terminal_toggle_search_case(),
+        _ if key.modifiers.contains(KeyModifiers::CONTROL) => return,
+        _ => {}
+    }
+
     match key.code {
         KeyCode::Esc => app.terminal_cancel_search(),
         KeyCode::Enter => app.terminal_search_next(),
@@ -471,14 +743,7 @@ fn wants_help_toggle(app: &App, key: KeyEvent) -> bool {
         return true;
     }
 
-    if key.code != KeyCode::Char(keymap::KEY_TOGGLE_HELP) {
-        return false;
-    }
-
-    if key
-        .modifiers
-        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
-    {
+    if !app.keymap.matches(Action::ToggleHelp, key) {
         return false;
     }
 
@@ -486,8 +751,17 @@ fn wants_help_toggle(app: &App, key: KeyEvent) -> bool {
 }
 
 fn can_toggle_help_with_question_mark(app: &App) -> bool {
-    if app.terminal_open {
-        return app.terminal_copy_mode && !app.terminal_search_open;
+    if app.finder_open
+        || app.tree_filter_editing
+        || app.task_picker_open
+        || app.command_palette_open
+    {
+        return false;
+    }
+
+    // Copy mode uses `?` for backward search; F1 still opens help.
+    if app.terminal_has_focus() {
+        return false;
     }
 
     if app.git_panel_open {
@@ -500,33 +774,26 @@ fn can_toggle_help_with_question_mark(app: &App) -> bool {
     true
 }
 
-fn handle_help_key(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc
-        | KeyCode::F(1)
-        | KeyCode::Char(keymap::KEY_TOGGLE_HELP)
-        | KeyCode::Char('q') => app.close_help_panel(),
-        _ => {}
+fn handle_help_key(app: &mut App, key: KeyEvent) {
+    if app.keymap.matches(Action::ToggleHelp, key)
+        || matches!(key.code, KeyCode::Esc | KeyCode::F(1) | KeyCode::Char('q'))
+    {
+        app.close_help_panel();
     }
 }
 
-fn is_terminal_close_chord(key: KeyEvent) -> bool {
-    if key.code == KeyCode::Esc {
-        return true;
-    }
-
-    if key.code == KeyCode::Char('\u{1d}') {
+fn is_terminal_close_chord(keymap: &Keymap, key: KeyEvent) -> bool {
+    if keymap.matches(Action::TerminalHide, key) {
         return true;
     }
 
-    key.modifiers.contains(KeyModifiers::CONTROL)
-        && matches!(
-            key.code,
-            KeyCode::Char(']')
-                | KeyCode::Char('g')
-                | KeyCode::Char('5')
-                | KeyCode::Char('q')
-                | KeyCode::Char('w')
+    // Some terminals report Ctrl+] as the raw GS byte or as Ctrl+5.
+    let ctrl_bracket_alias = key.code == KeyCode::Char('\u{1d}')
+        || (key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('5'));
+    ctrl_bracket_alias
+        && keymap.matches(
+            Action::TerminalHide,
+            KeyEvent::new(KeyCode::Char(']'), KeyModifiers::CONTROL),
         )
 }
 
@@ -546,58 +813,93 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crossterm::event::KeyCode;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-    use super::{MainKeyAction, SettingsKeyAction, map_main_key, map_settings_key};
-    use crate::keymap;
+    use std::collections::BTreeMap;
+
+    use super::{SettingsKeyAction, is_terminal_close_chord, map_main_key, map_settings_key};
+    use crate::keymap::{Action, Keymap};
+    use crate::settings::KeyBinding;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
 
     #[test]
     fn maps_main_keybindings_to_actions() {
+        let keys = Keymap::default();
+        let cases = [
+            (KeyCode::Tab, Action::CycleFocusForward),
+            (KeyCode::BackTab, Action::CycleFocusBackward),
+            (KeyCode::Enter, Action::StageToggle),
+            (KeyCode::Char('s'), Action::Stage),
+            (KeyCode::Char('x'), Action::UndoToMainline),
+            (KeyCode::Char('d'), Action::DiscardWorktree),
+            (KeyCode::Char('X'), Action::ResetToHead),
+            (KeyCode::Char(':'), Action::OpenTerminal),
+            (KeyCode::Char('g'), Action::ToggleGitPanel),
+            (KeyCode::Char('c'), Action::OpenCommitPrompt),
+            (KeyCode::Char('/'), Action::OpenFinder),
+            (KeyCode::Char('f'), Action::FilterTree),
+            (KeyCode::Char('z'), Action::ToggleDirectory),
+            (KeyCode::Char('T'), Action::ToggleTerminalPane),
+            (KeyCode::Char('}'), Action::TerminalDockGrow),
+        ];
+        for (code, action) in cases {
+            assert_eq!(map_main_key(&keys, key(code)), Some(action), "{code:?}");
+        }
+        assert_eq!(map_main_key(&keys, key(KeyCode::F(5))), None);
+        assert_eq!(map_main_key(&keys, key(KeyCode::Char('q'))), None);
         assert_eq!(
-            map_main_key(KeyCode::Tab),
-            Some(MainKeyAction::CycleFocusForward)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::BackTab),
-            Some(MainKeyAction::CycleFocusBackward)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::Enter),
-            Some(MainKeyAction::StageToggle)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::Char(keymap::KEY_STAGE)),
-            Some(MainKeyAction::Stage)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::Char(keymap::KEY_UNDO_MAINLINE)),
-            Some(MainKeyAction::UndoToMainline)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::Char(keymap::KEY_OPEN_TERMINAL_PRIMARY)),
-            Some(MainKeyAction::OpenTerminal)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::Char(keymap::KEY_OPEN_GIT_PANEL)),
-            Some(MainKeyAction::ToggleGitPanel)
-        );
-        assert_eq!(
-            map_main_key(KeyCode::Char(keymap::KEY_OPEN_COMMIT)),
-            Some(MainKeyAction::OpenCommitPrompt)
+            map_main_key(
+                &keys,
+                KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)
+            ),
+            None
         );
-        assert_eq!(map_main_key(KeyCode::F(5)), None);
     }
 
     #[test]
     fn maps_settings_keybindings_to_actions() {
+        let keys = Keymap::default();
         assert_eq!(
-            map_settings_key(KeyCode::Char(keymap::KEY_SETTINGS_CLOSE)),
+            map_settings_key(&keys, key(KeyCode::Char('o'))),
             Some(SettingsKeyAction::Close)
         );
         assert_eq!(
-            map_settings_key(KeyCode::Char('h')),
+            map_settings_key(&keys, key(KeyCode::Char('h'))),
+            Some(SettingsKeyAction::AdjustLeft)
+        );
+        assert_eq!(map_settings_key(&keys, key(KeyCode::Char('x'))), None);
+    }
+
+    #[test]
+    fn terminal_hide_and_settings_keys_follow_the_keymap() {
+        let ctrl = |ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL);
+        let keys = Keymap::default();
+        assert!(is_terminal_close_chord(&keys, key(KeyCode::Esc)));
+        assert!(is_terminal_close_chord(&keys, ctrl('5')));
+        assert!(!is_terminal_close_chord(&keys, ctrl('x')));
+
+        let overrides = BTreeMap::from([
+            (
+                String::from("terminal_hide"),
+                KeyBinding::One(String::from("ctrl+x")),
+            ),
+            (
+                String::from("setting_decrease"),
+                KeyBinding::One(String::from("-")),
+            ),
+        ]);
+        let (keys, problems) = Keymap::from_config(&overrides);
+        assert!(problems.is_empty(), "{problems:?}");
+        assert!(is_terminal_close_chord(&keys, ctrl('x')));
+        assert!(!is_terminal_close_chord(&keys, key(KeyCode::Esc)));
+        assert!(!is_terminal_close_chord(&keys, ctrl('5')));
+        assert_eq!(
+            map_settings_key(&keys, key(KeyCode::Char('-'))),
             Some(SettingsKeyAction::AdjustLeft)
         );
-        assert_eq!(map_settings_key(KeyCode::Char('x')), None);
+        assert_eq!(map_settings_key(&keys, key(KeyCode::Char('h'))), None);
     }
 }
@@ -1,120 +1,255 @@
-pub const KEY_QUIT: char = 'q';
-pub const KEY_STAGE: char = 's';
-pub const KEY_UNSTAGE: char = 'u';
-pub const KEY_UNDO_MAINLINE: char = 'x';
-pub const KEY_CYCLE_DIFF_VIEW: char = 'v';
-pub const KEY_TOGGLE_SIDEBAR: char = 'b';
-pub const KEY_SIDEBAR_NARROW: char = '[';
-pub const KEY_SIDEBAR_WIDE: char = ']';
-pub const KEY_OPEN_SETTINGS: char = 'o';
-pub const KEY_OPEN_GIT_PANEL: char = 'g';
-pub const KEY_OPEN_COMMIT: char = 'c';
-pub const KEY_OPEN_TERMINAL_PRIMARY: char = ':';
-pub const KEY_OPEN_TERMINAL_ALT: char = '!';
-pub const KEY_TOGGLE_HELP: char = '?';
-pub const KEY_REFRESH: char = 'r';
-
-pub const KEY_GIT_CREATE_BRANCH: char = 'n';
-pub const KEY_GIT_SWITCH_BRANCH: char = 's';
-pub const KEY_GIT_DELETE_BRANCH: char = 'd';
-pub const KEY_GIT_COMMIT: char = KEY_OPEN_COMMIT;
-
-pub const KEY_TERMINAL_COPY_MODE: char = 'c';
-pub const KEY_TERMINAL_INTERACTIVE_MODE: char = 'i';
-pub const KEY_TERMINAL_SELECTION_ANCHOR: char = 'v';
-pub const KEY_TERMINAL_YANK: char = 'y';
-pub const KEY_TERMINAL_SEARCH: char = '/';
-pub const KEY_TERMINAL_SEARCH_NEXT: char = 'n';
-
-pub const KEY_SETTINGS_CLOSE: char = KEY_OPEN_SETTINGS;
-
-pub fn terminal_close_hint() -> &'static str {
-    "Esc, Ctrl+], Ctrl+g, Ctrl+q, or Ctrl+w"
+use crossterm::event::{KeyCode, KeyEvent};
+
+mod chord;
+mod conflicts;
+mod hints;
+mod spec;
+
+pub use chord::KeyChord;
+pub use hints::{
+    footer_hint_command_palette, footer_hint_finder, footer_hint_git_panel, footer_hint_main,
+    footer_hint_main_compact, footer_hint_main_navigation, footer_hint_pending_undo,
+    footer_hint_settings, footer_hint_task_picker, footer_hint_terminal, footer_hint_terminal_copy,
+    footer_hint_terminal_rename, footer_hint_terminal_search, footer_hint_tree_filter,
+    help_git_lines, help_main_lines, help_terminal_lines, terminal_modal_copy_hint,
+    terminal_modal_interactive_hint, terminal_tab_hint,
+};
+use spec::ACTIONS;
+
+/// Where a binding applies. Bindings only conflict when they share one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    Main,
+    GitPanel,
+    Terminal,
+    /// The task picker and settings lists.
+    Picker,
 }
 
-pub fn footer_hint_pending_undo() -> &'static str {
-    "confirm undo: Enter/y apply to mainline, n/Esc cancel"
+/// Everything a key can be bound to from `[keys]` in `config.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    CycleFocusForward,
+    CycleFocusBackward,
+    MoveUp,
+    MoveDown,
+    TogglePaneFocus,
+    PageUp,
+    PageDown,
+    JumpTop,
+    JumpBottom,
+    StageToggle,
+    Stage,
+    Unstage,
+    UndoToMainline,
+    DiscardWorktree,
+    ResetToHead,
+    CycleDiffView,
+    ToggleWrap,
+    ScrollLeft,
+    ScrollRight,
+    ToggleSidebar,
+    SidebarNarrow,
+    SidebarWide,
+    ToggleSettings,
+    SettingDecrease,
+    SettingIncrease,
+    ToggleGitPanel,
+    OpenCommitPrompt,
+    OpenTerminal,
+    ToggleTerminalPane,
+    OpenTaskPicker,
+    TrustRepoConfig,
+    TerminalDockShrink,
+    TerminalDockGrow,
+    OpenFinder,
+    OpenCommandPalette,
+    FilterTree,
+    ToggleDirectory,
+    ToggleAllDirectories,
+    Refresh,
+    ToggleHelp,
+    Quit,
+    GitCreateBranch,
+    GitSwitchBranch,
+    GitDeleteBranch,
+    GitCommit,
+    TerminalCopyMode,
+    TerminalHide,
+    TerminalScrollUp,
+    TerminalScrollDown,
+    TerminalNewTab,
+    TerminalCloseTab,
+    TerminalNextTab,
+    TerminalPrevTab,
+    TerminalSelectTab,
+    TerminalRenameTab,
+    TerminalInteractiveMode,
+    TerminalCursorUp,
+    TerminalCursorDown,
+    TerminalCursorLeft,
+    TerminalCursorRight,
+    TerminalSelectionAnchor,
+    TerminalYank,
+    TerminalSearch,
+    TerminalSearchBackward,
+    TerminalSearchNext,
+    TerminalSearchPrev,
+    TerminalSearchToggleRegex,
+    TerminalSearchToggleCase,
+    TerminalNextRef,
+    TerminalOpenRef,
+    TerminalExport,
+    TerminalExportAnsi,
 }
 
-pub fn footer_hint_terminal_search() -> &'static str {
-    "terminal search: type query, Enter find, Esc cancel"
+/// The active bindings: the defaults with `[keys]` from `config.toml` on top.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyChord>)>,
 }
 
-pub fn footer_hint_git_panel() -> String {
-    format!(
-        "git: j/k move  Enter/{} switch  {} new branch  {} delete  {} commit  Esc close",
-        KEY_GIT_SWITCH_BRANCH, KEY_GIT_CREATE_BRANCH, KEY_GIT_DELETE_BRANCH, KEY_GIT_COMMIT,
-    )
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .map(|spec| {
+                // Every default parses; see the test in `spec`.
+                let chords = spec
+                    .keys
+                    .iter()
+                    .filter_map(|key| KeyChord::parse(key).ok())
+                    .collect();
+                (spec.action, chords)
+            })
+            .collect();
+        Self { bindings }
+    }
 }
 
-pub fn footer_hint_terminal_copy() -> &'static str {
-    "copy: move(hjkl/arrows)  Shift+Up/Down fast scroll  v mark  y copy  / search  i shell"
-}
+impl Keymap {
+    /// `bindings` is built from `ACTIONS`, so it is indexed the same way.
+    fn slot_mut(&mut self, action: Action) -> &mut Vec<KeyChord> {
+        &mut self.bindings[action as usize].1
+    }
 
-pub fn footer_hint_terminal() -> String {
-    format!(
-        "terminal: keys->shell, Shift+Up/Down scroll, Alt+{} copy, {} close",
-        KEY_TERMINAL_COPY_MODE,
-        terminal_close_hint()
-    )
-}
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        &self.bindings[action as usize].1
+    }
 
-pub fn footer_hint_settings() -> &'static str {
-    "settings: j/k select, h/l change, Esc/o close"
-}
+    pub fn matches(&self, action: Action, key: KeyEvent) -> bool {
+        self.chords(action).iter().any(|chord| chord.matches(key))
+    }
 
-pub fn footer_hint_main() -> String {
-    format!(
-        "Tab pane  h/l pane  j/k move-or-scroll  Enter toggle-stage  {} stage  {} unstage  {} undo  {} branches  {} commit  {} terminal  {} settings  {} help  {} quit",
-        KEY_STAGE,
-        KEY_UNSTAGE,
-        KEY_UNDO_MAINLINE,
-        KEY_OPEN_GIT_PANEL,
-        KEY_OPEN_COMMIT,
-        KEY_OPEN_TERMINAL_PRIMARY,
-        KEY_OPEN_SETTINGS,
-        KEY_TOGGLE_HELP,
-        KEY_QUIT,
-    )
-}
+    /// The action `key` runs in `context`, if any.
+    pub fn action(&self, context: KeyContext, key: KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(action, chords)| {
+                action.applies_in(context) && chords.iter().any(|chord| chord.matches(key))
+            })
+            .map(|(action, _)| *action)
+    }
 
-pub fn terminal_modal_interactive_hint() -> String {
-    format!(
-        "interactive shell. Shift+Up/Down scrolls faster. Alt+{} enters copy mode. {} close.",
-        KEY_TERMINAL_COPY_MODE,
-        terminal_close_hint()
-    )
-}
+    /// The tab index `key` picks. `terminal_select_tab` is bound to the chord
+    /// for tab 1, and the same modifiers with 2-9 pick the others.
+    pub fn selected_tab(&self, key: KeyEvent) -> Option<usize> {
+        let KeyCode::Char(digit @ '1'..='9') = key.code else {
+            return None;
+        };
+        let pressed = KeyChord::normalized(key.code, key.modifiers);
+        self.chords(Action::TerminalSelectTab)
+            .iter()
+            .any(|chord| chord.modifiers == pressed.modifiers)
+            .then(|| digit as usize - '1' as usize)
+    }
 
-pub fn terminal_modal_copy_hint() -> &'static str {
-    "copy: hjkl/arrows move  Shift+Up/Down fast scroll  v mark  y yank  / search  n next  i shell"
+    /// The first key bound to `action`, for hints; `-` when unbound.
+    pub fn key(&self, action: Action) -> String {
+        self.chords(action)
+            .first()
+            .map_or_else(|| String::from("-"), ToString::to_string)
+    }
+
+    /// Every key bound to `action`, joined with `/`.
+    pub fn keys(&self, action: Action) -> String {
+        let keys = self
+            .chords(action)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            String::from("-")
+        } else {
+            keys.join("/")
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{
-        KEY_OPEN_GIT_PANEL, KEY_OPEN_SETTINGS, KEY_OPEN_TERMINAL_PRIMARY, KEY_QUIT, KEY_STAGE,
-        KEY_TERMINAL_COPY_MODE, KEY_TOGGLE_HELP, KEY_UNDO_MAINLINE, footer_hint_main,
-        footer_hint_terminal, terminal_modal_interactive_hint,
-    };
+    use std::collections::BTreeMap;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Action, KeyContext, Keymap, footer_hint_terminal};
+    use crate::settings::KeyBinding;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
 
     #[test]
-    fn footer_main_hint_contains_primary_keys() {
-        let hint = footer_hint_main();
-        assert!(hint.contains(KEY_STAGE));
-        assert!(hint.contains(KEY_UNDO_MAINLINE));
-        assert!(hint.contains(KEY_OPEN_GIT_PANEL));
-        assert!(hint.contains(KEY_OPEN_TERMINAL_PRIMARY));
-        assert!(hint.contains(KEY_OPEN_SETTINGS));
-        assert!(hint.contains(KEY_TOGGLE_HELP));
-        assert!(hint.contains(KEY_QUIT));
+    fn default_keymap_resolves_actions_by_context() {
+        let keys = Keymap::default();
+        let plain = |ch| key(KeyCode::Char(ch), KeyModifiers::NONE);
+        assert_eq!(
+            keys.action(KeyContext::Main, plain('s')),
+            Some(Action::Stage)
+        );
+        assert_eq!(
+            keys.action(KeyContext::GitPanel, plain('s')),
+            Some(Action::GitSwitchBranch)
+        );
+        assert_eq!(
+            keys.action(
+                KeyContext::Main,
+                key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+            ),
+            None
+        );
+        assert_eq!(
+            keys.action(
+                KeyContext::Terminal,
+                key(KeyCode::Char('t'), KeyModifiers::ALT)
+            ),
+            Some(Action::TerminalNewTab)
+        );
+        assert_eq!(
+            keys.action(KeyContext::Picker, plain('h')),
+            Some(Action::SettingDecrease)
+        );
     }
 
     #[test]
-    fn terminal_hints_reference_copy_mode_key() {
-        let footer = footer_hint_terminal();
-        let modal = terminal_modal_interactive_hint();
-        assert!(footer.contains(KEY_TERMINAL_COPY_MODE));
-        assert!(modal.contains(KEY_TERMINAL_COPY_MODE));
+    fn tab_selection_follows_the_configured_modifiers() {
+        let digit = |ch, modifiers| key(KeyCode::Char(ch), modifiers);
+        let keys = Keymap::default();
+        assert_eq!(keys.selected_tab(digit('3', KeyModifiers::ALT)), Some(2));
+        assert_eq!(keys.selected_tab(digit('3', KeyModifiers::NONE)), None);
+        assert_eq!(keys.selected_tab(digit('0', KeyModifiers::ALT)), None);
+
+        let overrides = BTreeMap::from([(
+            String::from("terminal_select_tab"),
+            KeyBinding::One(String::from("ctrl+1")),
+        )]);
+        let (keys, problems) = Keymap::from_config(&overrides);
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(
+            keys.selected_tab(digit('9', KeyModifiers::CONTROL)),
+            Some(8)
+        );
+        assert_eq!(keys.selected_tab(digit('9', KeyModifiers::ALT)), None);
+        assert!(footer_hint_terminal(&keys).contains("Ctrl+1-9 switch"));
     }
 }
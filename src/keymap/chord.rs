@@ -0,0 +1,171 @@
+//! Parsing and display of key chords like `ctrl+s` and `Alt+c`.
+
+use std::fmt;
+
+use anyhow::{Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key plus the Ctrl/Alt/Shift/Super modifiers that must be held with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    pub(super) modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Parses `ctrl+s`, `alt+shift+t`, `X`, `enter`, `f5`, `space`, ...
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (modifier_part, key_part) = if text.len() > 1 && text.ends_with("++") {
+            (&text[..text.len() - 2], "+")
+        } else {
+            match text.rfind('+') {
+                Some(idx) if idx + 1 < text.len() => (&text[..idx], &text[idx + 1..]),
+                _ => ("", text),
+            }
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in modifier_part.split('+').filter(|part| !part.is_empty()) {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" | "cmd" | "win" => KeyModifiers::SUPER,
+                other => bail!("unknown modifier `{other}` in `{text}`"),
+            };
+        }
+
+        let mut chars = key_part.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match key_part.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "insert" => KeyCode::Insert,
+                "delete" | "del" => KeyCode::Delete,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(number @ 1..=12) => KeyCode::F(number),
+                    _ => bail!("unknown key `{key_part}` in `{text}`"),
+                },
+            },
+        };
+
+        Ok(Self::normalized(code, modifiers))
+    }
+
+    /// Folds Shift into the key where the key already says it: `shift+x` is
+    /// `X` and `shift+tab` is `backtab`.
+    pub(super) fn normalized(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        let modifiers = modifiers
+            & (KeyModifiers::CONTROL
+                | KeyModifiers::ALT
+                | KeyModifiers::SHIFT
+                | KeyModifiers::SUPER);
+        match code {
+            KeyCode::Char(ch) => {
+                let ch = if shift { ch.to_ascii_uppercase() } else { ch };
+                Self {
+                    code: KeyCode::Char(ch),
+                    modifiers: modifiers - KeyModifiers::SHIFT,
+                }
+            }
+            KeyCode::BackTab => Self {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::Tab if shift => Self {
+                code: KeyCode::BackTab,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    pub fn matches(&self, key: KeyEvent) -> bool {
+        *self == Self::normalized(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, label) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SUPER, "Super+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(label)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::Insert => f.write_str("Insert"),
+            KeyCode::Delete => f.write_str("Delete"),
+            KeyCode::F(number) => write!(f, "F{number}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::KeyChord;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_and_displays_key_chords() {
+        let chord = KeyChord::parse("ctrl+alt+s").unwrap();
+        assert!(chord.matches(key(
+            KeyCode::Char('s'),
+            KeyModifiers::CONTROL | KeyModifiers::ALT
+        )));
+        assert!(!chord.matches(key(KeyCode::Char('s'), KeyModifiers::CONTROL)));
+        assert_eq!(chord.to_string(), "Ctrl+Alt+s");
+
+        let upper = KeyChord::parse("shift+x").unwrap();
+        assert_eq!(upper, KeyChord::parse("X").unwrap());
+        assert!(upper.matches(key(KeyCode::Char('X'), KeyModifiers::SHIFT)));
+        assert_eq!(
+            KeyChord::parse("shift+tab").unwrap(),
+            KeyChord::parse("backtab").unwrap()
+        );
+        assert_eq!(KeyChord::parse("ctrl++").unwrap().to_string(), "Ctrl++");
+        assert_eq!(KeyChord::parse("F5").unwrap().to_string(), "F5");
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("f13").is_err());
+    }
+}
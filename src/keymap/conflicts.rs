@@ -0,0 +1,133 @@
+//! Layering `[keys]` from `config.toml` over the defaults, and resolving
+//! chords claimed by two actions in the same context.
+
+use std::collections::BTreeMap;
+
+use super::{Action, KeyChord, Keymap};
+use crate::settings::KeyBinding;
+
+impl Keymap {
+    /// Applies the `[keys]` overrides. Unknown actions, unparsable keys and
+    /// conflicts are reported and skipped; a configured key wins over a
+    /// default one, and between two configured ones the first action in help
+    /// order keeps it.
+    pub fn from_config(overrides: &BTreeMap<String, KeyBinding>) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut problems = Vec::new();
+        let mut configured = Vec::new();
+
+        for (name, binding) in overrides {
+            let Some(action) = Action::from_name(name) else {
+                problems.push(format!("unknown action `{name}`"));
+                continue;
+            };
+            let mut chords = Vec::new();
+            for key in binding.keys() {
+                match KeyChord::parse(key) {
+                    Ok(chord) if !chords.contains(&chord) => chords.push(chord),
+                    Ok(_) => {}
+                    Err(error) => problems.push(format!("{name}: {error}")),
+                }
+            }
+            keymap.slot_mut(action).clone_from(&chords);
+            configured.push(action);
+        }
+
+        for action in Action::all().filter(|action| configured.contains(action)) {
+            for chord in keymap.chords(action).to_vec() {
+                for other in Action::all() {
+                    if other == action
+                        || !shares_context(action, other)
+                        || !keymap.chords(other).contains(&chord)
+                    {
+                        continue;
+                    }
+                    let claimed = configured.contains(&other) && other < action;
+                    if claimed {
+                        continue;
+                    }
+                    keymap.slot_mut(other).retain(|bound| *bound != chord);
+                    problems.push(format!(
+                        "`{chord}` is bound to both {} and {}; keeping {}",
+                        action.name(),
+                        other.name(),
+                        action.name()
+                    ));
+                }
+            }
+        }
+
+        (keymap, problems)
+    }
+}
+
+fn shares_context(left: Action, right: Action) -> bool {
+    left.spec()
+        .contexts
+        .iter()
+        .any(|context| right.spec().contexts.contains(context))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::keymap::{Action, KeyContext, Keymap};
+    use crate::settings::KeyBinding;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn config_overrides_replace_defaults_and_report_conflicts() {
+        let mut config = BTreeMap::new();
+        config.insert(
+            String::from("move_down"),
+            KeyBinding::Many(vec![String::from("e"), String::from("down")]),
+        );
+        config.insert(String::from("stage"), KeyBinding::One(String::from("d")));
+        config.insert(String::from("unstage"), KeyBinding::One(String::from("d")));
+        config.insert(String::from("launch"), KeyBinding::One(String::from("L")));
+        config.insert(
+            String::from("refresh"),
+            KeyBinding::One(String::from("hyper+r")),
+        );
+
+        let (keys, problems) = Keymap::from_config(&config);
+        let plain = |ch| key(KeyCode::Char(ch), KeyModifiers::NONE);
+
+        assert_eq!(
+            keys.action(KeyContext::Main, plain('e')),
+            Some(Action::MoveDown)
+        );
+        assert_eq!(keys.action(KeyContext::Main, plain('j')), None);
+        assert_eq!(
+            keys.action(KeyContext::Main, plain('d')),
+            Some(Action::Stage)
+        );
+        assert!(keys.chords(Action::Unstage).is_empty());
+        assert!(keys.chords(Action::DiscardWorktree).is_empty());
+        assert_eq!(keys.key(Action::Refresh), "-");
+        assert_eq!(
+            keys.action(KeyContext::GitPanel, plain('d')),
+            Some(Action::GitDeleteBranch)
+        );
+
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.contains("unknown action `launch`"))
+        );
+        assert!(problems.iter().any(|p| p.contains("hyper")));
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.contains("stage and discard_worktree"))
+        );
+        assert!(problems.iter().any(|p| p.contains("stage and unstage")));
+    }
+}
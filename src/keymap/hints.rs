@@ -0,0 +1,395 @@
+//! Footer and help text, built from the active bindings.
+
+use super::{Action, Keymap};
+
+pub fn footer_hint_pending_undo(action: &str) -> String {
+    format!("confirm {action}: Enter/y apply, n/Esc cancel")
+}
+
+pub fn footer_hint_terminal_search(keys: &Keymap) -> String {
+    format!(
+        "terminal search: type query, {} regex, {} case, Enter find, Esc cancel",
+        keys.key(Action::TerminalSearchToggleRegex),
+        keys.key(Action::TerminalSearchToggleCase),
+    )
+}
+
+pub fn footer_hint_finder() -> &'static str {
+    "find: type to match, Up/Down or Ctrl+n/p select, Enter jump, Esc cancel"
+}
+
+pub fn footer_hint_command_palette() -> &'static str {
+    "commands: type to match, Up/Down or Ctrl+n/p select, Enter run, Esc cancel"
+}
+
+pub fn footer_hint_tree_filter() -> &'static str {
+    "filter: type to narrow sidebar, Up/Down move, Enter keep, Esc clear"
+}
+
+pub fn footer_hint_git_panel(keys: &Keymap) -> String {
+    format!(
+        "git: {} move  Enter/{} switch  {} new branch  {} delete  {} commit  Esc close",
+        move_keys(keys),
+        keys.key(Action::GitSwitchBranch),
+        keys.key(Action::GitCreateBranch),
+        keys.key(Action::GitDeleteBranch),
+        keys.key(Action::GitCommit),
+    )
+}
+
+pub fn footer_hint_terminal_copy(keys: &Keymap) -> String {
+    format!(
+        "copy: {} move  {} fast scroll  {} mark  {} copy  {} {} search  {}/{} next/prev  {} next ref  {} open ref/link  {}/{} save  {} shell",
+        copy_move_keys(keys),
+        scroll_keys(keys),
+        keys.key(Action::TerminalSelectionAnchor),
+        keys.key(Action::TerminalYank),
+        keys.key(Action::TerminalSearch),
+        keys.key(Action::TerminalSearchBackward),
+        keys.key(Action::TerminalSearchNext),
+        keys.key(Action::TerminalSearchPrev),
+        keys.key(Action::TerminalNextRef),
+        keys.key(Action::TerminalOpenRef),
+        keys.key(Action::TerminalExport),
+        keys.key(Action::TerminalExportAnsi),
+        keys.key(Action::TerminalInteractiveMode),
+    )
+}
+
+pub fn footer_hint_terminal(keys: &Keymap) -> String {
+    format!(
+        "terminal: keys->shell, {} scroll, {} copy, {}, {} hide/unfocus",
+        scroll_keys(keys),
+        keys.key(Action::TerminalCopyMode),
+        terminal_tab_hint(keys),
+        keys.keys(Action::TerminalHide),
+    )
+}
+
+pub fn terminal_tab_hint(keys: &Keymap) -> String {
+    format!(
+        "{} new tab, {}/{} or {} switch, {} rename, {} close tab",
+        keys.key(Action::TerminalNewTab),
+        keys.key(Action::TerminalNextTab),
+        keys.key(Action::TerminalPrevTab),
+        select_tab_keys(keys),
+        keys.key(Action::TerminalRenameTab),
+        keys.key(Action::TerminalCloseTab),
+    )
+}
+
+pub fn footer_hint_terminal_rename() -> &'static str {
+    "rename tab: type a name, Enter save, Esc cancel"
+}
+
+pub fn footer_hint_task_picker(keys: &Keymap) -> String {
+    format!(
+        "tasks: {} select, Enter or 1-9 run, Esc close",
+        move_keys(keys)
+    )
+}
+
+pub fn footer_hint_settings(keys: &Keymap) -> String {
+    format!(
+        "settings: {} select, {}/{} change, Esc/{} close",
+        move_keys(keys),
+        keys.key(Action::SettingDecrease),
+        keys.key(Action::SettingIncrease),
+        keys.key(Action::ToggleSettings)
+    )
+}
+
+pub fn footer_hint_main(keys: &Keymap) -> String {
+    format!(
+        "{} pane  {} pane  {} move-or-scroll  {} toggle-stage  {} stage  {} unstage  {} undo  {} discard  {} reset-head  {} find  {} commands  {} filter  {} fold-dir  {} branches  {} commit  {} terminal  {} dock  {} tasks  {} settings  {} help  {} quit",
+        keys.key(Action::CycleFocusForward),
+        keys.key(Action::TogglePaneFocus),
+        move_keys(keys),
+        keys.key(Action::StageToggle),
+        keys.key(Action::Stage),
+        keys.key(Action::Unstage),
+        keys.key(Action::UndoToMainline),
+        keys.key(Action::DiscardWorktree),
+        keys.key(Action::ResetToHead),
+        keys.key(Action::OpenFinder),
+        keys.key(Action::OpenCommandPalette),
+        keys.key(Action::FilterTree),
+        keys.key(Action::ToggleDirectory),
+        keys.key(Action::ToggleGitPanel),
+        keys.key(Action::OpenCommitPrompt),
+        keys.key(Action::OpenTerminal),
+        keys.key(Action::ToggleTerminalPane),
+        keys.key(Action::OpenTaskPicker),
+        keys.key(Action::ToggleSettings),
+        keys.key(Action::ToggleHelp),
+        keys.key(Action::Quit),
+    )
+}
+
+/// How to get around the main view, the first footer hint that usually fits.
+pub fn footer_hint_main_navigation(keys: &Keymap) -> String {
+    format!(
+        "{}/{} pane  {} move  {} toggle  {}/{}/{}/{} navigate",
+        keys.key(Action::CycleFocusForward),
+        keys.key(Action::TogglePaneFocus),
+        move_keys(keys),
+        keys.key(Action::StageToggle),
+        keys.key(Action::PageUp),
+        keys.key(Action::PageDown),
+        keys.key(Action::JumpTop),
+        keys.key(Action::JumpBottom),
+    )
+}
+
+/// The main actions in a shorter form than `footer_hint_main`.
+pub fn footer_hint_main_compact(keys: &Keymap) -> String {
+    format!(
+        "{} stage  {} unstage  {} discard  {} undo  {} find  {} commands  {} filter  {} fold  {} branches  {} terminal  {} dock  {} help  {} quit",
+        keys.key(Action::Stage),
+        keys.key(Action::Unstage),
+        keys.key(Action::DiscardWorktree),
+        keys.key(Action::UndoToMainline),
+        keys.key(Action::OpenFinder),
+        keys.key(Action::OpenCommandPalette),
+        keys.key(Action::FilterTree),
+        keys.key(Action::ToggleDirectory),
+        keys.key(Action::ToggleGitPanel),
+        keys.key(Action::OpenTerminal),
+        keys.key(Action::ToggleTerminalPane),
+        keys.key(Action::ToggleHelp),
+        keys.key(Action::Quit),
+    )
+}
+
+fn move_keys(keys: &Keymap) -> String {
+    format!(
+        "{}/{}",
+        keys.key(Action::MoveDown),
+        keys.key(Action::MoveUp)
+    )
+}
+
+fn scroll_keys(keys: &Keymap) -> String {
+    format!(
+        "{}/{}",
+        keys.key(Action::TerminalScrollUp),
+        keys.key(Action::TerminalScrollDown)
+    )
+}
+
+/// `Alt+1-9` for the default `terminal_select_tab`.
+fn select_tab_keys(keys: &Keymap) -> String {
+    keys.chords(Action::TerminalSelectTab)
+        .first()
+        .map_or_else(|| String::from("-"), |chord| format!("{chord}-9"))
+}
+
+fn copy_move_keys(keys: &Keymap) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        keys.key(Action::TerminalCursorLeft),
+        keys.key(Action::TerminalCursorDown),
+        keys.key(Action::TerminalCursorUp),
+        keys.key(Action::TerminalCursorRight)
+    )
+}
+
+pub fn terminal_modal_interactive_hint(keys: &Keymap) -> String {
+    format!(
+        "interactive shell. {} scrolls faster. {} enters copy mode. {} hide/unfocus.",
+        scroll_keys(keys),
+        keys.key(Action::TerminalCopyMode),
+        keys.keys(Action::TerminalHide)
+    )
+}
+
+pub fn terminal_modal_copy_hint(keys: &Keymap) -> String {
+    format!(
+        "copy: {} move  {} fast scroll  {} mark  {} yank  {} search  {} search up  {}/{} next/prev  {} next file:line  {} open ref or link  {} save text  {} save with colors  {} shell",
+        copy_move_keys(keys),
+        scroll_keys(keys),
+        keys.key(Action::TerminalSelectionAnchor),
+        keys.key(Action::TerminalYank),
+        keys.key(Action::TerminalSearch),
+        keys.key(Action::TerminalSearchBackward),
+        keys.key(Action::TerminalSearchNext),
+        keys.key(Action::TerminalSearchPrev),
+        keys.key(Action::TerminalNextRef),
+        keys.keys(Action::TerminalOpenRef),
+        keys.key(Action::TerminalExport),
+        keys.key(Action::TerminalExportAnsi),
+        keys.key(Action::TerminalInteractiveMode),
+    )
+}
+
+/// Help modal lines for the main view.
+pub fn help_main_lines(keys: &Keymap) -> Vec<String> {
+    vec![
+        format!(
+            "{} toggles between sidebar and diff",
+            [Action::CycleFocusForward, Action::CycleFocusBackward]
+                .map(|action| keys.keys(action))
+                .join("/")
+        ),
+        format!(
+            "{} or {} move selection or scroll diff",
+            keys.keys(Action::MoveUp),
+            keys.keys(Action::MoveDown),
+        ),
+        format!("{} switch pane focus", keys.keys(Action::TogglePaneFocus)),
+        format!(
+            "{} toggle stage state; {} stage; {} unstage; {} undo to mainline",
+            keys.keys(Action::StageToggle),
+            keys.key(Action::Stage),
+            keys.key(Action::Unstage),
+            keys.key(Action::UndoToMainline),
+        ),
+        format!(
+            "{} discard unstaged edits (keeps staged); {} reset file to HEAD",
+            keys.key(Action::DiscardWorktree),
+            keys.key(Action::ResetToHead),
+        ),
+        String::from("Tree mode markers: left M staged (green), right M unstaged (red)"),
+        format!(
+            "{} fold/unfold directory; {} fold/unfold all; {}/{}/{} on a directory apply to all its files",
+            keys.key(Action::ToggleDirectory),
+            keys.key(Action::ToggleAllDirectories),
+            keys.key(Action::Stage),
+            keys.key(Action::Unstage),
+            keys.key(Action::StageToggle),
+        ),
+        format!(
+            "{}/{} jump to edge; {}/{} move by page",
+            keys.key(Action::JumpTop),
+            keys.key(Action::JumpBottom),
+            keys.key(Action::PageUp),
+            keys.key(Action::PageDown),
+        ),
+        format!(
+            "{} wrap long lines; {}/{} or Shift+wheel scroll the diff sideways when not wrapping",
+            keys.key(Action::ToggleWrap),
+            keys.key(Action::ScrollLeft),
+            keys.key(Action::ScrollRight),
+        ),
+        format!(
+            "{} branches; {} commit; {} terminal; {} run a configured task; {} settings; {} refresh",
+            keys.key(Action::ToggleGitPanel),
+            keys.key(Action::OpenCommitPrompt),
+            keys.keys(Action::OpenTerminal),
+            keys.key(Action::OpenTaskPicker),
+            keys.key(Action::ToggleSettings),
+            keys.key(Action::Refresh),
+        ),
+        format!(
+            "{} trust this repo's .dif.toml shell settings (ignored until trusted)",
+            keys.key(Action::TrustRepoConfig),
+        ),
+        format!(
+            "{} fuzzy find a changed file; {} filter the sidebar (Esc in filter clears)",
+            keys.key(Action::OpenFinder),
+            keys.key(Action::FilterTree),
+        ),
+        format!(
+            "{} command palette: every action with its key, greyed out where it does not apply",
+            keys.key(Action::OpenCommandPalette),
+        ),
+    ]
+}
+
+/// Help modal lines for the branch panel.
+pub fn help_git_lines(keys: &Keymap) -> Vec<String> {
+    vec![format!(
+        "Enter/{} switch branch; {} create; {} delete; {} commit prompt",
+        keys.keys(Action::GitSwitchBranch),
+        keys.keys(Action::GitCreateBranch),
+        keys.key(Action::GitDeleteBranch),
+        keys.key(Action::GitCommit),
+    )]
+}
+
+/// Help modal lines for the terminal.
+pub fn help_terminal_lines(keys: &Keymap) -> Vec<String> {
+    vec![
+        format!(
+            "{} copy mode; {} fast scroll; {} hide (tabs keep running)",
+            keys.key(Action::TerminalCopyMode),
+            scroll_keys(keys),
+            keys.keys(Action::TerminalHide),
+        ),
+        format!(
+            "{} new tab; {}/{} or {} switch; {} rename; {} close tab",
+            keys.key(Action::TerminalNewTab),
+            keys.key(Action::TerminalNextTab),
+            keys.key(Action::TerminalPrevTab),
+            select_tab_keys(keys),
+            keys.key(Action::TerminalRenameTab),
+            keys.key(Action::TerminalCloseTab),
+        ),
+        format!(
+            "Copy mode: {} saves the scrollback as text, {} with colors ([transcripts] dir in config)",
+            keys.key(Action::TerminalExport),
+            keys.key(Action::TerminalExportAnsi),
+        ),
+        String::from(
+            "Mouse: drag to copy a selection; double-click copies a word; Shift+mouse bypasses apps like vim",
+        ),
+        format!(
+            "Copy mode: {} or {} search ({} regex, {} case), {}/{} next/prev; {} next file:line reference; {} open it, or the underlined link under the cursor",
+            keys.key(Action::TerminalSearch),
+            keys.key(Action::TerminalSearchBackward),
+            keys.key(Action::TerminalSearchToggleRegex),
+            keys.key(Action::TerminalSearchToggleCase),
+            keys.key(Action::TerminalSearchNext),
+            keys.key(Action::TerminalSearchPrev),
+            keys.key(Action::TerminalNextRef),
+            keys.keys(Action::TerminalOpenRef),
+        ),
+        format!(
+            "Hidden tabs that ring the bell or exit get a footer badge; Notify Host Terminal ({}) forwards it",
+            keys.key(Action::ToggleSettings)
+        ),
+        format!(
+            "Docked ({} settings): Tab cycles into it, Esc returns to the diff; {} show/hide; {} {} resize",
+            keys.key(Action::ToggleSettings),
+            keys.key(Action::ToggleTerminalPane),
+            keys.key(Action::TerminalDockShrink),
+            keys.key(Action::TerminalDockGrow),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{footer_hint_main, footer_hint_terminal, terminal_modal_interactive_hint};
+    use crate::keymap::{Action, Keymap};
+
+    #[test]
+    fn footer_main_hint_contains_primary_keys() {
+        let keys = Keymap::default();
+        let hint = footer_hint_main(&keys);
+        for action in [
+            Action::Stage,
+            Action::UndoToMainline,
+            Action::OpenFinder,
+            Action::FilterTree,
+            Action::ToggleGitPanel,
+            Action::OpenTerminal,
+            Action::ToggleSettings,
+            Action::ToggleHelp,
+            Action::Quit,
+        ] {
+            assert!(hint.contains(&keys.key(action)), "{action:?}");
+        }
+    }
+
+    #[test]
+    fn terminal_hints_reference_copy_mode_key() {
+        let keys = Keymap::default();
+        let footer = footer_hint_terminal(&keys);
+        let modal = terminal_modal_interactive_hint(&keys);
+        assert!(footer.contains("Alt+c"));
+        assert!(modal.contains("Alt+c"));
+        assert!(footer.contains("Alt+t"));
+        assert!(footer.contains("Alt+x"));
+    }
+}
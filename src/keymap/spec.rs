@@ -0,0 +1,188 @@
+//! The table behind every `Action`: its `[keys]` name, where it applies,
+//! its default keys and its palette description.
+
+use super::{Action, KeyContext};
+
+pub(super) struct ActionSpec {
+    pub(super) action: Action,
+    name: &'static str,
+    pub(super) contexts: &'static [KeyContext],
+    pub(super) keys: &'static [&'static str],
+    description: &'static str,
+}
+
+const MAIN: &[KeyContext] = &[KeyContext::Main];
+const MAIN_AND_GIT: &[KeyContext] = &[KeyContext::Main, KeyContext::GitPanel];
+const LISTS: &[KeyContext] = &[KeyContext::Main, KeyContext::GitPanel, KeyContext::Picker];
+const GIT: &[KeyContext] = &[KeyContext::GitPanel];
+const TERMINAL: &[KeyContext] = &[KeyContext::Terminal];
+const PICKER: &[KeyContext] = &[KeyContext::Picker];
+
+const fn spec(
+    action: Action,
+    name: &'static str,
+    contexts: &'static [KeyContext],
+    keys: &'static [&'static str],
+    description: &'static str,
+) -> ActionSpec {
+    ActionSpec {
+        action,
+        name,
+        contexts,
+        keys,
+        description,
+    }
+}
+
+/// Every action with its config name and default keys, in help order. Rows
+/// follow the `Action` declaration order so `Action::spec` can index them.
+#[rustfmt::skip]
+pub(super) const ACTIONS: &[ActionSpec] = &[
+    spec(Action::CycleFocusForward, "cycle_focus_forward", MAIN, &["tab"], "focus next pane"),
+    spec(Action::CycleFocusBackward, "cycle_focus_backward", MAIN, &["shift+tab"], "focus previous pane"),
+    spec(Action::MoveUp, "move_up", LISTS, &["k", "up"], "move up or scroll the diff"),
+    spec(Action::MoveDown, "move_down", LISTS, &["j", "down"], "move down or scroll the diff"),
+    spec(Action::TogglePaneFocus, "toggle_pane_focus", MAIN, &["h", "l", "left", "right"], "switch between sidebar and diff"),
+    spec(Action::PageUp, "page_up", MAIN, &["pageup"], "move up a page"),
+    spec(Action::PageDown, "page_down", MAIN, &["pagedown"], "move down a page"),
+    spec(Action::JumpTop, "jump_top", MAIN, &["home"], "jump to the top"),
+    spec(Action::JumpBottom, "jump_bottom", MAIN, &["end"], "jump to the bottom"),
+    spec(Action::StageToggle, "stage_toggle", MAIN, &["enter", "space"], "toggle stage state"),
+    spec(Action::Stage, "stage", MAIN, &["s"], "stage the selection"),
+    spec(Action::Unstage, "unstage", MAIN, &["u"], "unstage the selection"),
+    spec(Action::UndoToMainline, "undo_to_mainline", MAIN, &["x"], "undo the file to mainline"),
+    spec(Action::DiscardWorktree, "discard_worktree", MAIN, &["d"], "discard unstaged edits"),
+    spec(Action::ResetToHead, "reset_to_head", MAIN, &["X"], "reset the file to HEAD"),
+    spec(Action::CycleDiffView, "cycle_diff_view", MAIN, &["v"], "cycle diff view mode"),
+    spec(Action::ToggleWrap, "toggle_wrap", MAIN, &["w"], "wrap or unwrap long lines"),
+    spec(Action::ScrollLeft, "scroll_left", MAIN, &["H"], "scroll the diff left"),
+    spec(Action::ScrollRight, "scroll_right", MAIN, &["L"], "scroll the diff right"),
+    spec(Action::ToggleSidebar, "toggle_sidebar", MAIN, &["b"], "show or hide the sidebar"),
+    spec(Action::SidebarNarrow, "sidebar_narrow", MAIN, &["["], "narrow the sidebar"),
+    spec(Action::SidebarWide, "sidebar_wide", MAIN, &["]"], "widen the sidebar"),
+    spec(Action::ToggleSettings, "toggle_settings", MAIN, &["o"], "open settings"),
+    spec(Action::SettingDecrease, "setting_decrease", PICKER, &["h", "left"], "step the setting back"),
+    spec(Action::SettingIncrease, "setting_increase", PICKER, &["l", "right"], "step the setting forward"),
+    spec(Action::ToggleGitPanel, "toggle_git_panel", MAIN_AND_GIT, &["g"], "open or close branches"),
+    spec(Action::OpenCommitPrompt, "open_commit_prompt", MAIN, &["c"], "write a commit"),
+    spec(Action::OpenTerminal, "open_terminal", MAIN, &[":", "!"], "open the terminal"),
+    spec(Action::ToggleTerminalPane, "toggle_terminal_pane", MAIN, &["T"], "show or hide the docked terminal"),
+    spec(Action::OpenTaskPicker, "open_task_picker", MAIN, &["R"], "run a configured task"),
+    spec(Action::TrustRepoConfig, "trust_repo_config", MAIN, &["ctrl+t"], "trust or untrust the repo's .dif.toml"),
+    spec(Action::TerminalDockShrink, "terminal_dock_shrink", MAIN, &["{"], "shrink the docked terminal"),
+    spec(Action::TerminalDockGrow, "terminal_dock_grow", MAIN, &["}"], "grow the docked terminal"),
+    spec(Action::OpenFinder, "open_finder", MAIN, &["/"], "fuzzy find a changed file"),
+    spec(Action::OpenCommandPalette, "open_command_palette", MAIN_AND_GIT, &["ctrl+p"], "open the command palette"),
+    spec(Action::FilterTree, "filter_tree", MAIN, &["f"], "filter the sidebar"),
+    spec(Action::ToggleDirectory, "toggle_directory", MAIN, &["z"], "fold or unfold a directory"),
+    spec(Action::ToggleAllDirectories, "toggle_all_directories", MAIN, &["Z"], "fold or unfold all directories"),
+    spec(Action::Refresh, "refresh", MAIN_AND_GIT, &["r"], "refresh"),
+    spec(Action::ToggleHelp, "toggle_help", MAIN, &["?"], "show help"),
+    spec(Action::Quit, "quit", MAIN_AND_GIT, &["q"], "quit"),
+    spec(Action::GitCreateBranch, "git_create_branch", GIT, &["n", "a"], "create a branch"),
+    spec(Action::GitSwitchBranch, "git_switch_branch", GIT, &["s"], "switch to the branch"),
+    spec(Action::GitDeleteBranch, "git_delete_branch", GIT, &["d"], "delete the branch"),
+    spec(Action::GitCommit, "git_commit", GIT, &["c"], "write a commit"),
+    spec(Action::TerminalCopyMode, "terminal_copy_mode", TERMINAL, &["alt+c"], "enter copy mode"),
+    spec(Action::TerminalHide, "terminal_hide", TERMINAL, &["esc", "ctrl+]", "ctrl+g", "ctrl+q", "ctrl+w"], "hide or unfocus the terminal"),
+    spec(Action::TerminalScrollUp, "terminal_scroll_up", TERMINAL, &["shift+up"], "scroll the terminal up fast"),
+    spec(Action::TerminalScrollDown, "terminal_scroll_down", TERMINAL, &["shift+down"], "scroll the terminal down fast"),
+    spec(Action::TerminalNewTab, "terminal_new_tab", TERMINAL, &["alt+t"], "open a terminal tab"),
+    spec(Action::TerminalCloseTab, "terminal_close_tab", TERMINAL, &["alt+x"], "close the terminal tab"),
+    spec(Action::TerminalNextTab, "terminal_next_tab", TERMINAL, &["alt+n"], "next terminal tab"),
+    spec(Action::TerminalPrevTab, "terminal_prev_tab", TERMINAL, &["alt+p"], "previous terminal tab"),
+    spec(Action::TerminalSelectTab, "terminal_select_tab", TERMINAL, &["alt+1"], "go to tab 1; 2-9 with the same modifiers"),
+    spec(Action::TerminalRenameTab, "terminal_rename_tab", TERMINAL, &["alt+r"], "rename the terminal tab"),
+    spec(Action::TerminalInteractiveMode, "terminal_interactive_mode", TERMINAL, &["i"], "leave copy mode"),
+    spec(Action::TerminalCursorUp, "terminal_cursor_up", TERMINAL, &["k", "up"], "move the copy cursor up"),
+    spec(Action::TerminalCursorDown, "terminal_cursor_down", TERMINAL, &["j", "down"], "move the copy cursor down"),
+    spec(Action::TerminalCursorLeft, "terminal_cursor_left", TERMINAL, &["h", "left"], "move the copy cursor left"),
+    spec(Action::TerminalCursorRight, "terminal_cursor_right", TERMINAL, &["l", "right"], "move the copy cursor right"),
+    spec(Action::TerminalSelectionAnchor, "terminal_selection_anchor", TERMINAL, &["v"], "mark a selection"),
+    spec(Action::TerminalYank, "terminal_yank", TERMINAL, &["y"], "copy the selection"),
+    spec(Action::TerminalSearch, "terminal_search", TERMINAL, &["/"], "search the scrollback"),
+    spec(Action::TerminalSearchBackward, "terminal_search_backward", TERMINAL, &["?"], "search the scrollback upward"),
+    spec(Action::TerminalSearchNext, "terminal_search_next", TERMINAL, &["n"], "next match"),
+    spec(Action::TerminalSearchPrev, "terminal_search_prev", TERMINAL, &["N"], "previous match"),
+    spec(Action::TerminalSearchToggleRegex, "terminal_search_toggle_regex", TERMINAL, &["ctrl+r"], "toggle regex search"),
+    spec(Action::TerminalSearchToggleCase, "terminal_search_toggle_case", TERMINAL, &["ctrl+t"], "toggle case-sensitive search"),
+    spec(Action::TerminalNextRef, "terminal_next_ref", TERMINAL, &["f"], "next file:line reference"),
+    spec(Action::TerminalOpenRef, "terminal_open_ref", TERMINAL, &["o", "enter"], "open the reference or link"),
+    spec(Action::TerminalExport, "terminal_export", TERMINAL, &["s"], "save the scrollback as text"),
+    spec(Action::TerminalExportAnsi, "terminal_export_ansi", TERMINAL, &["S"], "save the scrollback with colors"),
+];
+
+const _: () = {
+    let mut idx = 0;
+    while idx < ACTIONS.len() {
+        assert!(
+            ACTIONS[idx].action as usize == idx,
+            "ACTIONS is out of order"
+        );
+        idx += 1;
+    }
+};
+
+impl Action {
+    pub(super) fn spec(self) -> &'static ActionSpec {
+        &ACTIONS[self as usize]
+    }
+
+    /// The name used for the action under `[keys]`.
+    pub fn name(self) -> &'static str {
+        self.spec().name
+    }
+
+    pub fn description(self) -> &'static str {
+        self.spec().description
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS
+            .iter()
+            .find(|spec| spec.name == name)
+            .map(|spec| spec.action)
+    }
+
+    /// Whether the action belongs to `context`.
+    pub fn applies_in(self, context: KeyContext) -> bool {
+        self.spec().contexts.contains(&context)
+    }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        ACTIONS.iter().map(|spec| spec.action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ACTIONS, Action};
+    use crate::keymap::Keymap;
+
+    #[test]
+    fn actions_table_follows_the_enum_order() {
+        for (idx, spec) in ACTIONS.iter().enumerate() {
+            assert_eq!(spec.action as usize, idx, "{:?}", spec.action);
+        }
+        assert_eq!(Action::all().count(), ACTIONS.len());
+    }
+
+    #[test]
+    fn every_action_has_a_unique_name_and_parsable_defaults() {
+        let keys = Keymap::default();
+        let mut names = Action::all().map(Action::name).collect::<Vec<_>>();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+        for action in Action::all() {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+            assert!(!keys.chords(action).is_empty(), "{action:?}");
+            assert_eq!(
+                keys.chords(action).len(),
+                action.spec().keys.len(),
+                "{action:?}"
+            );
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::widgets::{Block, Borders};
 
-use crate::settings::{self, AppSettings, SidebarPosition};
+use crate::settings::{self, AppSettings, SidebarPosition, TerminalDock};
 
 pub const MIN_DIFF_WIDTH_WITH_SIDEBAR: u16 = 48;
 pub const TERMINAL_MODAL_WIDTH_PERCENT: u16 = 92;
@@ -12,6 +12,10 @@ pub const SETTINGS_MODAL_WIDTH_PERCENT: u16 = 70;
 pub const SETTINGS_MODAL_HEIGHT_PERCENT: u16 = 60;
 pub const HELP_MODAL_WIDTH_PERCENT: u16 = 76;
 pub const HELP_MODAL_HEIGHT_PERCENT: u16 = 74;
+pub const FINDER_MODAL_WIDTH_PERCENT: u16 = 70;
+pub const FINDER_MODAL_HEIGHT_PERCENT: u16 = 64;
+pub const TASK_MODAL_WIDTH_PERCENT: u16 = 60;
+pub const TASK_MODAL_HEIGHT_PERCENT: u16 = 50;
 
 pub fn split_root(root: Rect) -> (Rect, Rect) {
     let rows = Layout::default()
@@ -21,6 +25,38 @@ pub fn split_root(root: Rect) -> (Rect, Rect) {
     (rows[0], rows[1])
 }
 
+/// Carves a docked terminal pane out of the main area. Returns the area left
+/// for the sidebar and diff, plus the pane when the terminal is docked and
+/// visible.
+pub fn split_terminal_dock(
+    area: Rect,
+    settings: &AppSettings,
+    terminal_visible: bool,
+) -> (Rect, Option<Rect>) {
+    if !terminal_visible {
+        return (area, None);
+    }
+
+    let percent = settings.terminal_dock_size.clamp(
+        settings::TERMINAL_DOCK_SIZE_MIN,
+        settings::TERMINAL_DOCK_SIZE_MAX,
+    );
+    let direction = match settings.terminal_dock {
+        TerminalDock::Overlay => return (area, None),
+        TerminalDock::Bottom => Direction::Vertical,
+        TerminalDock::Right => Direction::Horizontal,
+    };
+
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints([
+            Constraint::Percentage(100 - percent),
+            Constraint::Percentage(percent),
+        ])
+        .split(area);
+    (chunks[0], Some(chunks[1]))
+}
+
 pub fn split_main_area(area: Rect, settings: &AppSettings) -> (Option<Rect>, Rect) {
     if !settings.sidebar_visible {
         return (None, area);
@@ -126,8 +162,30 @@ pub fn help_popup(area: Rect) -> Rect {
     centered_rect(HELP_MODAL_WIDTH_PERCENT, HELP_MODAL_HEIGHT_PERCENT, area)
 }
 
-pub fn terminal_output_area(area: Rect) -> Rect {
-    bordered_inner(terminal_popup(area))
+pub fn finder_popup(area: Rect) -> Rect {
+    centered_rect(
+        FINDER_MODAL_WIDTH_PERCENT,
+        FINDER_MODAL_HEIGHT_PERCENT,
+        area,
+    )
+}
+
+/// The rect the terminal frame occupies: the docked pane when docked,
+/// otherwise the centered popup.
+pub fn terminal_frame_area(root: Rect, settings: &AppSettings) -> Rect {
+    let (main_area, _) = split_root(root);
+    match split_terminal_dock(main_area, settings, true) {
+        (_, Some(dock)) => dock,
+        (_, None) => terminal_popup(root),
+    }
+}
+
+pub fn task_popup(area: Rect) -> Rect {
+    centered_rect(TASK_MODAL_WIDTH_PERCENT, TASK_MODAL_HEIGHT_PERCENT, area)
+}
+
+pub fn terminal_output_area(root: Rect, settings: &AppSettings) -> Rect {
+    bordered_inner(terminal_frame_area(root, settings))
 }
 
 pub fn bordered_inner(area: Rect) -> Rect {
@@ -138,9 +196,9 @@ pub fn bordered_inner(area: Rect) -> Rect {
 mod tests {
     use ratatui::layout::Rect;
 
-    use crate::settings::{AppSettings, SidebarPosition};
+    use crate::settings::{AppSettings, SidebarPosition, TerminalDock};
 
-    use super::{MIN_DIFF_WIDTH_WITH_SIDEBAR, centered_rect, split_main_area};
+    use super::{MIN_DIFF_WIDTH_WITH_SIDEBAR, centered_rect, split_main_area, split_terminal_dock};
 
     #[test]
     fn hides_sidebar_when_main_area_too_narrow() {
@@ -182,6 +240,37 @@ mod tests {
         assert_eq!(right_sidebar.x, right_diff.width);
     }
 
+    #[test]
+    fn docks_terminal_below_or_beside_main_area() {
+        let area = Rect::new(0, 0, 100, 50);
+        let bottom = AppSettings {
+            terminal_dock: TerminalDock::Bottom,
+            terminal_dock_size: 40,
+            ..AppSettings::default()
+        };
+        let right = AppSettings {
+            terminal_dock: TerminalDock::Right,
+            ..bottom.clone()
+        };
+
+        let (rest, dock) = split_terminal_dock(area, &bottom, true);
+        let dock = dock.expect("bottom dock should be present");
+        assert_eq!(dock.height, 20);
+        assert_eq!(dock.y, rest.height);
+        assert_eq!(rest.width, area.width);
+
+        let (rest, dock) = split_terminal_dock(area, &right, true);
+        let dock = dock.expect("right dock should be present");
+        assert_eq!(dock.width, 40);
+        assert_eq!(dock.x, rest.width);
+
+        assert_eq!(split_terminal_dock(area, &right, false), (area, None));
+        assert_eq!(
+            split_terminal_dock(area, &AppSettings::default(), true),
+            (area, None)
+        );
+    }
+
     #[test]
     fn centers_rect_with_requested_percentages() {
         let area = Rect::new(0, 0, 100, 50);
@@ -1,7 +1,9 @@
 pub mod app;
 pub mod diff;
+pub mod fuzzy;
 pub mod git;
 pub mod highlight;
+pub mod host;
 pub mod input;
 pub mod keymap;
 pub mod layout;
@@ -1,11 +1,15 @@
-use std::io;
+use std::io::{self, Write};
 use std::time::Duration;
 
 use anyhow::Result;
-use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    supports_keyboard_enhancement,
 };
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
@@ -14,14 +18,30 @@ use ratatui::layout::Rect;
 use dif::app::App;
 use dif::git;
 use dif::highlight::Highlighter;
+use dif::host;
 use dif::input;
+use dif::settings::{self, ThemeMode};
 use dif::ui;
 
+/// Upper bound on waiting for the host terminal's answers at startup; they
+/// normally arrive within a few milliseconds. Keys typed meanwhile are lost,
+/// so keep it short.
+const HOST_QUERY_TIMEOUT: Duration = Duration::from_millis(150);
+
 fn main() -> Result<()> {
     let repo_root = git::repo_root()?;
     let mut app = App::new(repo_root)?;
-    let highlighter = Highlighter::new()?;
-    let mut terminal_guard = TerminalGuard::new()?;
+    let mut highlighter = Highlighter::new()?;
+    let theme_problems = highlighter.load_custom_themes(&app.settings.themes);
+    app.report_config_problems("themes", &theme_problems);
+    let syntax_problems =
+        highlighter.load_syntaxes(settings::syntaxes_dir().as_deref(), &app.settings.syntaxes);
+    app.report_config_problems("syntaxes", &syntax_problems);
+    let mut terminal_guard = TerminalGuard::new(app.settings.kitty_keyboard)?;
+    if app.settings.theme_mode == ThemeMode::Auto {
+        // Before the event loop starts, so the replies never reach it.
+        app.set_host_background(host::background_color(HOST_QUERY_TIMEOUT));
+    }
 
     let run_result = run_app(terminal_guard.terminal_mut(), &mut app, &highlighter);
     let settings_result = app.flush_pending_settings();
@@ -46,6 +66,15 @@ fn run_app(
             needs_draw = true;
         }
 
+        let notifications = app.take_host_notifications();
+        if !notifications.is_empty() {
+            let backend = terminal.backend_mut();
+            for sequence in notifications {
+                backend.write_all(sequence.as_bytes())?;
+            }
+            backend.flush()?;
+        }
+
         let timeout = if app.terminal_open {
             poll_timeout_terminal
         } else {
@@ -81,10 +110,11 @@ fn run_app(
 
 struct TerminalGuard {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    keyboard_enhanced: bool,
 }
 
 impl TerminalGuard {
-    fn new() -> Result<Self> {
+    fn new(kitty_keyboard: bool) -> Result<Self> {
         enable_raw_mode()?;
 
         let mut stdout = io::stdout();
@@ -93,8 +123,21 @@ impl TerminalGuard {
             return Err(error.into());
         }
 
+        // Disambiguated keys let Ctrl+i, Tab and friends reach terminal tabs
+        // as distinct keys; hosts without support keep legacy input.
+        let keyboard_enhanced = kitty_keyboard
+            && supports_keyboard_enhancement().unwrap_or(false)
+            && execute!(
+                stdout,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            )
+            .is_ok();
+
         match Terminal::new(CrosstermBackend::new(stdout)) {
-            Ok(terminal) => Ok(Self { terminal }),
+            Ok(terminal) => Ok(Self {
+                terminal,
+                keyboard_enhanced,
+            }),
             Err(error) => {
                 let _ = disable_raw_mode();
                 Err(error.into())
@@ -109,6 +152,9 @@ impl TerminalGuard {
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
+        if self.keyboard_enhanced {
+            let _ = execute!(self.terminal.backend_mut(), PopKeyboardEnhancementFlags);
+        }
         let _ = disable_raw_mode();
         let _ = execute!(
             self.terminal.backend_mut(),
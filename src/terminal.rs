@@ -1,17 +1,46 @@
 use std::env;
+use std::fs::File;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver};
 
 use anyhow::{Context, Result};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use portable_pty::{Child, CommandBuilder, MasterPty, PtySize, native_pty_system};
-use vt100::Parser;
+use vt100::{MouseProtocolEncoding, MouseProtocolMode, Parser};
+
+use crate::settings::ShellSettings;
+
+mod keys;
+mod links;
+mod osc;
+mod selection;
+mod tmux;
+mod transcript;
+
+use keys::{KeyModes, KittyKeyboard, KittyRequest};
+use links::OpenLink;
+pub use links::{Hyperlink, is_openable_url, open_url};
+use osc::{OscEvent, OscScanner, Segment};
+pub use selection::{RowText, row_text, selection_text, word_bounds};
+pub use tmux::{
+    kill_persistent_session, list_persistent_sessions, persistent_session_prefix, persistent_shell,
+    rename_persistent_session, tmux_safe,
+};
+pub use transcript::{create_private_file, ensure_private_dir};
 
 const SCROLLBACK_LEN: usize = 20_000;
 
 pub struct TerminalSession {
     parser: Parser,
+    osc: OscScanner,
+    open_link: Option<OpenLink>,
+    hyperlinks: Vec<Hyperlink>,
+    /// Scrollback rows above the screen as of the last output.
+    history: usize,
+    clipboard: Option<String>,
+    kitty: Option<KittyKeyboard>,
+    log: Option<File>,
     master: Box<dyn MasterPty + Send>,
     writer: Box<dyn Write + Send>,
     child: Box<dyn Child + Send + Sync>,
@@ -19,6 +48,7 @@ pub struct TerminalSession {
     rows: u16,
     cols: u16,
     exited: bool,
+    success: Option<bool>,
 }
 
 struct ShellCommand {
@@ -27,7 +57,7 @@ struct ShellCommand {
 }
 
 impl TerminalSession {
-    pub fn start(cwd: &Path, rows: u16, cols: u16) -> Result<Self> {
+    pub fn start(cwd: &Path, rows: u16, cols: u16, config: &ShellSettings) -> Result<Self> {
         let pty_system = native_pty_system();
         let pair = pty_system.openpty(PtySize {
             rows,
@@ -36,11 +66,14 @@ impl TerminalSession {
             pixel_height: 0,
         })?;
 
-        let shell = shell_command();
+        let shell = shell_command(config);
         let mut command = CommandBuilder::new(shell.program);
         for arg in shell.args {
             command.arg(arg);
         }
+        for (key, value) in &config.env {
+            command.env(key, value);
+        }
         command.cwd(cwd);
 
         let child = pair
@@ -52,11 +85,24 @@ impl TerminalSession {
             .master
             .try_clone_reader()
             .context("failed to clone PTY reader")?;
-        let writer = pair
+        let mut writer = pair
             .master
             .take_writer()
             .context("failed to acquire PTY writer")?;
 
+        // The shell reads this from the PTY once it is ready, exactly as if
+        // it had been typed.
+        if let Some(initial) = config
+            .initial_command
+            .as_deref()
+            .filter(|command| !command.trim().is_empty())
+        {
+            writer
+                .write_all(format!("{initial}\r").as_bytes())
+                .context("failed to send initial terminal command")?;
+            writer.flush().context("failed to flush PTY writer")?;
+        }
+
         let (output_tx, output_rx) = mpsc::channel::<Vec<u8>>();
         std::thread::spawn(move || {
             let mut buf = [0u8; 8192];
@@ -76,6 +122,13 @@ impl TerminalSession {
 
         Ok(Self {
             parser: Parser::new(rows, cols, SCROLLBACK_LEN),
+            osc: OscScanner::default(),
+            open_link: None,
+            hyperlinks: Vec::new(),
+            history: 0,
+            clipboard: None,
+            kitty: None,
+            log: None,
             master: pair.master,
             writer,
             child,
@@ -83,6 +136,7 @@ impl TerminalSession {
             rows,
             cols,
             exited: false,
+            success: None,
         })
     }
 
@@ -90,13 +144,74 @@ impl TerminalSession {
         let mut updated = false;
 
         while let Ok(chunk) = self.output_rx.try_recv() {
-            self.parser.process(&chunk);
+            if let Some(log) = self.log.as_mut()
+                && log.write_all(&chunk).is_err()
+            {
+                self.log = None;
+            }
+            self.process(&chunk);
             updated = true;
         }
 
         updated
     }
 
+    fn process(&mut self, chunk: &[u8]) {
+        for segment in self.osc.scan(chunk) {
+            match segment {
+                Segment::Bytes(bytes) => {
+                    if let Some(link) = self.open_link.as_mut() {
+                        link.bytes.extend_from_slice(&bytes);
+                    }
+                    self.parser.process(&bytes);
+                }
+                Segment::Osc(event) => self.handle_osc(event),
+                Segment::Keyboard(request) => self.handle_keyboard_request(request),
+            }
+        }
+        self.history = self.history_len();
+    }
+
+    fn handle_osc(&mut self, event: OscEvent) {
+        match event {
+            OscEvent::Clipboard(text) => self.clipboard = Some(text),
+            OscEvent::HyperlinkStart(url) => self.start_hyperlink(url),
+            OscEvent::HyperlinkEnd => self.finish_hyperlink(),
+        }
+    }
+
+    /// The cursor as `(line, col)` counted from the top of the scrollback.
+    fn cursor_cell(&mut self) -> (usize, usize) {
+        let history = self.history_len();
+        let (row, col) = self.parser.screen().cursor_position();
+        (history + usize::from(row), usize::from(col))
+    }
+
+    fn handle_keyboard_request(&mut self, request: KittyRequest) {
+        let Some(reply) = self
+            .kitty
+            .as_mut()
+            .and_then(|keyboard| keyboard.apply(request))
+        else {
+            return;
+        };
+        if self.writer.write_all(reply.as_bytes()).is_ok() {
+            self.writer.flush().ok();
+        }
+    }
+
+    /// Lets the program turn on the kitty keyboard protocol. Until this is
+    /// called its requests are ignored, as in a terminal without support.
+    pub fn enable_kitty_keyboard(&mut self) {
+        self.kitty.get_or_insert_with(KittyKeyboard::default);
+    }
+
+    /// The latest OSC 52 clipboard write from the program, if it has not been
+    /// taken yet.
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
     pub fn resize(&mut self, rows: u16, cols: u16) -> Result<()> {
         if rows == 0 || cols == 0 {
             return Ok(());
@@ -112,13 +227,18 @@ impl TerminalSession {
             pixel_height: 0,
         })?;
         self.parser.set_size(rows, cols);
+        self.history = self.history_len();
         self.rows = rows;
         self.cols = cols;
         Ok(())
     }
 
     pub fn send_key(&mut self, key: KeyEvent) -> Result<()> {
-        let Some(bytes) = encode_key_event(key) else {
+        let modes = KeyModes {
+            application_cursor: self.parser.screen().application_cursor(),
+            kitty_flags: self.kitty.as_ref().map_or(0, KittyKeyboard::flags),
+        };
+        let Some(bytes) = keys::encode_key_event(key, modes) else {
             return Ok(());
         };
 
@@ -129,6 +249,32 @@ impl TerminalSession {
         Ok(())
     }
 
+    /// Whether the program asked for mouse reports (vim, htop, ...).
+    pub fn wants_mouse(&self) -> bool {
+        self.parser.screen().mouse_protocol_mode() != MouseProtocolMode::None
+    }
+
+    /// Reports `event` at the zero-based cell (`col`, `row`) to the program
+    /// in the protocol it enabled. Events the mode does not cover are dropped.
+    pub fn send_mouse(&mut self, event: MouseEvent, col: u16, row: u16) -> Result<()> {
+        let screen = self.parser.screen();
+        let Some(bytes) = encode_mouse_event(
+            event,
+            col,
+            row,
+            screen.mouse_protocol_mode(),
+            screen.mouse_protocol_encoding(),
+        ) else {
+            return Ok(());
+        };
+
+        self.writer
+            .write_all(&bytes)
+            .context("failed to write mouse event to PTY")?;
+        self.writer.flush().ok();
+        Ok(())
+    }
+
     pub fn send_text(&mut self, text: &str) -> Result<()> {
         self.writer
             .write_all(text.as_bytes())
@@ -149,10 +295,20 @@ impl TerminalSession {
         self.parser.screen()
     }
 
+    /// How many times the program has rung the bell.
+    pub fn bell_count(&self) -> usize {
+        self.parser.screen().audible_bell_count()
+    }
+
     pub fn is_exited(&self) -> bool {
         self.exited
     }
 
+    /// Whether the child exited cleanly; `None` until it has exited.
+    pub fn exit_success(&self) -> Option<bool> {
+        self.success
+    }
+
     pub fn poll_exit_message(&mut self) -> Result<Option<String>> {
         if self.exited {
             return Ok(None);
@@ -164,6 +320,7 @@ impl TerminalSession {
             .context("failed checking PTY child status")?;
         if let Some(status) = maybe_status {
             self.exited = true;
+            self.success = Some(status.success());
             return Ok(Some(status.to_string()));
         }
 
@@ -177,23 +334,48 @@ impl Drop for TerminalSession {
     }
 }
 
-fn shell_command() -> ShellCommand {
-    let env_shell = env::var("SHELL").ok().filter(|value| !value.is_empty());
+fn shell_command(config: &ShellSettings) -> ShellCommand {
+    let program = config
+        .program
+        .clone()
+        .filter(|program| !program.trim().is_empty())
+        .unwrap_or_else(|| {
+            let env_shell = env::var("SHELL").ok().filter(|value| !value.is_empty());
+            env_shell
+                .map(PathBuf::from)
+                .filter(|path| path.exists())
+                .or_else(default_shell_program)
+                .unwrap_or_else(fallback_shell_program)
+                .to_string_lossy()
+                .into_owned()
+        });
 
-    let preferred = env_shell
-        .map(PathBuf::from)
-        .filter(|path| path.exists())
-        .or_else(default_shell_program)
-        .unwrap_or_else(fallback_shell_program);
+    let args = match &config.args {
+        Some(args) => args.clone(),
+        None if cfg!(windows) => shell_args_for_windows(&program),
+        None => vec![String::from("-i")],
+    };
+
+    ShellCommand { program, args }
+}
 
-    let program = preferred.to_string_lossy().into_owned();
+/// Shell settings that run `command` once through the configured shell
+/// instead of starting an interactive session.
+pub fn task_shell(config: &ShellSettings, command: &str) -> ShellSettings {
+    let program = shell_command(config).program;
     let args = if cfg!(windows) {
-        shell_args_for_windows(&program)
+        task_args_for_windows(&program, command)
     } else {
-        vec![String::from("-i")]
+        vec![String::from("-c"), command.to_owned()]
     };
 
-    ShellCommand { program, args }
+    ShellSettings {
+        program: Some(program),
+        args: Some(args),
+        env: config.env.clone(),
+        initial_command: None,
+        persist: None,
+    }
 }
 
 fn default_shell_program() -> Option<PathBuf> {
@@ -236,109 +418,191 @@ fn shell_args_for_windows(program: &str) -> Vec<String> {
     }
 }
 
-fn encode_key_event(event: KeyEvent) -> Option<Vec<u8>> {
-    let mut bytes = Vec::new();
+fn task_args_for_windows(program: &str, command: &str) -> Vec<String> {
+    let lower = program.to_ascii_lowercase();
+    if lower.ends_with("cmd.exe") {
+        vec![String::from("/C"), command.to_owned()]
+    } else {
+        vec![
+            String::from("-NoLogo"),
+            String::from("-Command"),
+            command.to_owned(),
+        ]
+    }
+}
 
-    let has_alt = event.modifiers.contains(KeyModifiers::ALT);
-    let has_ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+fn encode_mouse_event(
+    event: MouseEvent,
+    col: u16,
+    row: u16,
+    mode: MouseProtocolMode,
+    encoding: MouseProtocolEncoding,
+) -> Option<Vec<u8>> {
+    let button_code = |button: MouseButton| match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+    };
 
-    match event.code {
-        KeyCode::Enter => bytes.push(b'\r'),
-        KeyCode::Tab => {
-            if event.modifiers.contains(KeyModifiers::SHIFT) {
-                bytes.extend_from_slice(b"\x1b[Z");
-            } else {
-                bytes.push(b'\t');
+    let (mut code, release) = match event.kind {
+        MouseEventKind::Down(button) => (button_code(button), false),
+        MouseEventKind::Up(button) => match mode {
+            MouseProtocolMode::None | MouseProtocolMode::Press => return None,
+            _ if encoding == MouseProtocolEncoding::Sgr => (button_code(button), true),
+            _ => (3, true),
+        },
+        MouseEventKind::Drag(button) => match mode {
+            MouseProtocolMode::ButtonMotion | MouseProtocolMode::AnyMotion => {
+                (button_code(button) + 32, false)
             }
-        }
-        KeyCode::BackTab => bytes.extend_from_slice(b"\x1b[Z"),
-        KeyCode::Backspace => bytes.push(0x7f),
-        KeyCode::Esc => bytes.push(0x1b),
-        KeyCode::Left => bytes.extend_from_slice(b"\x1b[D"),
-        KeyCode::Right => bytes.extend_from_slice(b"\x1b[C"),
-        KeyCode::Up => bytes.extend_from_slice(b"\x1b[A"),
-        KeyCode::Down => bytes.extend_from_slice(b"\x1b[B"),
-        KeyCode::Home => bytes.extend_from_slice(b"\x1b[H"),
-        KeyCode::End => bytes.extend_from_slice(b"\x1b[F"),
-        KeyCode::PageUp => bytes.extend_from_slice(b"\x1b[5~"),
-        KeyCode::PageDown => bytes.extend_from_slice(b"\x1b[6~"),
-        KeyCode::Insert => bytes.extend_from_slice(b"\x1b[2~"),
-        KeyCode::Delete => bytes.extend_from_slice(b"\x1b[3~"),
-        KeyCode::F(1) => bytes.extend_from_slice(b"\x1bOP"),
-        KeyCode::F(2) => bytes.extend_from_slice(b"\x1bOQ"),
-        KeyCode::F(3) => bytes.extend_from_slice(b"\x1bOR"),
-        KeyCode::F(4) => bytes.extend_from_slice(b"\x1bOS"),
-        KeyCode::F(5) => bytes.extend_from_slice(b"\x1b[15~"),
-        KeyCode::F(6) => bytes.extend_from_slice(b"\x1b[17~"),
-        KeyCode::F(7) => bytes.extend_from_slice(b"\x1b[18~"),
-        KeyCode::F(8) => bytes.extend_from_slice(b"\x1b[19~"),
-        KeyCode::F(9) => bytes.extend_from_slice(b"\x1b[20~"),
-        KeyCode::F(10) => bytes.extend_from_slice(b"\x1b[21~"),
-        KeyCode::F(11) => bytes.extend_from_slice(b"\x1b[23~"),
-        KeyCode::F(12) => bytes.extend_from_slice(b"\x1b[24~"),
-        KeyCode::Char(ch) if has_ctrl => {
-            let ctrl = ctrl_code(ch)?;
-            bytes.push(ctrl);
-        }
-        KeyCode::Char(ch) => {
-            let mut tmp = [0u8; 4];
-            let encoded = ch.encode_utf8(&mut tmp);
-            bytes.extend_from_slice(encoded.as_bytes());
-        }
+            _ => return None,
+        },
+        MouseEventKind::Moved if mode == MouseProtocolMode::AnyMotion => (3 + 32, false),
+        MouseEventKind::ScrollUp => (64, false),
+        MouseEventKind::ScrollDown => (65, false),
         _ => return None,
+    };
+    if mode == MouseProtocolMode::None {
+        return None;
     }
 
-    if has_alt && !bytes.is_empty() {
-        bytes.insert(0, 0x1b);
+    if event.modifiers.contains(KeyModifiers::SHIFT) {
+        code += 4;
+    }
+    if event.modifiers.contains(KeyModifiers::ALT) {
+        code += 8;
+    }
+    if event.modifiers.contains(KeyModifiers::CONTROL) {
+        code += 16;
     }
 
-    Some(bytes)
-}
-
-fn ctrl_code(ch: char) -> Option<u8> {
-    match ch {
-        ' ' | '2' | '@' => Some(0),
-        'a'..='z' => Some((ch as u8 - b'a') + 1),
-        'A'..='Z' => Some((ch as u8 - b'A') + 1),
-        '3' | '[' => Some(27),
-        '4' | '\\' => Some(28),
-        '5' | ']' => Some(29),
-        '6' | '^' => Some(30),
-        '7' | '/' | '_' => Some(31),
-        '8' | '?' => Some(127),
-        _ => None,
+    let (x, y) = (u32::from(col) + 1, u32::from(row) + 1);
+    match encoding {
+        MouseProtocolEncoding::Sgr => {
+            Some(format!("\x1b[<{code};{x};{y}{}", if release { 'm' } else { 'M' }).into_bytes())
+        }
+        MouseProtocolEncoding::Default => {
+            let [x, y] = [x, y].map(|value| u8::try_from(value + 32).ok());
+            let mut bytes = b"\x1b[M".to_vec();
+            bytes.extend_from_slice(&[32 + code, x?, y?]);
+            Some(bytes)
+        }
+        MouseProtocolEncoding::Utf8 => {
+            let mut out = String::from("\x1b[M");
+            for value in [u32::from(code) + 32, x + 32, y + 32] {
+                out.push(char::from_u32(value)?);
+            }
+            Some(out.into_bytes())
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-
-    use super::{ctrl_code, encode_key_event};
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+    use vt100::{MouseProtocolEncoding, MouseProtocolMode};
 
-    #[test]
-    fn encodes_ctrl_character() {
-        let key = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
-        assert_eq!(encode_key_event(key), Some(vec![3]));
-    }
+    use super::{encode_mouse_event, shell_command, task_shell};
+    use crate::settings::ShellSettings;
 
     #[test]
-    fn encodes_alt_character_with_escape_prefix() {
-        let key = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::ALT);
-        assert_eq!(encode_key_event(key), Some(vec![0x1b, b'x']));
+    fn encodes_mouse_events_for_the_enabled_protocol() {
+        let mouse = |kind| MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        let press = mouse(MouseEventKind::Down(MouseButton::Left));
+        let release = mouse(MouseEventKind::Up(MouseButton::Left));
+        let drag = mouse(MouseEventKind::Drag(MouseButton::Left));
+
+        assert_eq!(
+            encode_mouse_event(
+                press,
+                4,
+                9,
+                MouseProtocolMode::PressRelease,
+                MouseProtocolEncoding::Sgr
+            ),
+            Some(b"\x1b[<0;5;10M".to_vec())
+        );
+        assert_eq!(
+            encode_mouse_event(
+                release,
+                4,
+                9,
+                MouseProtocolMode::PressRelease,
+                MouseProtocolEncoding::Sgr
+            ),
+            Some(b"\x1b[<0;5;10m".to_vec())
+        );
+        assert_eq!(
+            encode_mouse_event(
+                release,
+                0,
+                0,
+                MouseProtocolMode::PressRelease,
+                MouseProtocolEncoding::Default
+            ),
+            Some(vec![0x1b, b'[', b'M', 35, 33, 33])
+        );
+        assert_eq!(
+            encode_mouse_event(
+                drag,
+                0,
+                0,
+                MouseProtocolMode::PressRelease,
+                MouseProtocolEncoding::Default
+            ),
+            None
+        );
+        assert_eq!(
+            encode_mouse_event(
+                press,
+                0,
+                0,
+                MouseProtocolMode::None,
+                MouseProtocolEncoding::Default
+            ),
+            None
+        );
     }
 
     #[test]
-    fn encodes_shift_tab_sequence() {
-        let key = KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT);
-        assert_eq!(encode_key_event(key), Some(b"\x1b[Z".to_vec()));
+    fn configured_shell_program_and_args_replace_defaults() {
+        let config = ShellSettings {
+            program: Some(String::from("/usr/bin/fish")),
+            args: Some(vec![String::from("--login")]),
+            ..ShellSettings::default()
+        };
+        let shell = shell_command(&config);
+        assert_eq!(shell.program, "/usr/bin/fish");
+        assert_eq!(shell.args, vec![String::from("--login")]);
     }
 
     #[test]
-    fn maps_ctrl_code_variants() {
-        assert_eq!(ctrl_code('a'), Some(1));
-        assert_eq!(ctrl_code('A'), Some(1));
-        assert_eq!(ctrl_code(']'), Some(29));
-        assert_eq!(ctrl_code('~'), None);
+    fn task_shell_runs_command_once_with_shell_env() {
+        let mut config = ShellSettings {
+            program: Some(String::from("/bin/sh")),
+            args: Some(vec![String::from("-l")]),
+            initial_command: Some(String::from("agent")),
+            ..ShellSettings::default()
+        };
+        config
+            .env
+            .insert(String::from("RUST_LOG"), String::from("debug"));
+
+        let task = task_shell(&config, "cargo test");
+
+        assert_eq!(task.program.as_deref(), Some("/bin/sh"));
+        if !cfg!(windows) {
+            assert_eq!(
+                task.args,
+                Some(vec![String::from("-c"), String::from("cargo test")])
+            );
+        }
+        assert_eq!(task.initial_command, None);
+        assert_eq!(task.env, config.env);
     }
 }
@@ -0,0 +1,504 @@
+//! Turns crossterm key events into the bytes a program in the PTY expects:
+//! xterm sequences by default, or kitty keyboard protocol codes once the
+//! program asks for them.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Kitty flag 1: report modified and ambiguous keys as `CSI … u`.
+const KITTY_DISAMBIGUATE: u8 = 0b1;
+/// Kitty flag 8: report every key, plain text included, as `CSI … u`.
+const KITTY_ALL_KEYS: u8 = 0b1000;
+/// The kitty flags dif can honor; event types, alternate keys and associated
+/// text are not reported.
+const KITTY_SUPPORTED: u8 = KITTY_DISAMBIGUATE | KITTY_ALL_KEYS;
+const KITTY_STACK_LIMIT: usize = 16;
+
+/// Terminal modes that change how keys are encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct KeyModes {
+    /// DECCKM: unmodified arrows, Home and End are sent as `SS3` sequences.
+    pub application_cursor: bool,
+    /// Active kitty keyboard protocol flags; zero means legacy encoding.
+    pub kitty_flags: u8,
+}
+
+/// A kitty keyboard protocol request (`CSI > u`, `CSI < u`, `CSI = u`,
+/// `CSI ? u`) from the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum KittyRequest {
+    Push(u8),
+    Pop(usize),
+    Set(u8, u8),
+    Query,
+}
+
+/// The program's stack of kitty keyboard flags.
+#[derive(Debug, Default)]
+pub(super) struct KittyKeyboard {
+    stack: Vec<u8>,
+}
+
+impl KittyKeyboard {
+    pub(super) fn flags(&self) -> u8 {
+        self.stack.last().copied().unwrap_or(0)
+    }
+
+    /// Applies `request`; returns the reply owed to the program, if any.
+    pub(super) fn apply(&mut self, request: KittyRequest) -> Option<String> {
+        match request {
+            KittyRequest::Push(flags) => {
+                if self.stack.len() == KITTY_STACK_LIMIT {
+                    self.stack.remove(0);
+                }
+                self.stack.push(flags & KITTY_SUPPORTED);
+            }
+            KittyRequest::Pop(count) => {
+                let keep = self.stack.len().saturating_sub(count);
+                self.stack.truncate(keep);
+            }
+            KittyRequest::Set(flags, mode) => {
+                let current = self.flags();
+                let next = match mode {
+                    2 => current | flags,
+                    3 => current & !flags,
+                    _ => flags,
+                } & KITTY_SUPPORTED;
+                match self.stack.last_mut() {
+                    Some(top) => *top = next,
+                    None => self.stack.push(next),
+                }
+            }
+            KittyRequest::Query => return Some(format!("\x1b[?{}u", self.flags())),
+        }
+        None
+    }
+}
+
+/// Parses the parameters and final byte of a CSI sequence as a kitty
+/// keyboard request.
+pub(super) fn parse_kitty_request(params: &[u8], final_byte: u8) -> Option<KittyRequest> {
+    if final_byte != b'u' {
+        return None;
+    }
+    let (&prefix, rest) = params.split_first()?;
+    let rest = std::str::from_utf8(rest).ok()?;
+    let mut numbers = rest.split(';').map(|part| {
+        if part.is_empty() {
+            Some(None)
+        } else {
+            part.parse::<u32>().ok().map(Some)
+        }
+    });
+    let first = numbers.next().flatten();
+    let second = numbers.next().flatten();
+
+    match prefix {
+        b'>' => Some(KittyRequest::Push(first?.unwrap_or(0) as u8)),
+        b'<' => Some(KittyRequest::Pop(first?.unwrap_or(1).max(1) as usize)),
+        b'=' => Some(KittyRequest::Set(
+            first?.unwrap_or(0) as u8,
+            second.flatten().unwrap_or(1) as u8,
+        )),
+        b'?' if rest.is_empty() => Some(KittyRequest::Query),
+        _ => None,
+    }
+}
+
+pub(super) fn encode_key_event(event: KeyEvent, modes: KeyModes) -> Option<Vec<u8>> {
+    if modes.kitty_flags != 0
+        && let Some(bytes) = encode_kitty_key(event, modes.kitty_flags)
+    {
+        return Some(bytes);
+    }
+
+    let modifiers = event.modifiers;
+    let has_alt = modifiers.contains(KeyModifiers::ALT);
+    let has_ctrl = modifiers.contains(KeyModifiers::CONTROL);
+    let param = modifier_param(modifiers);
+
+    let bytes = match event.code {
+        KeyCode::Enter => alt_prefixed(has_alt, b"\r"),
+        KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => backtab(modifiers),
+        KeyCode::BackTab => backtab(modifiers),
+        KeyCode::Tab => alt_prefixed(has_alt, b"\t"),
+        KeyCode::Backspace => alt_prefixed(has_alt, if has_ctrl { b"\x08" } else { b"\x7f" }),
+        KeyCode::Esc => alt_prefixed(has_alt, b"\x1b"),
+        KeyCode::Up => cursor_key(b'A', param, modes),
+        KeyCode::Down => cursor_key(b'B', param, modes),
+        KeyCode::Right => cursor_key(b'C', param, modes),
+        KeyCode::Left => cursor_key(b'D', param, modes),
+        KeyCode::Home => cursor_key(b'H', param, modes),
+        KeyCode::End => cursor_key(b'F', param, modes),
+        KeyCode::Insert => tilde_key(2, param),
+        KeyCode::Delete => tilde_key(3, param),
+        KeyCode::PageUp => tilde_key(5, param),
+        KeyCode::PageDown => tilde_key(6, param),
+        KeyCode::F(number @ 1..=4) => {
+            let final_byte = b"PQRS"[usize::from(number - 1)];
+            match param {
+                1 => vec![0x1b, b'O', final_byte],
+                _ => format!("\x1b[1;{param}{}", final_byte as char).into_bytes(),
+            }
+        }
+        KeyCode::F(number @ 5..=12) => {
+            let code = [15, 17, 18, 19, 20, 21, 23, 24][usize::from(number - 5)];
+            tilde_key(code, param)
+        }
+        KeyCode::Char(ch) => {
+            let ch = shifted(ch, modifiers);
+            match ctrl_code(ch).filter(|_| has_ctrl) {
+                Some(ctrl) => alt_prefixed(has_alt, &[ctrl]),
+                None => {
+                    let mut tmp = [0u8; 4];
+                    alt_prefixed(has_alt, ch.encode_utf8(&mut tmp).as_bytes())
+                }
+            }
+        }
+        _ => return None,
+    };
+
+    Some(bytes)
+}
+
+/// Encodes `event` as `CSI code ; modifiers u` when the active kitty flags
+/// call for it; `None` falls back to the legacy sequence, which the
+/// protocol keeps for cursor and function keys.
+fn encode_kitty_key(event: KeyEvent, flags: u8) -> Option<Vec<u8>> {
+    let modifiers = event.modifiers;
+    let all_keys = flags & KITTY_ALL_KEYS != 0;
+    let text_modifiers = modifiers.difference(KeyModifiers::SHIFT).is_empty();
+    let modified = !modifiers.is_empty();
+
+    let code = match event.code {
+        KeyCode::Esc => 27,
+        KeyCode::Enter if all_keys || modified => 13,
+        KeyCode::Tab if all_keys || modified => 9,
+        KeyCode::BackTab => {
+            return Some(kitty_sequence(9, modifiers | KeyModifiers::SHIFT));
+        }
+        KeyCode::Backspace if all_keys || modified => 127,
+        KeyCode::Char(ch) if all_keys || !text_modifiers => {
+            u32::from(ch.to_lowercase().next().unwrap_or(ch))
+        }
+        _ => return None,
+    };
+
+    Some(kitty_sequence(code, modifiers))
+}
+
+fn kitty_sequence(code: u32, modifiers: KeyModifiers) -> Vec<u8> {
+    let mut param = modifier_param(modifiers);
+    if modifiers.contains(KeyModifiers::SUPER) {
+        param += 8;
+    }
+    if param == 1 {
+        format!("\x1b[{code}u").into_bytes()
+    } else {
+        format!("\x1b[{code};{param}u").into_bytes()
+    }
+}
+
+/// The xterm modifier parameter: 1 plus Shift 1, Alt 2, Ctrl 4.
+fn modifier_param(modifiers: KeyModifiers) -> u8 {
+    let mut param = 1;
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        param += 1;
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        param += 2;
+    }
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        param += 4;
+    }
+    param
+}
+
+fn cursor_key(final_byte: u8, param: u8, modes: KeyModes) -> Vec<u8> {
+    match param {
+        1 if modes.application_cursor => vec![0x1b, b'O', final_byte],
+        1 => vec![0x1b, b'[', final_byte],
+        _ => format!("\x1b[1;{param}{}", final_byte as char).into_bytes(),
+    }
+}
+
+fn tilde_key(code: u8, param: u8) -> Vec<u8> {
+    match param {
+        1 => format!("\x1b[{code}~").into_bytes(),
+        _ => format!("\x1b[{code};{param}~").into_bytes(),
+    }
+}
+
+fn backtab(modifiers: KeyModifiers) -> Vec<u8> {
+    match modifier_param(modifiers.union(KeyModifiers::SHIFT)) {
+        2 => b"\x1b[Z".to_vec(),
+        param => format!("\x1b[1;{param}Z").into_bytes(),
+    }
+}
+
+fn alt_prefixed(has_alt: bool, bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 1);
+    if has_alt {
+        out.push(0x1b);
+    }
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Some terminals report Shift with the unshifted letter.
+fn shifted(ch: char, modifiers: KeyModifiers) -> char {
+    if modifiers.contains(KeyModifiers::SHIFT) && ch.is_ascii_lowercase() {
+        ch.to_ascii_uppercase()
+    } else {
+        ch
+    }
+}
+
+pub(super) fn ctrl_code(ch: char) -> Option<u8> {
+    match ch {
+        ' ' | '2' | '@' => Some(0),
+        'a'..='z' => Some((ch as u8 - b'a') + 1),
+        'A'..='Z' => Some((ch as u8 - b'A') + 1),
+        '3' | '[' => Some(27),
+        '4' | '\\' => Some(28),
+        '5' | ']' => Some(29),
+        '6' | '^' => Some(30),
+        '7' | '/' | '_' => Some(31),
+        '8' | '?' => Some(127),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{
+        KeyModes, KittyKeyboard, KittyRequest, ctrl_code, encode_key_event, parse_kitty_request,
+    };
+
+    fn encode(code: KeyCode, modifiers: KeyModifiers) -> Vec<u8> {
+        encode_key_event(KeyEvent::new(code, modifiers), KeyModes::default()).unwrap()
+    }
+
+    fn encode_kitty(code: KeyCode, modifiers: KeyModifiers, flags: u8) -> Vec<u8> {
+        let modes = KeyModes {
+            application_cursor: false,
+            kitty_flags: flags,
+        };
+        encode_key_event(KeyEvent::new(code, modifiers), modes).unwrap()
+    }
+
+    #[test]
+    fn encodes_ctrl_character() {
+        assert_eq!(encode(KeyCode::Char('c'), KeyModifiers::CONTROL), vec![3]);
+    }
+
+    #[test]
+    fn encodes_alt_character_with_escape_prefix() {
+        assert_eq!(
+            encode(KeyCode::Char('x'), KeyModifiers::ALT),
+            vec![0x1b, b'x']
+        );
+    }
+
+    #[test]
+    fn encodes_alt_shift_and_ctrl_alt_characters() {
+        let alt_shift = KeyModifiers::ALT | KeyModifiers::SHIFT;
+        assert_eq!(encode(KeyCode::Char('X'), alt_shift), b"\x1bX".to_vec());
+        assert_eq!(encode(KeyCode::Char('x'), alt_shift), b"\x1bX".to_vec());
+        assert_eq!(
+            encode(
+                KeyCode::Char('a'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ),
+            vec![0x1b, 1]
+        );
+        assert_eq!(
+            encode(KeyCode::Backspace, KeyModifiers::ALT),
+            vec![0x1b, 0x7f]
+        );
+        assert_eq!(encode(KeyCode::Backspace, KeyModifiers::CONTROL), vec![8]);
+    }
+
+    #[test]
+    fn encodes_shift_tab_sequence() {
+        assert_eq!(
+            encode(KeyCode::Tab, KeyModifiers::SHIFT),
+            b"\x1b[Z".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::BackTab, KeyModifiers::SHIFT),
+            b"\x1b[Z".to_vec()
+        );
+        assert_eq!(
+            encode(
+                KeyCode::BackTab,
+                KeyModifiers::SHIFT | KeyModifiers::CONTROL
+            ),
+            b"\x1b[1;6Z".to_vec()
+        );
+    }
+
+    #[test]
+    fn encodes_arrows_with_modifiers_and_cursor_mode() {
+        assert_eq!(encode(KeyCode::Up, KeyModifiers::NONE), b"\x1b[A".to_vec());
+        assert_eq!(
+            encode(KeyCode::Left, KeyModifiers::CONTROL),
+            b"\x1b[1;5D".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::Right, KeyModifiers::SHIFT | KeyModifiers::ALT),
+            b"\x1b[1;4C".to_vec()
+        );
+        assert_eq!(
+            encode(
+                KeyCode::Down,
+                KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CONTROL
+            ),
+            b"\x1b[1;8B".to_vec()
+        );
+
+        let application = KeyModes {
+            application_cursor: true,
+            kitty_flags: 0,
+        };
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(encode_key_event(up, application), Some(b"\x1bOA".to_vec()));
+        let ctrl_up = KeyEvent::new(KeyCode::Up, KeyModifiers::CONTROL);
+        assert_eq!(
+            encode_key_event(ctrl_up, application),
+            Some(b"\x1b[1;5A".to_vec())
+        );
+    }
+
+    #[test]
+    fn encodes_home_end_and_page_keys_with_modifiers() {
+        assert_eq!(
+            encode(KeyCode::Home, KeyModifiers::NONE),
+            b"\x1b[H".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::End, KeyModifiers::SHIFT),
+            b"\x1b[1;2F".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::PageUp, KeyModifiers::NONE),
+            b"\x1b[5~".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::PageDown, KeyModifiers::CONTROL),
+            b"\x1b[6;5~".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::Delete, KeyModifiers::ALT),
+            b"\x1b[3;3~".to_vec()
+        );
+    }
+
+    #[test]
+    fn encodes_function_keys_with_modifiers() {
+        assert_eq!(
+            encode(KeyCode::F(1), KeyModifiers::NONE),
+            b"\x1bOP".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::F(2), KeyModifiers::SHIFT),
+            b"\x1b[1;2Q".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::F(5), KeyModifiers::NONE),
+            b"\x1b[15~".to_vec()
+        );
+        assert_eq!(
+            encode(KeyCode::F(12), KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+            b"\x1b[24;6~".to_vec()
+        );
+    }
+
+    #[test]
+    fn encodes_kitty_disambiguated_keys() {
+        let flags = 1;
+        assert_eq!(
+            encode_kitty(KeyCode::Char('a'), KeyModifiers::NONE, flags),
+            b"a".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(KeyCode::Char('i'), KeyModifiers::CONTROL, flags),
+            b"\x1b[105;5u".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(
+                KeyCode::Char('X'),
+                KeyModifiers::ALT | KeyModifiers::SHIFT,
+                flags
+            ),
+            b"\x1b[120;4u".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(KeyCode::Esc, KeyModifiers::NONE, flags),
+            b"\x1b[27u".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(KeyCode::Enter, KeyModifiers::NONE, flags),
+            b"\r".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(KeyCode::Enter, KeyModifiers::SHIFT, flags),
+            b"\x1b[13;2u".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(KeyCode::Left, KeyModifiers::CONTROL, flags),
+            b"\x1b[1;5D".to_vec()
+        );
+        assert_eq!(
+            encode_kitty(KeyCode::Char('a'), KeyModifiers::NONE, 1 | 8),
+            b"\x1b[97u".to_vec()
+        );
+    }
+
+    #[test]
+    fn tracks_kitty_flag_stack_and_answers_queries() {
+        let mut keyboard = KittyKeyboard::default();
+        assert_eq!(
+            keyboard.apply(KittyRequest::Query).as_deref(),
+            Some("\x1b[?0u")
+        );
+
+        keyboard.apply(KittyRequest::Push(0b11111));
+        assert_eq!(keyboard.flags(), 0b1001);
+        keyboard.apply(KittyRequest::Push(1));
+        keyboard.apply(KittyRequest::Set(8, 2));
+        assert_eq!(keyboard.flags(), 9);
+        keyboard.apply(KittyRequest::Set(8, 3));
+        assert_eq!(keyboard.flags(), 1);
+        keyboard.apply(KittyRequest::Pop(1));
+        assert_eq!(keyboard.flags(), 9);
+        keyboard.apply(KittyRequest::Pop(5));
+        assert_eq!(keyboard.flags(), 0);
+    }
+
+    #[test]
+    fn parses_kitty_requests() {
+        assert_eq!(
+            parse_kitty_request(b">1", b'u'),
+            Some(KittyRequest::Push(1))
+        );
+        assert_eq!(parse_kitty_request(b"<", b'u'), Some(KittyRequest::Pop(1)));
+        assert_eq!(parse_kitty_request(b"<3", b'u'), Some(KittyRequest::Pop(3)));
+        assert_eq!(
+            parse_kitty_request(b"=5;2", b'u'),
+            Some(KittyRequest::Set(5, 2))
+        );
+        assert_eq!(parse_kitty_request(b"?", b'u'), Some(KittyRequest::Query));
+        assert_eq!(parse_kitty_request(b"", b'u'), None);
+        assert_eq!(parse_kitty_request(b">1", b'm'), None);
+    }
+
+    #[test]
+    fn maps_ctrl_code_variants() {
+        assert_eq!(ctrl_code('a'), Some(1));
+        assert_eq!(ctrl_code('A'), Some(1));
+        assert_eq!(ctrl_code(']'), Some(29));
+        assert_eq!(ctrl_code('~'), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+use super::{TerminalSession, osc};
+
+const MAX_HYPERLINKS: usize = 512;
+
+/// An OSC 8 hyperlink: the text it was printed over, where it points, and
+/// the cells it covers as `(line, col)` counted from the top of the
+/// scrollback, with `end` exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    pub text: String,
+    pub url: String,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+/// URL schemes a link may hand to the platform opener; anything else could
+/// reach local files or app handlers.
+const OPENABLE_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// An OSC 8 link whose closing sequence has not arrived yet.
+pub(super) struct OpenLink {
+    url: String,
+    pub(super) bytes: Vec<u8>,
+    start: (usize, usize),
+}
+
+impl TerminalSession {
+    pub(super) fn start_hyperlink(&mut self, url: String) {
+        self.finish_hyperlink();
+        self.open_link = Some(OpenLink {
+            url,
+            bytes: Vec::new(),
+            start: self.cursor_cell(),
+        });
+    }
+
+    pub(super) fn finish_hyperlink(&mut self) {
+        let Some(OpenLink { url, bytes, start }) = self.open_link.take() else {
+            return;
+        };
+        let text = osc::printable_text(&bytes);
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let end = self.cursor_cell();
+        self.hyperlinks.retain(|link| link.start != start);
+        self.hyperlinks.push(Hyperlink {
+            text,
+            url,
+            start,
+            end,
+        });
+        if self.hyperlinks.len() > MAX_HYPERLINKS {
+            self.hyperlinks.remove(0);
+        }
+    }
+
+    /// OSC 8 links seen so far, oldest first.
+    pub fn hyperlinks(&self) -> &[Hyperlink] {
+        &self.hyperlinks
+    }
+
+    /// Links wholly inside the current view as `(first row, last row, link)`
+    /// in screen rows. A link only counts while its cells still hold the
+    /// text it was printed with, so cleared or overwritten links drop out.
+    pub fn visible_hyperlinks(&self) -> Vec<(usize, usize, &Hyperlink)> {
+        let screen = self.parser.screen();
+        let rows = usize::from(screen.size().0);
+        let top = self.history.saturating_sub(screen.scrollback());
+        let to_u16 = |value: usize| u16::try_from(value).ok();
+
+        self.hyperlinks
+            .iter()
+            .filter_map(|link| {
+                let first = link.start.0.checked_sub(top).filter(|row| *row < rows)?;
+                let last = link.end.0.checked_sub(top).filter(|row| *row < rows)?;
+                let shown = screen.contents_between(
+                    to_u16(first)?,
+                    to_u16(link.start.1)?,
+                    to_u16(last)?,
+                    to_u16(link.end.1)?,
+                );
+                (shown.trim() == link.text.trim()).then_some((first, last, link))
+            })
+            .collect()
+    }
+}
+
+/// Whether `url` is a web or mail link that is safe to hand to the platform
+/// opener.
+pub fn is_openable_url(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return false;
+    };
+    OPENABLE_SCHEMES
+        .iter()
+        .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+        && !rest.is_empty()
+        && !url.chars().any(|ch| ch.is_whitespace() || ch.is_control())
+}
+
+/// Hands `url` to the platform opener without waiting for it. Only
+/// `is_openable_url` links are accepted.
+pub fn open_url(url: &str) -> Result<()> {
+    if !is_openable_url(url) {
+        anyhow::bail!("refusing to open {url}: only http, https and mailto links open");
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Not `cmd /C start`: cmd would parse `&`, `|` and `^` in the URL.
+        Command::new("explorer.exe")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to open {url}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::is_openable_url;
+    use crate::settings::ShellSettings;
+    use crate::terminal::TerminalSession;
+
+    #[cfg(unix)]
+    #[test]
+    fn hyperlinks_cover_only_the_cells_they_were_printed_on() {
+        let config = ShellSettings {
+            program: Some(String::from("/bin/sh")),
+            args: Some(vec![String::from("-c"), String::from("true")]),
+            ..ShellSettings::default()
+        };
+        let mut session = TerminalSession::start(Path::new("."), 3, 20, &config)
+            .expect("/bin/sh should start in a pty");
+        session.process(
+            b"see \x1b]8;;https://example.com\x1b\\src/main.rs\x1b]8;;\x1b\\\r\nsrc/main.rs:12\r\n",
+        );
+
+        let visible = session.visible_hyperlinks();
+        assert_eq!(visible.len(), 1);
+        let (first, last, link) = visible[0];
+        assert_eq!((first, last), (0, 0));
+        assert_eq!((link.start, link.end), ((0, 4), (0, 15)));
+        assert_eq!(link.url, "https://example.com");
+
+        // Once the screen scrolls the link moves with its row, and once its
+        // cells are overwritten it is gone.
+        session.process(b"one\r\ntwo\r\n");
+        assert!(session.visible_hyperlinks().is_empty());
+        session.set_scrollback(2);
+        assert_eq!(session.visible_hyperlinks()[0].0, 0);
+        session.set_scrollback(0);
+        session.process(b"\x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\");
+        assert_eq!(session.visible_hyperlinks()[0].0, 2);
+        session.process(b"\rgone");
+        assert!(session.visible_hyperlinks().is_empty());
+    }
+
+    #[test]
+    fn only_web_and_mail_links_are_openable() {
+        assert!(is_openable_url("https://example.com/a?b=1&c=2"));
+        assert!(is_openable_url("HTTP://example.com"));
+        assert!(is_openable_url("mailto:dev@example.com"));
+        assert!(!is_openable_url("file:///etc/passwd"));
+        assert!(!is_openable_url("smb://host/share"));
+        assert!(!is_openable_url("vscode://open?file=x"));
+        assert!(!is_openable_url("https://example.com/a b"));
+        assert!(!is_openable_url("https:"));
+        assert!(!is_openable_url("example.com"));
+    }
+}
@@ -0,0 +1,299 @@
+//! Pulls the sequences `vt100` ignores (OSC 8 hyperlinks, OSC 52 clipboard
+//! writes, kitty keyboard requests) out of the PTY byte stream before it
+//! reaches the parser.
+
+use super::keys::{KittyRequest, parse_kitty_request};
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+/// Longest OSC payload kept while waiting for its terminator; anything longer
+/// is passed through untouched.
+const MAX_PENDING: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Segment {
+    Bytes(Vec<u8>),
+    Osc(OscEvent),
+    Keyboard(KittyRequest),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum OscEvent {
+    Clipboard(String),
+    HyperlinkStart(String),
+    HyperlinkEnd,
+}
+
+/// Splits PTY output into plain bytes, OSC events and keyboard requests. Sequences split across
+/// reads are held back until their terminator arrives.
+#[derive(Default)]
+pub(super) struct OscScanner {
+    pending: Vec<u8>,
+}
+
+impl OscScanner {
+    pub(super) fn scan(&mut self, chunk: &[u8]) -> Vec<Segment> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(chunk);
+
+        let mut segments = Vec::new();
+        let mut plain = Vec::new();
+        let mut idx = 0;
+
+        while idx < input.len() {
+            if input[idx] != ESC {
+                plain.push(input[idx]);
+                idx += 1;
+                continue;
+            }
+
+            match input.get(idx + 1) {
+                None => {
+                    self.pending = input[idx..].to_vec();
+                    break;
+                }
+                Some(b']') => {}
+                Some(b'[') => match find_csi_end(&input, idx + 2) {
+                    CsiEnd::Incomplete => {
+                        self.pending = input[idx..].to_vec();
+                        break;
+                    }
+                    CsiEnd::Invalid => {
+                        plain.push(ESC);
+                        idx += 1;
+                        continue;
+                    }
+                    CsiEnd::Final(end) => {
+                        match parse_kitty_request(&input[idx + 2..end], input[end]) {
+                            Some(request) => {
+                                if !plain.is_empty() {
+                                    segments.push(Segment::Bytes(std::mem::take(&mut plain)));
+                                }
+                                segments.push(Segment::Keyboard(request));
+                            }
+                            None => plain.extend_from_slice(&input[idx..=end]),
+                        }
+                        idx = end + 1;
+                        continue;
+                    }
+                },
+                Some(_) => {
+                    plain.push(ESC);
+                    idx += 1;
+                    continue;
+                }
+            }
+
+            let body_start = idx + 2;
+            let Some((body_end, next)) = find_terminator(&input, body_start) else {
+                if input.len() - idx <= MAX_PENDING {
+                    self.pending = input[idx..].to_vec();
+                } else {
+                    plain.extend_from_slice(&input[idx..]);
+                }
+                break;
+            };
+
+            match parse_osc(&input[body_start..body_end]) {
+                Some(event) => {
+                    if !plain.is_empty() {
+                        segments.push(Segment::Bytes(std::mem::take(&mut plain)));
+                    }
+                    segments.push(Segment::Osc(event));
+                }
+                None => plain.extend_from_slice(&input[idx..next]),
+            }
+            idx = next;
+        }
+
+        if !plain.is_empty() {
+            segments.push(Segment::Bytes(plain));
+        }
+        segments
+    }
+}
+
+enum CsiEnd {
+    Final(usize),
+    Incomplete,
+    Invalid,
+}
+
+/// Finds the final byte of a CSI sequence whose parameters start at `start`.
+fn find_csi_end(input: &[u8], start: usize) -> CsiEnd {
+    for (idx, &byte) in input.iter().enumerate().skip(start) {
+        match byte {
+            0x20..=0x3f => {}
+            0x40..=0x7e => return CsiEnd::Final(idx),
+            _ => return CsiEnd::Invalid,
+        }
+    }
+    CsiEnd::Incomplete
+}
+
+/// Returns the end of the OSC body and the index just past its terminator
+/// (BEL or ESC `\`).
+fn find_terminator(input: &[u8], start: usize) -> Option<(usize, usize)> {
+    let mut idx = start;
+    while idx < input.len() {
+        match input[idx] {
+            BEL => return Some((idx, idx + 1)),
+            ESC if input.get(idx + 1) == Some(&b'\\') => return Some((idx, idx + 2)),
+            _ => idx += 1,
+        }
+    }
+    None
+}
+
+fn parse_osc(body: &[u8]) -> Option<OscEvent> {
+    let body = std::str::from_utf8(body).ok()?;
+    let (code, rest) = body.split_once(';')?;
+    match code {
+        "8" => {
+            let (_params, uri) = rest.split_once(';')?;
+            if uri.is_empty() {
+                Some(OscEvent::HyperlinkEnd)
+            } else {
+                Some(OscEvent::HyperlinkStart(uri.to_owned()))
+            }
+        }
+        "52" => {
+            let (_selection, data) = rest.split_once(';')?;
+            // `?` asks the terminal to report the clipboard, which is not
+            // something shell programs get to read.
+            if data == "?" {
+                return None;
+            }
+            let bytes = decode_base64(data)?;
+            String::from_utf8(bytes).ok().map(OscEvent::Clipboard)
+        }
+        _ => None,
+    }
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in data.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(out)
+}
+
+/// Visible text in a run of terminal output: escape sequences and control
+/// characters are dropped.
+pub(super) fn printable_text(bytes: &[u8]) -> String {
+    let mut kept = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            ESC => {
+                idx += 1;
+                if bytes.get(idx) == Some(&b'[') {
+                    idx += 1;
+                    while idx < bytes.len() && !(0x40..=0x7e).contains(&bytes[idx]) {
+                        idx += 1;
+                    }
+                }
+                idx += 1;
+            }
+            byte if byte < 0x20 || byte == 0x7f => idx += 1,
+            byte => {
+                kept.push(byte);
+                idx += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&kept).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OscEvent, OscScanner, Segment, decode_base64, printable_text};
+    use crate::terminal::keys::KittyRequest;
+
+    #[test]
+    fn extracts_hyperlinks_and_clipboard_writes() {
+        let mut scanner = OscScanner::default();
+        let segments = scanner.scan(
+            b"see \x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x07 \x1b]52;c;aGVsbG8=\x07done",
+        );
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Bytes(b"see ".to_vec()),
+                Segment::Osc(OscEvent::HyperlinkStart(String::from(
+                    "https://example.com"
+                ))),
+                Segment::Bytes(b"docs".to_vec()),
+                Segment::Osc(OscEvent::HyperlinkEnd),
+                Segment::Bytes(b" ".to_vec()),
+                Segment::Osc(OscEvent::Clipboard(String::from("hello"))),
+                Segment::Bytes(b"done".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn holds_split_sequences_and_passes_other_escapes_through() {
+        let mut scanner = OscScanner::default();
+        assert_eq!(
+            scanner.scan(b"\x1b[31mred\x1b]8;;file:///tmp"),
+            vec![Segment::Bytes(b"\x1b[31mred".to_vec())]
+        );
+        assert_eq!(
+            scanner.scan(b"/a\x07\x1b]0;title\x07"),
+            vec![
+                Segment::Osc(OscEvent::HyperlinkStart(String::from("file:///tmp/a"))),
+                Segment::Bytes(b"\x1b]0;title\x07".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_kitty_keyboard_requests_across_reads() {
+        let mut scanner = OscScanner::default();
+        assert_eq!(
+            scanner.scan(b"\x1b[1;31mhi\x1b[>"),
+            vec![Segment::Bytes(b"\x1b[1;31mhi".to_vec())]
+        );
+        assert_eq!(
+            scanner.scan(b"1u\x1b[?u\x1b[u"),
+            vec![
+                Segment::Keyboard(KittyRequest::Push(1)),
+                Segment::Keyboard(KittyRequest::Query),
+                Segment::Bytes(b"\x1b[u".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_base64_and_strips_escapes_from_link_text() {
+        assert_eq!(decode_base64("Zm9vYmFy"), Some(b"foobar".to_vec()));
+        assert_eq!(decode_base64("Zm8="), Some(b"fo".to_vec()));
+        assert_eq!(decode_base64("not base64!"), None);
+        assert_eq!(printable_text(b"\x1b[1mbold\x1b[0m\r\n"), "bold");
+    }
+}
@@ -0,0 +1,134 @@
+/// The visible cells of `row` as `(column, width, text)`: a wide character
+/// appears once at its first column, blanks are spaces, and trailing blanks
+/// are left off.
+fn row_cells(screen: &vt100::Screen, row: usize) -> Vec<(usize, usize, String)> {
+    let Ok(row) = u16::try_from(row) else {
+        return Vec::new();
+    };
+    let (_, cols) = screen.size();
+    let mut cells = Vec::new();
+    for col in 0..cols {
+        let Some(cell) = screen.cell(row, col) else {
+            break;
+        };
+        if cell.is_wide_continuation() {
+            continue;
+        }
+        let text = if cell.has_contents() {
+            cell.contents()
+        } else {
+            String::from(" ")
+        };
+        cells.push((usize::from(col), if cell.is_wide() { 2 } else { 1 }, text));
+    }
+    while cells
+        .last()
+        .is_some_and(|(_, _, text)| text.trim().is_empty())
+    {
+        cells.pop();
+    }
+    cells
+}
+
+/// A row's text with the screen column of every char, so matches found in
+/// the text can be placed on the screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowText {
+    pub text: String,
+    /// The column of each char, then the column just past the last cell.
+    columns: Vec<usize>,
+}
+
+impl RowText {
+    /// The screen column of char `idx` in `text`; the char count maps to the
+    /// column just past the row's last cell.
+    pub fn column(&self, idx: usize) -> usize {
+        self.columns
+            .get(idx)
+            .or(self.columns.last())
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// The text of visible `row` as `selection_text` copies it, with the column
+/// each char sits in.
+pub fn row_text(screen: &vt100::Screen, row: usize) -> RowText {
+    let mut text = String::new();
+    let mut columns = Vec::new();
+    let mut end = 0;
+    for (col, width, contents) in row_cells(screen, row) {
+        for ch in contents.chars() {
+            text.push(ch);
+            columns.push(col);
+        }
+        end = col + width;
+    }
+    columns.push(end);
+    RowText { text, columns }
+}
+
+/// The text between two visible `(row, col)` screen positions, both
+/// inclusive, with rows joined by newlines. A wide character is taken when
+/// either of its columns is selected.
+pub fn selection_text(
+    screen: &vt100::Screen,
+    (start_row, start_col): (usize, usize),
+    (end_row, end_col): (usize, usize),
+) -> String {
+    let mut out = String::new();
+    for row in start_row..=end_row {
+        let from = if row == start_row { start_col } else { 0 };
+        let to = if row == end_row { end_col } else { usize::MAX };
+        for (col, width, text) in row_cells(screen, row) {
+            if col <= to && col + width > from {
+                out.push_str(&text);
+            }
+        }
+        if row < end_row {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The first and last column of the whitespace-delimited word covering
+/// `col` on visible `row`, if that cell is not blank.
+pub fn word_bounds(screen: &vt100::Screen, row: usize, col: usize) -> Option<(usize, usize)> {
+    let cells = row_cells(screen, row);
+    let is_blank = |idx: usize| cells[idx].2.trim().is_empty();
+    let hit = cells
+        .iter()
+        .position(|(start, width, _)| (*start..start + width).contains(&col))
+        .filter(|idx| !is_blank(*idx))?;
+
+    let first = (0..hit)
+        .rev()
+        .find(|idx| is_blank(*idx))
+        .map_or(0, |idx| idx + 1);
+    let last = (hit..cells.len())
+        .find(|idx| is_blank(*idx))
+        .map_or(cells.len() - 1, |idx| idx - 1);
+    Some((cells[first].0, cells[last].0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{selection_text, word_bounds};
+
+    #[test]
+    fn selection_and_words_follow_screen_cells_across_wide_chars() {
+        let mut parser = vt100::Parser::new(2, 20, 0);
+        parser.process("日本 src/main.rs\r\nab".as_bytes());
+        let screen = parser.screen();
+
+        // "日本" takes columns 0..4, so the path starts at column 5.
+        assert_eq!(word_bounds(screen, 0, 1), Some((0, 2)));
+        assert_eq!(word_bounds(screen, 0, 7), Some((5, 15)));
+        assert_eq!(word_bounds(screen, 0, 4), None);
+        assert_eq!(word_bounds(screen, 0, 18), None);
+
+        assert_eq!(selection_text(screen, (0, 3), (0, 7)), "本 src");
+        assert_eq!(selection_text(screen, (0, 5), (1, 0)), "src/main.rs\na");
+    }
+}
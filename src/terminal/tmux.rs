@@ -0,0 +1,173 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+use super::shell_command;
+use crate::settings::ShellSettings;
+
+/// Persistent tabs live on their own tmux server so they never mix with the
+/// user's sessions.
+const TMUX_SOCKET: &str = "dif";
+
+/// Prefix shared by every persistent session of the repo at `repo_root`:
+/// `dif-<dir>-<hash>-`, so two checkouts with the same name stay apart.
+pub fn persistent_session_prefix(repo_root: &Path) -> String {
+    let dir = repo_root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // FNV-1a, so the name is stable across builds.
+    let hash = repo_root
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("dif-{}-{:08x}-", tmux_safe(&dir), hash as u32)
+}
+
+/// tmux rejects `.` and `:` in session names; this keeps names readable.
+pub fn tmux_safe(name: &str) -> String {
+    name.chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Shell settings that attach to the tmux session `session`, creating it
+/// with `config`'s shell in `cwd` when it does not exist yet. The initial
+/// command is only typed into a brand-new session.
+pub fn persistent_shell(
+    config: &ShellSettings,
+    session: &str,
+    cwd: &Path,
+    exists: bool,
+) -> ShellSettings {
+    let shell = shell_command(config);
+    let mut args = ["-L", TMUX_SOCKET, "new-session", "-A", "-s", session, "-c"]
+        .map(String::from)
+        .to_vec();
+    args.push(cwd.to_string_lossy().into_owned());
+    for (key, value) in &config.env {
+        args.push(String::from("-e"));
+        args.push(format!("{key}={value}"));
+    }
+    args.push(shell.program);
+    args.extend(shell.args);
+    // dif draws its own tab bar.
+    args.extend([";", "set-option", "-t", session, "status", "off"].map(String::from));
+
+    ShellSettings {
+        program: Some(String::from("tmux")),
+        args: Some(args),
+        env: config.env.clone(),
+        initial_command: config.initial_command.clone().filter(|_| !exists),
+        persist: None,
+    }
+}
+
+/// Names of the live persistent sessions that start with `prefix`. An absent
+/// tmux or server simply yields none.
+pub fn list_persistent_sessions(prefix: &str) -> Vec<String> {
+    let Ok(output) = tmux_command()
+        .args(["list-sessions", "-F", "#{session_name}"])
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|name| name.starts_with(prefix))
+        .map(str::to_owned)
+        .collect()
+}
+
+pub fn kill_persistent_session(session: &str) -> Result<()> {
+    run_tmux(&["kill-session", "-t", session])
+}
+
+pub fn rename_persistent_session(session: &str, new_name: &str) -> Result<()> {
+    run_tmux(&["rename-session", "-t", session, new_name])
+}
+
+fn tmux_command() -> Command {
+    let mut command = Command::new("tmux");
+    command.args(["-L", TMUX_SOCKET]).stdin(Stdio::null());
+    command
+}
+
+fn run_tmux(args: &[&str]) -> Result<()> {
+    let output = tmux_command()
+        .args(args)
+        .output()
+        .context("failed to run tmux")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "tmux {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{persistent_session_prefix, persistent_shell, tmux_safe};
+    use crate::settings::ShellSettings;
+
+    #[test]
+    fn persistent_shell_wraps_the_shell_in_a_tmux_session() {
+        let mut config = ShellSettings {
+            program: Some(String::from("/bin/zsh")),
+            args: Some(vec![String::from("-l")]),
+            initial_command: Some(String::from("agent")),
+            ..ShellSettings::default()
+        };
+        config
+            .env
+            .insert(String::from("EDITOR"), String::from("vim"));
+
+        let fresh = persistent_shell(&config, "dif-repo-1-shell", Path::new("/work/repo"), false);
+        let args = fresh.args.expect("tmux args");
+        assert_eq!(fresh.program.as_deref(), Some("tmux"));
+        assert_eq!(
+            args[..9],
+            [
+                "-L",
+                "dif",
+                "new-session",
+                "-A",
+                "-s",
+                "dif-repo-1-shell",
+                "-c",
+                "/work/repo",
+                "-e"
+            ]
+        );
+        assert_eq!(args[9..12], ["EDITOR=vim", "/bin/zsh", "-l"]);
+        assert_eq!(fresh.initial_command.as_deref(), Some("agent"));
+
+        let reattach = persistent_shell(&config, "dif-repo-1-shell", Path::new("/work/repo"), true);
+        assert_eq!(reattach.initial_command, None);
+    }
+
+    #[test]
+    fn persistent_session_names_are_tmux_safe_and_repo_specific() {
+        let a = persistent_session_prefix(Path::new("/work/my.repo"));
+        let b = persistent_session_prefix(Path::new("/other/my.repo"));
+        assert!(a.starts_with("dif-my_repo-"));
+        assert_ne!(a, b);
+        assert_eq!(tmux_safe("agent: run.1"), "agent__run_1");
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs::{self, File};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::TerminalSession;
+use super::selection::{RowText, row_text};
+
+impl TerminalSession {
+    /// Writes all further raw output, escape sequences included, to a new
+    /// private file at `path`.
+    pub fn start_log(&mut self, path: &Path) -> Result<()> {
+        let file = create_private_file(path)
+            .with_context(|| format!("failed to open session log `{}`", path.display()))?;
+        self.log = Some(file);
+        Ok(())
+    }
+
+    /// The whole scrollback plus the visible screen, one line per row. With
+    /// `ansi` each row keeps its colors and attributes as escape codes.
+    pub fn transcript(&mut self, ansi: bool) -> String {
+        let mut lines = self.history_and_screen(|screen| {
+            let cols = screen.size().1;
+            if ansi {
+                screen
+                    .rows_formatted(0, cols)
+                    .map(|row| format!("\x1b[m{}\x1b[m", String::from_utf8_lossy(&row)))
+                    .collect()
+            } else {
+                screen
+                    .rows(0, cols)
+                    .map(|row| row.trim_end().to_owned())
+                    .collect()
+            }
+        });
+        let blank = if ansi { "\x1b[m\x1b[m" } else { "" };
+        while lines.last().is_some_and(|line| line == blank) {
+            lines.pop();
+        }
+        let mut out = lines.join("\n");
+        out.push('\n');
+        out
+    }
+
+    /// Rows of the scrollback followed by the screen, each with the screen
+    /// column of every char; the screen's top row is at index `history_len()`.
+    pub fn all_rows(&mut self) -> Vec<RowText> {
+        self.history_and_screen(|screen| {
+            (0..usize::from(screen.size().0))
+                .map(|row| row_text(screen, row))
+                .collect()
+        })
+    }
+
+    /// How many rows of scrollback sit above the screen.
+    pub fn history_len(&mut self) -> usize {
+        let view_offset = self.parser.screen().scrollback();
+        self.parser.set_scrollback(usize::MAX);
+        let history = self.parser.screen().scrollback();
+        self.parser.set_scrollback(view_offset);
+        history
+    }
+
+    /// Every row of the scrollback and screen, top first, as `page` renders
+    /// the rows of each screenful.
+    fn history_and_screen<T>(&mut self, page: impl Fn(&vt100::Screen) -> Vec<T>) -> Vec<T> {
+        let view_offset = self.parser.screen().scrollback();
+        let history = self.history_len();
+        let (rows, _) = self.parser.screen().size();
+        let total = history + rows as usize;
+
+        let mut lines = Vec::with_capacity(total);
+        while lines.len() < total {
+            let offset = history.saturating_sub(lines.len());
+            self.parser.set_scrollback(offset);
+            let top = history - offset;
+            let skip = lines.len() - top;
+            lines.extend(page(self.parser.screen()).into_iter().skip(skip));
+        }
+        self.parser.set_scrollback(view_offset);
+        lines
+    }
+}
+
+/// Creates `dir` so only the current user can enter it. An existing `dir`
+/// must be a real directory, not a symlink, owned by that user; any group or
+/// other access it has is removed.
+pub fn ensure_private_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("failed to create directory `{}`", dir.display()))?;
+        let meta = fs::symlink_metadata(dir)
+            .with_context(|| format!("failed to inspect directory `{}`", dir.display()))?;
+        if meta.file_type().is_symlink() || !meta.is_dir() {
+            anyhow::bail!("refusing to use `{}`: not a directory", dir.display());
+        }
+        // SAFETY: `geteuid` has no preconditions and cannot fail.
+        if meta.uid() != unsafe { libc::geteuid() } {
+            anyhow::bail!("refusing to use `{}`: owned by another user", dir.display());
+        }
+        if meta.mode() & 0o077 != 0 {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+                .with_context(|| format!("failed to restrict `{}`", dir.display()))?;
+        }
+    }
+    #[cfg(not(unix))]
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create directory `{}`", dir.display()))?;
+
+    Ok(())
+}
+
+/// Creates `path`, which must not exist yet, readable only by the current
+/// user. Refusing an existing path means a planted symlink is never followed.
+pub fn create_private_file(path: &Path) -> std::io::Result<File> {
+    let mut options = File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::{create_private_file, ensure_private_dir};
+    use crate::settings::ShellSettings;
+    use crate::terminal::TerminalSession;
+
+    #[cfg(unix)]
+    #[test]
+    fn transcript_includes_scrollback_above_the_screen() {
+        let config = ShellSettings {
+            program: Some(String::from("/bin/sh")),
+            args: Some(vec![String::from("-c"), String::from("true")]),
+            ..ShellSettings::default()
+        };
+        let mut session = TerminalSession::start(Path::new("."), 3, 20, &config)
+            .expect("/bin/sh should start in a pty");
+        session
+            .parser
+            .process(b"one\r\ntwo\r\nthree\r\nfour\r\n\x1b[31mfive\x1b[0m");
+        session.set_scrollback(1);
+
+        assert_eq!(session.transcript(false), "one\ntwo\nthree\nfour\nfive\n");
+        assert!(session.transcript(true).contains("\x1b[31mfive"));
+        assert_eq!(session.scrollback(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn transcript_files_and_dirs_are_private() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let temp = tempfile::tempdir().expect("temp dir should be created");
+        let dir = temp.path().join("dif");
+        fs::create_dir(&dir).expect("dir should be created");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777))
+            .expect("permissions should be set");
+
+        ensure_private_dir(&dir).expect("own dir should be accepted");
+        let mode = |path: &Path| {
+            fs::metadata(path)
+                .expect("metadata should be readable")
+                .permissions()
+                .mode()
+                & 0o777
+        };
+        assert_eq!(mode(&dir), 0o700);
+
+        let file = dir.join("tab-1.log");
+        create_private_file(&file).expect("new file should be created");
+        assert_eq!(mode(&file), 0o600);
+        assert!(create_private_file(&file).is_err());
+
+        let link = temp.path().join("link");
+        symlink(&dir, &link).expect("symlink should be created");
+        assert!(ensure_private_dir(&link).is_err());
+    }
+}
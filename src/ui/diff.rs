@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use ratatui::Frame;
 use ratatui::layout::Rect;
 use ratatui::style::Style;
@@ -5,11 +7,18 @@ use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
 use crate::app::{App, PaneFocus, ResolvedDiffLayout};
-use crate::diff::{CellKind, DiffCell, DiffRow};
-use crate::highlight::{Highlighter, LineHighlighter};
+use crate::diff::{
+    CellKind, DiffCell, DiffRow, WrapWidths, char_width, display_width, expand_tabs,
+    line_number_width, row_display_height, wrapped_height,
+};
+use crate::highlight::{HighlightedFile, Highlighter, SourceFile};
 
 use super::palette::{Palette, rgb};
 
+/// Stands in for the line number on the screen lines a wrapped line spills
+/// onto.
+const CONTINUATION_MARKER: char = '↪';
+
 #[derive(Clone)]
 struct UnifiedLine {
     old_no: Option<usize>,
@@ -19,6 +28,40 @@ struct UnifiedLine {
     marker: char,
 }
 
+/// Colors one side of the diff from its cached highlighted source.
+struct SideHighlighter {
+    file: Option<Rc<HighlightedFile>>,
+}
+
+impl SideHighlighter {
+    fn new(highlighter: &Highlighter, app: &App, source: Option<&SourceFile>) -> Self {
+        Self {
+            file: source.map(|source| {
+                highlighter.highlight_file(
+                    app.active_path(),
+                    source,
+                    &app.settings,
+                    app.light_theme(),
+                )
+            }),
+        }
+    }
+
+    /// Plain text when the source has something else at `line_no`, which
+    /// only happens if the file changed after the diff was read.
+    fn highlight(
+        &self,
+        line_no: Option<usize>,
+        text: &str,
+        background_rgb: (u8, u8, u8),
+    ) -> Vec<Span<'static>> {
+        line_no
+            .zip(self.file.as_ref())
+            .and_then(|(line_no, file)| file.spans(line_no, text, background_rgb))
+            .unwrap_or_else(|| vec![Span::raw(text.to_owned())])
+    }
+}
+
 pub(crate) fn render_diff_header(
     frame: &mut Frame,
     app: &App,
@@ -28,9 +71,21 @@ pub(crate) fn render_diff_header(
     palette: &Palette,
 ) {
     let (added, removed) = app.active_diff_line_counts();
-    let text = match app.active_path() {
-        Some(path) => format!("{path} [{}]  +{added} -{removed}", app.active_label()),
-        None => String::from("No file selected"),
+    let text = match (app.active_path(), app.selected_tree_dir()) {
+        (Some(path), _) => format!("{path} [{}]  +{added} -{removed}", app.active_label()),
+        (None, Some(header)) => format!(
+            "{} [{}]  {} files  {} staged  {} unstaged",
+            header.display_name(),
+            if header.is_directory() {
+                "Directory"
+            } else {
+                "Group"
+            },
+            header.file_count,
+            header.staged_count,
+            header.unstaged_count
+        ),
+        (None, None) => String::from("No file selected"),
     };
     let header = Paragraph::new(text).style(Style::default().fg(rgb(palette.dim)));
     frame.render_widget(header, area);
@@ -40,17 +95,17 @@ pub(crate) fn render_split_diff_panes(
     frame: &mut Frame,
     app: &App,
     panes: (Rect, Rect),
+    wrap: Option<WrapWidths>,
     highlighter: &Highlighter,
     palette: &Palette,
 ) {
-    let source_path = app.active_path();
     let old_width = line_number_width(&app.diff_rows, true);
     let new_width = line_number_width(&app.diff_rows, false);
 
     let mut old_lines = Vec::new();
     let mut new_lines = Vec::new();
-    let mut old_highlighter = highlighter.begin(source_path, app.settings.theme);
-    let mut new_highlighter = highlighter.begin(source_path, app.settings.theme);
+    let old_highlighter = SideHighlighter::new(highlighter, app, app.diff_old_source());
+    let new_highlighter = SideHighlighter::new(highlighter, app, app.diff_new_source());
 
     if app.diff_rows.is_empty() {
         old_lines.push(Line::styled(
@@ -62,23 +117,45 @@ pub(crate) fn render_split_diff_panes(
             Style::default().fg(rgb(palette.dim)),
         ));
     } else {
+        // Only the rows on screen are built; the rest would be thrown away.
+        let height = usize::from(panes.0.height.saturating_sub(2));
+        let mut skip = app.diff_scroll;
         for row in &app.diff_rows {
-            old_lines.push(build_split_line(
+            if old_lines.len() >= height {
+                break;
+            }
+            let row_height = row_display_height(row, false, wrap);
+            if skip >= row_height {
+                skip -= row_height;
+                continue;
+            }
+
+            let old = build_split_lines(
                 row.old.as_ref(),
                 old_width,
-                &mut old_highlighter,
+                wrap.map(|widths| widths.old),
+                app.diff_hscroll,
+                row_height,
+                &old_highlighter,
                 palette,
-            ));
-            new_lines.push(build_split_line(
+            );
+            let new = build_split_lines(
                 row.new.as_ref(),
                 new_width,
-                &mut new_highlighter,
+                wrap.map(|widths| widths.new),
+                app.diff_hscroll,
+                row_height,
+                &new_highlighter,
                 palette,
-            ));
+            );
+            old_lines.extend(old.into_iter().skip(skip));
+            new_lines.extend(new.into_iter().skip(skip));
+            skip = 0;
         }
+        old_lines.truncate(height);
+        new_lines.truncate(height);
     }
 
-    let scroll = to_u16(app.diff_scroll);
     let pane_style = Style::default()
         .fg(rgb(palette.text))
         .bg(rgb(palette.pane_bg));
@@ -99,8 +176,7 @@ pub(crate) fn render_split_diff_panes(
                 })
                 .border_style(diff_border),
         )
-        .style(pane_style)
-        .scroll((scroll, 0));
+        .style(pane_style);
     let new = Paragraph::new(Text::from(new_lines))
         .block(
             Block::default()
@@ -112,8 +188,7 @@ pub(crate) fn render_split_diff_panes(
                 })
                 .border_style(diff_border),
         )
-        .style(pane_style)
-        .scroll((scroll, 0));
+        .style(pane_style);
 
     frame.render_widget(old, panes.0);
     frame.render_widget(new, panes.1);
@@ -123,49 +198,56 @@ pub(crate) fn render_unified_diff_pane(
     frame: &mut Frame,
     app: &App,
     area: Rect,
+    wrap: Option<WrapWidths>,
     highlighter: &Highlighter,
     palette: &Palette,
 ) {
-    let source_path = app.active_path();
     let unified = collect_unified_lines(&app.diff_rows);
-
-    let old_width = unified
-        .iter()
-        .filter_map(|line| line.old_no)
-        .max()
-        .unwrap_or(1)
-        .to_string()
-        .len()
-        .max(1);
-    let new_width = unified
-        .iter()
-        .filter_map(|line| line.new_no)
-        .max()
-        .unwrap_or(1)
-        .to_string()
-        .len()
-        .max(1);
+    let old_width = line_number_width(&app.diff_rows, true);
+    let new_width = line_number_width(&app.diff_rows, false);
 
     let mut lines = Vec::new();
-    let mut line_highlighter = highlighter.begin(source_path, app.settings.theme);
+    let old_highlighter = SideHighlighter::new(highlighter, app, app.diff_old_source());
+    let new_highlighter = SideHighlighter::new(highlighter, app, app.diff_new_source());
     if unified.is_empty() {
         lines.push(Line::styled(
             "No diff",
             Style::default().fg(rgb(palette.dim)),
         ));
     } else {
+        let height = usize::from(area.height.saturating_sub(2));
+        let mut skip = app.diff_scroll;
         for line in &unified {
-            lines.push(build_unified_line(
+            if lines.len() >= height {
+                break;
+            }
+            let removed = line.kind == CellKind::Removed;
+            let wrap_width = wrap.map(|widths| if removed { widths.old } else { widths.new });
+            let line_height = wrap_width.map_or(1, |width| wrapped_height(&line.text, width));
+            if skip >= line_height {
+                skip -= line_height;
+                continue;
+            }
+
+            let built = build_unified_lines(
                 line,
                 old_width,
                 new_width,
-                &mut line_highlighter,
+                wrap_width,
+                app.diff_hscroll,
+                if removed {
+                    &old_highlighter
+                } else {
+                    &new_highlighter
+                },
                 palette,
-            ));
+            );
+            lines.extend(built.into_iter().skip(skip));
+            skip = 0;
         }
+        lines.truncate(height);
     }
 
-    let scroll = to_u16(app.diff_scroll);
     let pane_style = Style::default()
         .fg(rgb(palette.text))
         .bg(rgb(palette.pane_bg));
@@ -185,8 +267,7 @@ pub(crate) fn render_unified_diff_pane(
                 })
                 .border_style(diff_border),
         )
-        .style(pane_style)
-        .scroll((scroll, 0));
+        .style(pane_style);
 
     frame.render_widget(paragraph, area);
 }
@@ -269,58 +350,78 @@ fn marker_for_kind(kind: CellKind) -> char {
     }
 }
 
-fn build_split_line(
+/// One diff cell as `height` screen lines: the text, wrapped at `wrap_width`
+/// or shifted left by `hscroll`, then blank lines so both panes of a row
+/// stay level.
+fn build_split_lines(
     cell: Option<&DiffCell>,
     line_number_width: usize,
-    line_highlighter: &mut LineHighlighter<'_>,
+    wrap_width: Option<usize>,
+    hscroll: usize,
+    height: usize,
+    side_highlighter: &SideHighlighter,
     palette: &Palette,
-) -> Line<'static> {
+) -> Vec<Line<'static>> {
     let bg_rgb = background_for_kind(cell.map(|item| item.kind), palette);
-
-    let Some(cell) = cell else {
-        return Line::from(Span::styled(
+    let blank = || {
+        Line::from(Span::styled(
             " ".repeat(line_number_width + 1),
             Style::default().bg(rgb(bg_rgb)),
-        ));
+        ))
     };
 
-    let number = match cell.line_no {
-        Some(value) => format!("{value:>width$} ", width = line_number_width),
-        None => " ".repeat(line_number_width + 1),
+    let Some(cell) = cell else {
+        return vec![blank(); height];
     };
 
-    let mut spans = vec![Span::styled(
-        number,
-        Style::default().fg(rgb(palette.line_no)),
-    )];
-
-    if cell.kind == CellKind::Meta {
-        spans.push(Span::styled(
+    let content = if cell.kind == CellKind::Meta {
+        vec![Span::styled(
             cell.text.clone(),
             Style::default().fg(rgb(palette.dim)),
-        ));
+        )]
     } else {
-        spans.extend(line_highlighter.highlight(&cell.text, bg_rgb));
-    }
+        side_highlighter.highlight(cell.line_no, &cell.text, bg_rgb)
+    };
 
-    Line::from(spans).style(Style::default().bg(rgb(bg_rgb)))
+    let mut lines: Vec<Line<'static>> = fit_spans(content, wrap_width, hscroll)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let number = match cell.line_no {
+                Some(value) if idx == 0 => Span::styled(
+                    format!("{value:>width$} ", width = line_number_width),
+                    Style::default().fg(rgb(palette.line_no)),
+                ),
+                _ if idx > 0 => Span::styled(
+                    format!("{CONTINUATION_MARKER:>width$} ", width = line_number_width),
+                    Style::default().fg(rgb(palette.dim)),
+                ),
+                _ => Span::raw(" ".repeat(line_number_width + 1)),
+            };
+            let mut spans = vec![number];
+            spans.extend(chunk);
+            Line::from(spans).style(Style::default().bg(rgb(bg_rgb)))
+        })
+        .collect();
+    lines.resize_with(height.max(lines.len()), blank);
+    lines
 }
 
-fn build_unified_line(
+/// One unified line as screen lines: wrapped at `wrap_width` with the
+/// continuation marker in the `+`/`-` column, or shifted left by `hscroll`.
+fn build_unified_lines(
     line: &UnifiedLine,
     old_width: usize,
     new_width: usize,
-    line_highlighter: &mut LineHighlighter<'_>,
+    wrap_width: Option<usize>,
+    hscroll: usize,
+    side_highlighter: &SideHighlighter,
     palette: &Palette,
-) -> Line<'static> {
+) -> Vec<Line<'static>> {
     let bg_rgb = background_for_kind(Some(line.kind), palette);
-    let old = match line.old_no {
-        Some(value) => format!("{value:>width$}", width = old_width),
-        None => " ".repeat(old_width),
-    };
-    let new = match line.new_no {
-        Some(value) => format!("{value:>width$}", width = new_width),
-        None => " ".repeat(new_width),
+    let number = |value: Option<usize>, width: usize| match value {
+        Some(value) => format!("{value:>width$}"),
+        None => " ".repeat(width),
     };
 
     let marker_color = match line.kind {
@@ -329,28 +430,119 @@ fn build_unified_line(
         CellKind::Meta | CellKind::Context => palette.marker_context,
     };
 
-    let mut spans = vec![
-        Span::styled(old, Style::default().fg(rgb(palette.line_no))),
-        Span::raw(" "),
-        Span::styled(new, Style::default().fg(rgb(palette.line_no))),
-        Span::raw(" "),
-        Span::styled(
-            line.marker.to_string(),
-            Style::default().fg(rgb(marker_color)),
-        ),
-        Span::raw(" "),
-    ];
-
-    if line.kind == CellKind::Meta {
-        spans.push(Span::styled(
+    let content = if line.kind == CellKind::Meta {
+        vec![Span::styled(
             line.text.clone(),
             Style::default().fg(rgb(palette.dim)),
-        ));
+        )]
     } else {
-        spans.extend(line_highlighter.highlight(&line.text, bg_rgb));
+        let line_no = if line.kind == CellKind::Removed {
+            line.old_no
+        } else {
+            line.new_no
+        };
+        side_highlighter.highlight(line_no, &line.text, bg_rgb)
+    };
+
+    fit_spans(content, wrap_width, hscroll)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let (old, new, marker) = if idx == 0 {
+                (
+                    number(line.old_no, old_width),
+                    number(line.new_no, new_width),
+                    Span::styled(
+                        line.marker.to_string(),
+                        Style::default().fg(rgb(marker_color)),
+                    ),
+                )
+            } else {
+                (
+                    " ".repeat(old_width),
+                    " ".repeat(new_width),
+                    Span::styled(
+                        CONTINUATION_MARKER.to_string(),
+                        Style::default().fg(rgb(palette.dim)),
+                    ),
+                )
+            };
+            let mut spans = vec![
+                Span::styled(old, Style::default().fg(rgb(palette.line_no))),
+                Span::raw(" "),
+                Span::styled(new, Style::default().fg(rgb(palette.line_no))),
+                Span::raw(" "),
+                marker,
+                Span::raw(" "),
+            ];
+            spans.extend(chunk);
+            Line::from(spans).style(Style::default().bg(rgb(bg_rgb)))
+        })
+        .collect()
+}
+
+/// Cuts `spans` into screen lines of `wrap_width` columns, or into one line
+/// missing its first `hscroll` columns when wrapping is off. Tabs are
+/// expanded first; a wide character cut by the left edge shows as spaces.
+fn fit_spans(
+    spans: Vec<Span<'static>>,
+    wrap_width: Option<usize>,
+    hscroll: usize,
+) -> Vec<Vec<Span<'static>>> {
+    let mut col = 0;
+    let spans = spans.into_iter().map(move |span| {
+        let text = expand_tabs(&span.content, col).into_owned();
+        col += display_width(&text);
+        (text, span.style)
+    });
+
+    let Some(width) = wrap_width.map(|width| width.max(1)) else {
+        let mut col = 0;
+        let line = spans
+            .filter_map(|(text, style)| {
+                let mut kept = String::new();
+                for ch in text.chars() {
+                    let ch_width = char_width(ch);
+                    if col >= hscroll {
+                        kept.push(ch);
+                    } else if col + ch_width > hscroll {
+                        kept.extend(std::iter::repeat_n(' ', col + ch_width - hscroll));
+                    }
+                    col += ch_width;
+                }
+                (!kept.is_empty()).then(|| Span::styled(kept, style))
+            })
+            .collect();
+        return vec![line];
+    };
+
+    let mut lines = vec![Vec::new()];
+    let mut used = 0;
+    for (text, style) in spans {
+        let mut chunk = String::new();
+        for ch in text.chars() {
+            let ch_width = char_width(ch);
+            if used > 0 && used + ch_width > width {
+                if !chunk.is_empty() {
+                    push_chunk(&mut lines, std::mem::take(&mut chunk), style);
+                }
+                lines.push(Vec::new());
+                used = 0;
+            }
+            chunk.push(ch);
+            used += ch_width;
+        }
+        if !chunk.is_empty() {
+            push_chunk(&mut lines, chunk, style);
+        }
     }
+    lines
+}
 
-    Line::from(spans).style(Style::default().bg(rgb(bg_rgb)))
+fn push_chunk(lines: &mut [Vec<Span<'static>>], chunk: String, style: Style) {
+    if let Some(line) = lines.last_mut() {
+        line.push(Span::styled(chunk, style));
+    }
 }
 
 fn background_for_kind(kind: Option<CellKind>, palette: &Palette) -> (u8, u8, u8) {
@@ -362,31 +554,22 @@ fn background_for_kind(kind: Option<CellKind>, palette: &Palette) -> (u8, u8, u8
     }
 }
 
-fn line_number_width(rows: &[DiffRow], old_side: bool) -> usize {
-    let max_line = rows
-        .iter()
-        .filter_map(|row| {
-            if old_side {
-                row.old.as_ref().and_then(|cell| cell.line_no)
-            } else {
-                row.new.as_ref().and_then(|cell| cell.line_no)
-            }
-        })
-        .max()
-        .unwrap_or(1);
-
-    max_line.to_string().len().max(1)
-}
-
-fn to_u16(value: usize) -> u16 {
-    u16::try_from(value).unwrap_or(u16::MAX)
-}
-
 #[cfg(test)]
 mod tests {
+    use ratatui::text::{Line, Span};
+
     use crate::diff::{CellKind, DiffCell, DiffRow};
+    use crate::settings::AppTheme;
+    use crate::ui::palette::palette_for;
+
+    use super::{SideHighlighter, build_split_lines, collect_unified_lines, fit_spans};
 
-    use super::collect_unified_lines;
+    fn line_text(line: &Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
 
     #[test]
     fn snapshot_collects_replacement_as_remove_then_add() {
@@ -451,4 +634,59 @@ mod tests {
 
         assert_eq!(snapshot, vec![String::from("Some(3)|Some(3)| |same")]);
     }
+
+    #[test]
+    fn wraps_across_spans_or_scrolls_sideways() {
+        let spans = vec![Span::raw("let value"), Span::raw(" = 42;")];
+        let text = |lines: Vec<Vec<Span<'static>>>| {
+            lines
+                .iter()
+                .map(|line| line.iter().map(|span| span.content.as_ref()).collect())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            text(fit_spans(spans.clone(), Some(6), 0)),
+            ["let va", "lue = ", "42;"]
+        );
+        assert_eq!(text(fit_spans(spans.clone(), None, 7)), ["ue = 42;"]);
+        assert_eq!(text(fit_spans(spans, None, 40)), [""]);
+        assert_eq!(text(fit_spans(Vec::new(), Some(6), 0)), [""]);
+    }
+
+    #[test]
+    fn fits_wide_characters_and_tabs_by_column() {
+        let text = |lines: Vec<Vec<Span<'static>>>| {
+            lines
+                .iter()
+                .map(|line| line.iter().map(|span| span.content.as_ref()).collect())
+                .collect::<Vec<String>>()
+        };
+
+        // Each ideograph covers two columns, so three do not fit in five.
+        let wide = vec![Span::raw("a日本語b")];
+        assert_eq!(text(fit_spans(wide.clone(), Some(5), 0)), ["a日本", "語b"]);
+        assert_eq!(text(fit_spans(wide.clone(), None, 2)), [" 本語b"]);
+        assert_eq!(text(fit_spans(wide, None, 5)), ["語b"]);
+
+        let tabbed = vec![Span::raw("a"), Span::raw("\tb")];
+        assert_eq!(text(fit_spans(tabbed.clone(), None, 0)), ["a   b"]);
+        assert_eq!(text(fit_spans(tabbed, Some(3), 0)), ["a  ", " b"]);
+    }
+
+    #[test]
+    fn wrapped_split_cell_numbers_only_its_first_line() {
+        let palette = palette_for(AppTheme::Ocean, false);
+        let cell = DiffCell {
+            line_no: Some(7),
+            text: String::from("abcdefghij"),
+            kind: CellKind::Added,
+        };
+        let plain = SideHighlighter { file: None };
+
+        let lines = build_split_lines(Some(&cell), 2, Some(4), 0, 4, &plain, &palette);
+        let snapshot: Vec<String> = lines.iter().map(line_text).collect();
+
+        assert_eq!(snapshot, [" 7 abcd", " ↪ efgh", " ↪ ij", "   "]);
+    }
 }
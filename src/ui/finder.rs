@@ -0,0 +1,254 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::app::{App, CommandEntry, FinderResult};
+use crate::layout;
+
+use super::palette::{Palette, rgb};
+
+pub(crate) fn render_finder_modal(frame: &mut Frame, app: &App, area: Rect, palette: &Palette) {
+    let popup = layout::finder_popup(area);
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .title(" Find File ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(rgb(palette.modal_border)))
+        .style(
+            Style::default()
+                .bg(rgb(palette.modal_bg))
+                .fg(rgb(palette.text)),
+        );
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+
+    let prompt = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(rgb(palette.border_focus))),
+        Span::styled(
+            format!("{}_", app.finder_query),
+            Style::default().fg(rgb(palette.text)),
+        ),
+    ]));
+    frame.render_widget(prompt, sections[0]);
+
+    let results = app.finder_results();
+    let count = Paragraph::new(format!(
+        "{}/{} changed files",
+        results.len(),
+        app.tree_file_total()
+    ))
+    .style(Style::default().fg(rgb(palette.dim)));
+    frame.render_widget(count, sections[1]);
+
+    let visible = sections[2].height as usize;
+    let selected = app.finder_selected.min(results.len().saturating_sub(1));
+    let start = (selected + 1).saturating_sub(visible);
+
+    let mut lines = Vec::new();
+    if results.is_empty() {
+        lines.push(Line::styled(
+            "(no matching files)",
+            Style::default().fg(rgb(palette.dim)),
+        ));
+    } else {
+        for (idx, result) in results.iter().enumerate().skip(start).take(visible) {
+            lines.push(result_line(result, idx == selected, palette));
+        }
+    }
+
+    let list = Paragraph::new(Text::from(lines)).style(Style::default().bg(rgb(palette.modal_bg)));
+    frame.render_widget(list, sections[2]);
+}
+
+fn result_line(result: &FinderResult, selected: bool, palette: &Palette) -> Line<'static> {
+    let entry = &result.entry;
+    let base = if entry.untracked {
+        Style::default().fg(rgb(palette.untracked))
+    } else {
+        Style::default().fg(rgb(palette.text))
+    };
+    let matched = Style::default()
+        .fg(rgb(palette.border_focus))
+        .add_modifier(Modifier::BOLD);
+
+    let mut spans = vec![
+        Span::raw(if selected { "> " } else { "  " }),
+        Span::styled(
+            if entry.staged { "M" } else { " " },
+            Style::default().fg(rgb(palette.marker_add)),
+        ),
+        Span::raw(" "),
+        Span::styled(
+            if entry.unstaged { "M" } else { " " },
+            Style::default().fg(rgb(palette.marker_remove)),
+        ),
+        Span::raw(" "),
+    ];
+
+    push_highlighted(&mut spans, &entry.path, &result.positions, base, matched);
+
+    let line = Line::from(spans);
+    if selected {
+        line.style(
+            Style::default()
+                .bg(rgb(palette.modal_selected_bg))
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        line
+    }
+}
+
+pub(crate) fn render_command_palette_modal(
+    frame: &mut Frame,
+    app: &App,
+    area: Rect,
+    palette: &Palette,
+) {
+    let popup = layout::finder_popup(area);
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .title(" Commands ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(rgb(palette.modal_border)))
+        .style(
+            Style::default()
+                .bg(rgb(palette.modal_bg))
+                .fg(rgb(palette.text)),
+        );
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ])
+        .split(inner);
+
+    let prompt = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(rgb(palette.border_focus))),
+        Span::styled(
+            format!("{}_", app.command_palette_query),
+            Style::default().fg(rgb(palette.text)),
+        ),
+    ]));
+    frame.render_widget(prompt, sections[0]);
+
+    let results = app.command_palette_results();
+    let enabled = results.iter().filter(|entry| entry.enabled).count();
+    let count = Paragraph::new(format!(
+        "{} commands, {} available here",
+        results.len(),
+        enabled
+    ))
+    .style(Style::default().fg(rgb(palette.dim)));
+    frame.render_widget(count, sections[1]);
+
+    let visible = sections[2].height as usize;
+    let width = sections[2].width as usize;
+    let selected = app
+        .command_palette_selected
+        .min(results.len().saturating_sub(1));
+    let start = (selected + 1).saturating_sub(visible);
+
+    let mut lines = Vec::new();
+    if results.is_empty() {
+        lines.push(Line::styled(
+            "(no matching commands)",
+            Style::default().fg(rgb(palette.dim)),
+        ));
+    } else {
+        for (idx, entry) in results.iter().enumerate().skip(start).take(visible) {
+            lines.push(command_line(entry, idx == selected, width, palette));
+        }
+    }
+
+    let list = Paragraph::new(Text::from(lines)).style(Style::default().bg(rgb(palette.modal_bg)));
+    frame.render_widget(list, sections[2]);
+}
+
+fn command_line(
+    entry: &CommandEntry,
+    selected: bool,
+    width: usize,
+    palette: &Palette,
+) -> Line<'static> {
+    let base = if entry.enabled {
+        Style::default().fg(rgb(palette.text))
+    } else {
+        Style::default().fg(rgb(palette.dim))
+    };
+    let matched = base
+        .fg(rgb(palette.border_focus))
+        .add_modifier(Modifier::BOLD);
+
+    let description = entry.action.description();
+    let mut spans = vec![Span::raw(if selected { "> " } else { "  " })];
+    push_highlighted(&mut spans, description, &entry.positions, base, matched);
+
+    // Right-align the binding; a disabled command says so instead of hiding it.
+    let keys = if entry.enabled {
+        entry.keys.clone()
+    } else {
+        format!("{} (n/a)", entry.keys)
+    };
+    let used = 2 + description.chars().count();
+    let pad = width.saturating_sub(used + keys.chars().count()).max(1);
+    spans.push(Span::raw(" ".repeat(pad)));
+    spans.push(Span::styled(keys, Style::default().fg(rgb(palette.dim))));
+
+    let line = Line::from(spans);
+    if selected {
+        line.style(
+            Style::default()
+                .bg(rgb(palette.modal_selected_bg))
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        line
+    }
+}
+
+/// Appends `text` as spans, styling the fuzzy-matched characters.
+fn push_highlighted(
+    spans: &mut Vec<Span<'static>>,
+    text: &str,
+    positions: &[usize],
+    base: Style,
+    matched: Style,
+) {
+    let mut positions = positions.iter().copied().peekable();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (idx, ch) in text.chars().enumerate() {
+        let is_match = positions.peek() == Some(&idx);
+        if is_match {
+            positions.next();
+        }
+        if is_match != run_matched && !run.is_empty() {
+            let style = if run_matched { matched } else { base };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = is_match;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_matched { matched } else { base }));
+    }
+}
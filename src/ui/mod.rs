@@ -1,26 +1,34 @@
 mod diff;
+mod finder;
 mod modal;
 mod palette;
 mod sidebar;
 
 use ratatui::Frame;
-use ratatui::style::Style;
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
 
-use crate::app::{App, GitPanelMode, ResolvedDiffLayout, StatusKind};
+use crate::app::{App, GitPanelMode, ResolvedDiffLayout, StatusKind, TabAttention};
 use crate::highlight::Highlighter;
-use crate::keymap;
+use crate::keymap::{self, Action};
 use crate::layout;
 
-use self::palette::{palette_for, rgb};
+use self::palette::{resolve_palette, rgb};
 
 pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
-    let palette = palette_for(app.settings.theme);
+    let palette = resolve_palette(&app.settings, app.light_theme());
+    let sources: Vec<_> = [app.diff_old_source(), app.diff_new_source()]
+        .into_iter()
+        .flatten()
+        .collect();
+    highlighter.prune_cache(app.refresh_generation(), &sources);
     let root = frame.area();
 
     let (main_area, footer_area) = layout::split_root(root);
-    let (sidebar_area, diff_area) = layout::split_main_area(main_area, &app.settings);
+    let (content_area, dock_area) =
+        layout::split_terminal_dock(main_area, &app.settings, app.terminal_open);
+    let (sidebar_area, diff_area) = layout::split_main_area(content_area, &app.settings);
 
     let (diff_header_area, diff_body_area) = layout::split_diff(diff_area);
     let resolved_layout = app.resolved_diff_layout(diff_body_area.width);
@@ -38,24 +46,37 @@ pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
         &palette,
     );
 
+    let wrap = app.diff_wrap_widths(resolved_layout, diff_body_area);
     match resolved_layout {
         ResolvedDiffLayout::Split => {
             let panes = layout::split_split_diff(diff_body_area);
-            diff::render_split_diff_panes(frame, app, panes, highlighter, &palette);
+            diff::render_split_diff_panes(frame, app, panes, wrap, highlighter, &palette);
         }
         ResolvedDiffLayout::Unified => {
-            diff::render_unified_diff_pane(frame, app, diff_body_area, highlighter, &palette);
+            diff::render_unified_diff_pane(frame, app, diff_body_area, wrap, highlighter, &palette);
         }
     }
 
+    if let Some(area) = dock_area {
+        modal::render_terminal_pane(frame, app, area, &palette);
+    }
+
     render_footer(frame, app, footer_area, &palette);
 
-    if app.terminal_open {
+    if app.terminal_overlay_open() {
         modal::render_terminal_modal(frame, app, root, &palette);
     } else if app.git_panel_open {
         modal::render_git_modal(frame, app, root, &palette);
     } else if app.settings_open {
         modal::render_settings_modal(frame, app, root, &palette);
+    } else if app.finder_open {
+        finder::render_finder_modal(frame, app, root, &palette);
+    } else if app.task_picker_open {
+        modal::render_task_picker_modal(frame, app, root, &palette);
+    }
+
+    if app.command_palette_open {
+        finder::render_command_palette_modal(frame, app, root, &palette);
     }
 
     if app.help_open {
@@ -76,9 +97,25 @@ fn render_footer(
     };
 
     let hints = footer_hint_variants(app);
-    let (status_text, hint_text) = fit_footer_parts(app.status_text(), &hints, area.width as usize);
+    let badge = attention_badge(app, palette);
+    let badge_width = badge.as_ref().map_or(0, |(text, _)| text.chars().count());
+    let (status_text, hint_text) = fit_footer_parts(
+        app.status_text(),
+        &hints,
+        (area.width as usize).saturating_sub(badge_width),
+    );
 
     let mut spans = Vec::new();
+    if let Some((text, color)) = badge {
+        spans.push(Span::styled(
+            text,
+            Style::default()
+                .fg(rgb(palette.modal_bg))
+                .bg(rgb(color))
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(" "));
+    }
     if !status_text.is_empty() {
         spans.push(Span::styled(
             status_text,
@@ -97,39 +134,132 @@ fn render_footer(
     frame.render_widget(footer, area);
 }
 
+/// Badge naming the terminal tabs that finished or rang the bell out of
+/// sight, colored by the most urgent of them.
+fn attention_badge(app: &App, palette: &palette::Palette) -> Option<(String, (u8, u8, u8))> {
+    let tabs = app.terminal_tabs_needing_attention();
+    if tabs.is_empty() {
+        return None;
+    }
+
+    let color = if tabs
+        .iter()
+        .any(|(_, attention)| *attention == TabAttention::Failed)
+    {
+        palette.status_error
+    } else {
+        palette.status_warn
+    };
+    let text = match tabs.as_slice() {
+        [(name, TabAttention::Bell)] => format!(" bell: {name} "),
+        [(name, TabAttention::Finished)] => format!(" done: {name} "),
+        [(name, TabAttention::Failed)] => format!(" failed: {name} "),
+        _ => format!(" {} terminals need attention ", tabs.len()),
+    };
+    Some((truncate_with_ellipsis(&text, 32), color))
+}
+
 fn footer_hint_variants(app: &App) -> Vec<String> {
+    let keys = &app.keymap;
     if app.help_open {
         return vec![
-            String::from("help: Esc/q/?/F1 close"),
-            String::from("main: Tab pane  Enter toggle  q quit"),
+            format!("help: Esc/q/{}/F1 close", keys.key(Action::ToggleHelp)),
+            format!(
+                "main: {} pane  {} toggle  {} quit",
+                keys.key(Action::CycleFocusForward),
+                keys.key(Action::StageToggle),
+                keys.key(Action::Quit),
+            ),
+        ];
+    }
+
+    if let Some(action) = app.pending_undo_action() {
+        return vec![
+            keymap::footer_hint_pending_undo(action.label()),
+            String::from("Enter/y confirm  n/Esc cancel"),
+        ];
+    }
+
+    if app.has_pending_repo_trust() {
+        return vec![
+            keymap::footer_hint_pending_undo("trusting this repo"),
+            String::from("Enter/y confirm  n/Esc cancel"),
+        ];
+    }
+
+    if app.command_palette_open {
+        return vec![
+            keymap::footer_hint_command_palette().to_owned(),
+            String::from("commands: type, Enter run, Esc cancel"),
+        ];
+    }
+
+    if app.finder_open {
+        return vec![
+            keymap::footer_hint_finder().to_owned(),
+            String::from("find: type, Enter jump, Esc cancel"),
+        ];
+    }
+
+    if app.tree_filter_editing {
+        return vec![
+            keymap::footer_hint_tree_filter().to_owned(),
+            String::from("filter: type, Enter keep, Esc clear"),
+        ];
+    }
+
+    if app.task_picker_open {
+        return vec![
+            keymap::footer_hint_task_picker(keys),
+            String::from("tasks: Enter run, Esc close"),
         ];
     }
 
-    if app.has_pending_undo_confirmation() {
+    if app.terminal_has_focus() && app.terminal_rename_open {
         return vec![
-            keymap::footer_hint_pending_undo().to_owned(),
-            String::from("undo: Enter/y confirm  n/Esc cancel"),
+            keymap::footer_hint_terminal_rename().to_owned(),
+            String::from("rename: type, Enter save, Esc cancel"),
         ];
     }
 
-    if app.terminal_open && app.terminal_search_open {
+    if app.terminal_has_focus() && app.terminal_search_open() {
         return vec![
-            keymap::footer_hint_terminal_search().to_owned(),
+            keymap::footer_hint_terminal_search(&app.keymap),
             String::from("search: type, Enter find, Esc cancel"),
         ];
     }
 
-    if app.terminal_open && app.terminal_copy_mode {
+    if app.terminal_has_focus() && app.terminal_copy_mode() {
         return vec![
-            keymap::footer_hint_terminal_copy().to_owned(),
-            String::from("copy: move  v mark  y copy  / search  i shell"),
+            keymap::footer_hint_terminal_copy(&app.keymap),
+            format!(
+                "copy: move  {} mark  {} copy  {} {} search  {}/{}  {}/{} file:line  {} save  {} shell",
+                keys.key(Action::TerminalSelectionAnchor),
+                keys.key(Action::TerminalYank),
+                keys.key(Action::TerminalSearch),
+                keys.key(Action::TerminalSearchBackward),
+                keys.key(Action::TerminalSearchNext),
+                keys.key(Action::TerminalSearchPrev),
+                keys.key(Action::TerminalNextRef),
+                keys.key(Action::TerminalOpenRef),
+                keys.key(Action::TerminalExport),
+                keys.key(Action::TerminalInteractiveMode),
+            ),
         ];
     }
 
-    if app.terminal_open {
+    if app.terminal_has_focus() {
         return vec![
-            keymap::footer_hint_terminal(),
-            String::from("terminal: Alt+c copy mode  Esc/Ctrl+]/g/q/w close"),
+            keymap::footer_hint_terminal(&app.keymap),
+            format!(
+                "terminal: {} copy  {}/{} tab  {}/{} switch  {} leave",
+                keys.key(Action::TerminalCopyMode),
+                keys.key(Action::TerminalNewTab),
+                keys.key(Action::TerminalCloseTab),
+                keys.key(Action::TerminalNextTab),
+                keys.key(Action::TerminalPrevTab),
+                keys.key(Action::TerminalHide),
+            ),
         ];
     }
 
@@ -142,21 +272,32 @@ fn footer_hint_variants(app: &App) -> Vec<String> {
         }
 
         return vec![
-            keymap::footer_hint_git_panel(),
-            String::from("git: Enter switch  n new  d delete  c commit  Esc close"),
+            keymap::footer_hint_git_panel(&app.keymap),
+            format!(
+                "git: Enter switch  {} new  {} delete  {} commit  Esc close",
+                keys.key(Action::GitCreateBranch),
+                keys.key(Action::GitDeleteBranch),
+                keys.key(Action::GitCommit),
+            ),
         ];
     }
 
     if app.settings_open {
         return vec![
-            keymap::footer_hint_settings().to_owned(),
-            String::from("settings: jk select  hl change  Esc/q close"),
+            keymap::footer_hint_settings(&app.keymap),
+            format!(
+                "settings: {}{} select  {}{} change  Esc/q close",
+                keys.key(Action::MoveDown),
+                keys.key(Action::MoveUp),
+                keys.key(Action::SettingDecrease),
+                keys.key(Action::SettingIncrease),
+            ),
         ];
     }
 
     vec![
-        String::from("Tab/h/l pane  j/k move  Enter toggle  PgUp/PgDn/Home/End navigate"),
-        String::from("s stage  u unstage  x undo  g branches  : terminal  ? help  q quit"),
+        keymap::footer_hint_main_navigation(keys),
+        keymap::footer_hint_main_compact(keys),
     ]
 }
 
@@ -6,32 +6,66 @@ use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
 use tui_term::widget::{Cursor, PseudoTerminal};
 
-use crate::app::{App, GitPanelMode};
-use crate::keymap;
+use crate::app::{
+    App, GitPanelMode, TabAttention, TaskRunState, TaskSource, TerminalLinkSpan,
+    TerminalSearchState,
+};
+use crate::keymap::{self, Action};
 use crate::layout;
 
-use super::palette::{Palette, rgb};
+use super::palette::{Palette, border_style, rgb};
 
 pub(crate) fn render_terminal_modal(frame: &mut Frame, app: &App, area: Rect, palette: &Palette) {
     let popup = layout::terminal_popup(area);
     frame.render_widget(Clear, popup);
+    render_terminal_frame(
+        frame,
+        app,
+        popup,
+        Style::default().fg(rgb(palette.modal_border)),
+        palette,
+    );
+}
 
-    let block = Block::default()
+pub(crate) fn render_terminal_pane(frame: &mut Frame, app: &App, area: Rect, palette: &Palette) {
+    render_terminal_frame(
+        frame,
+        app,
+        area,
+        border_style(app.terminal_has_focus(), palette),
+        palette,
+    );
+}
+
+fn render_terminal_frame(
+    frame: &mut Frame,
+    app: &App,
+    area: Rect,
+    border: Style,
+    palette: &Palette,
+) {
+    let mut block = Block::default()
+        .title(terminal_tab_title(app, palette))
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(rgb(palette.modal_border)))
+        .border_style(border)
         .style(
             Style::default()
                 .bg(rgb(palette.modal_bg))
                 .fg(rgb(palette.text)),
         );
-    let inner = block.inner(popup);
-    frame.render_widget(block, popup);
+    if app.terminal_copy_mode()
+        && let Some(search) = app.terminal_search_state()
+    {
+        block = block.title_bottom(terminal_search_line(&search, palette));
+    }
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
 
     let terminal_screen = app.terminal_screen();
     let copy_cursor = app.terminal_cursor();
 
     if let Some(screen) = terminal_screen {
-        let terminal_widget = if app.terminal_copy_mode {
+        let terminal_widget = if app.terminal_copy_mode() || !app.terminal_has_focus() {
             PseudoTerminal::new(screen).cursor(Cursor::default().visibility(false))
         } else {
             PseudoTerminal::new(screen)
@@ -45,10 +79,21 @@ pub(crate) fn render_terminal_modal(frame: &mut Frame, app: &App, area: Rect, pa
             },
             inner,
         );
+        frame.render_widget(
+            HyperlinkOverlay {
+                spans: app.terminal_link_spans(),
+                fg: rgb(palette.border_focus),
+            },
+            inner,
+        );
 
-        if app.terminal_copy_mode {
+        if app.terminal_copy_mode() {
             let overlay = CopyModeOverlay {
                 selection_rows: app.terminal_selection_rows(),
+                search_matches: app.terminal_search_highlights(),
+                match_style: Style::default()
+                    .fg(rgb(palette.modal_bg))
+                    .bg(rgb(palette.status_warn)),
                 cursor: copy_cursor,
                 selection_bg: rgb(palette.selected_bg_unfocused),
                 cursor_row_bg: rgb(palette.selected_bg_focused),
@@ -66,6 +111,171 @@ pub(crate) fn render_terminal_modal(frame: &mut Frame, app: &App, area: Rect, pa
     }
 }
 
+/// The search prompt (or last query and match counter) on the bottom border.
+fn terminal_search_line(search: &TerminalSearchState, palette: &Palette) -> Line<'static> {
+    let prefix = if search.backward { "?" } else { "/" };
+    let query_style = Style::default().fg(rgb(palette.text));
+    let mut spans = vec![Span::styled(
+        format!(" {prefix}{}", search.query),
+        query_style,
+    )];
+    if search.editing {
+        spans.push(Span::styled(
+            "_",
+            Style::default().fg(rgb(palette.border_focus)),
+        ));
+    }
+
+    let flag = |label: &str, on: bool| {
+        let color = if on {
+            palette.border_focus
+        } else {
+            palette.dim
+        };
+        Span::styled(format!(" [{label}]"), Style::default().fg(rgb(color)))
+    };
+    spans.push(flag(".*", search.regex));
+    spans.push(flag("Aa", search.case_sensitive));
+
+    if let Some(error) = &search.error {
+        spans.push(Span::styled(
+            format!(" {error}"),
+            Style::default().fg(rgb(palette.status_error)),
+        ));
+    } else if let Some((current, total)) = search.position {
+        spans.push(Span::styled(
+            format!(" {current}/{total}"),
+            Style::default().fg(rgb(palette.status_warn)),
+        ));
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans)
+}
+
+fn terminal_tab_title(app: &App, palette: &Palette) -> Line<'static> {
+    let mut spans = vec![Span::raw(" ")];
+    for (idx, tab) in app.terminal_tabs().iter().enumerate() {
+        let active = idx == app.terminal_active_tab_index();
+        let name = if active && app.terminal_rename_open {
+            format!("{}_", app.terminal_rename_input)
+        } else {
+            tab.name.clone()
+        };
+        let mut label = format!("{}:{name}", idx + 1);
+        let failed = tab.task_name().is_some() && tab.exit_success() == Some(false);
+        if failed {
+            label.push_str(" [failed]");
+        } else if tab.task_name().is_some() && !tab.is_running() {
+            label.push_str(" [done]");
+        } else if !tab.is_running() {
+            label.push_str(" [exited]");
+        } else if tab.attention() == Some(TabAttention::Bell) {
+            label.push_str(" [bell]");
+        }
+
+        let fg = if failed {
+            palette.status_error
+        } else if active {
+            palette.text
+        } else {
+            palette.dim
+        };
+        let style = if active {
+            Style::default()
+                .fg(rgb(fg))
+                .bg(rgb(palette.modal_selected_bg))
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(rgb(fg))
+        };
+        spans.push(Span::styled(format!(" {label} "), style));
+        spans.push(Span::raw(" "));
+    }
+    Line::from(spans)
+}
+
+pub(crate) fn render_task_picker_modal(
+    frame: &mut Frame,
+    app: &App,
+    area: Rect,
+    palette: &Palette,
+) {
+    let popup = layout::task_popup(area);
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .title(" Run Task ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(rgb(palette.modal_border)))
+        .style(
+            Style::default()
+                .bg(rgb(palette.modal_bg))
+                .fg(rgb(palette.text)),
+        );
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    let tasks = app.tasks();
+    let name_width = tasks
+        .iter()
+        .map(|task| task.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (idx, task) in tasks.iter().enumerate() {
+        let selected = idx == app.task_picker_selected;
+        let row_style = if selected {
+            Style::default()
+                .fg(rgb(palette.text))
+                .bg(rgb(palette.modal_selected_bg))
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(rgb(palette.text))
+        };
+
+        let mut spans = vec![
+            Span::styled(
+                format!(
+                    "{} {}  {:<name_width$}  ",
+                    if selected { ">" } else { " " },
+                    idx + 1,
+                    task.name
+                ),
+                row_style,
+            ),
+            Span::styled(
+                format!("{:<6} ", task.source.label()),
+                row_style.fg(rgb(match task.source {
+                    TaskSource::Global => palette.dim,
+                    TaskSource::Repo => palette.status_warn,
+                })),
+            ),
+            Span::styled(task.command.clone(), row_style.fg(rgb(palette.dim))),
+        ];
+        if let Some(state) = app.task_run_state(&task.name) {
+            let color = match state {
+                TaskRunState::Running => palette.status_warn,
+                TaskRunState::Passed => palette.text,
+                TaskRunState::Failed => palette.status_error,
+            };
+            spans.push(Span::styled(
+                format!("  [{}]", state.label()),
+                row_style.fg(rgb(color)),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::styled(
+        "Each task runs in its own terminal tab; rerunning reuses that tab. `repo` tasks come from .dif.toml.",
+        Style::default().fg(rgb(palette.dim)),
+    ));
+
+    frame.render_widget(Paragraph::new(Text::from(lines)), inner);
+}
+
 pub(crate) fn render_settings_modal(frame: &mut Frame, app: &App, area: Rect, palette: &Palette) {
     let popup = layout::settings_popup(area);
     frame.render_widget(Clear, popup);
@@ -224,15 +434,18 @@ pub(crate) fn render_git_modal(frame: &mut Frame, app: &App, area: Rect, palette
             Line::styled(
                 format!(
                     "{} new branch  Enter/{} switch  {} delete  {} commit",
-                    keymap::KEY_GIT_CREATE_BRANCH,
-                    keymap::KEY_GIT_SWITCH_BRANCH,
-                    keymap::KEY_GIT_DELETE_BRANCH,
-                    keymap::KEY_GIT_COMMIT,
+                    app.keymap.key(Action::GitCreateBranch),
+                    app.keymap.key(Action::GitSwitchBranch),
+                    app.keymap.key(Action::GitDeleteBranch),
+                    app.keymap.key(Action::GitCommit),
                 ),
                 Style::default().fg(rgb(palette.dim)),
             ),
             Line::styled(
-                format!("Esc/q/{} closes this panel", keymap::KEY_OPEN_GIT_PANEL),
+                format!(
+                    "Esc/q/{} closes this panel",
+                    app.keymap.key(Action::ToggleGitPanel)
+                ),
                 Style::default().fg(rgb(palette.dim)),
             ),
         ],
@@ -296,7 +509,9 @@ pub(crate) fn render_help_modal(frame: &mut Frame, app: &App, area: Rect, palett
     frame.render_widget(block, popup);
 
     let branch = app.current_branch_name().unwrap_or("<detached>");
-    let lines = vec![
+    let keys = &app.keymap;
+    let text = |line: String| Line::styled(line, Style::default().fg(rgb(palette.text)));
+    let mut lines = vec![
         Line::styled(
             format!(
                 "repo: {}  |  branch: {}  |  focus: {}",
@@ -313,34 +528,10 @@ pub(crate) fn render_help_modal(frame: &mut Frame, app: &App, area: Rect, palett
                 .fg(rgb(palette.border_focus))
                 .add_modifier(Modifier::BOLD),
         ),
-        Line::styled(
-            "Tab/Shift+Tab toggles between sidebar and diff",
-            Style::default().fg(rgb(palette.text)),
-        ),
-        Line::styled(
-            "j/k or Up/Down move selection or scroll diff",
-            Style::default().fg(rgb(palette.text)),
-        ),
-        Line::styled(
-            "h/l or Left/Right switch pane focus",
-            Style::default().fg(rgb(palette.text)),
-        ),
-        Line::styled(
-            "Enter/Space toggle stage state; s stage; u unstage; x undo",
-            Style::default().fg(rgb(palette.text)),
-        ),
-        Line::styled(
-            "Tree mode markers: left M staged (green), right M unstaged (red)",
-            Style::default().fg(rgb(palette.text)),
-        ),
-        Line::styled(
-            "Home/End jump to edge; PageUp/PageDown move by page",
-            Style::default().fg(rgb(palette.text)),
-        ),
-        Line::styled(
-            "g branches; c commit; : or ! terminal; o settings; r refresh",
-            Style::default().fg(rgb(palette.text)),
-        ),
+    ];
+    lines.extend(keymap::help_main_lines(keys).into_iter().map(text));
+    lines.extend([
+        Line::from(""),
         Line::from(""),
         Line::styled(
             "GIT PANEL",
@@ -348,10 +539,9 @@ pub(crate) fn render_help_modal(frame: &mut Frame, app: &App, area: Rect, palett
                 .fg(rgb(palette.border_focus))
                 .add_modifier(Modifier::BOLD),
         ),
-        Line::styled(
-            "Enter switch branch; n (or a) create; d delete; c commit prompt",
-            Style::default().fg(rgb(palette.text)),
-        ),
+    ]);
+    lines.extend(keymap::help_git_lines(keys).into_iter().map(text));
+    lines.extend([
         Line::from(""),
         Line::styled(
             "TERMINAL",
@@ -359,16 +549,15 @@ pub(crate) fn render_help_modal(frame: &mut Frame, app: &App, area: Rect, palett
                 .fg(rgb(palette.border_focus))
                 .add_modifier(Modifier::BOLD),
         ),
-        Line::styled(
-            "Alt+c copy mode; Shift+Up/Down fast scroll; Esc/Ctrl+]/g/q/w close",
-            Style::default().fg(rgb(palette.text)),
-        ),
+    ]);
+    lines.extend(keymap::help_terminal_lines(keys).into_iter().map(text));
+    lines.extend([
         Line::from(""),
         Line::styled(
-            "Close: Esc, q, ?, or F1",
+            format!("Close: Esc, q, {}, or F1", keys.key(Action::ToggleHelp)),
             Style::default().fg(rgb(palette.dim)),
         ),
-    ];
+    ]);
 
     let paragraph = Paragraph::new(Text::from(lines)).style(
         Style::default()
@@ -446,6 +635,8 @@ fn line_with_editor_cursor(
 
 struct CopyModeOverlay {
     selection_rows: Option<(usize, usize)>,
+    search_matches: Vec<(usize, usize, usize)>,
+    match_style: Style,
     cursor: (usize, usize),
     selection_bg: ratatui::style::Color,
     cursor_row_bg: ratatui::style::Color,
@@ -473,6 +664,29 @@ impl Widget for TerminalPaletteDefaults {
     }
 }
 
+/// Underlines OSC 8 hyperlinks, which `tui_term` renders as plain text.
+struct HyperlinkOverlay {
+    spans: Vec<TerminalLinkSpan>,
+    fg: Color,
+}
+
+impl Widget for HyperlinkOverlay {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for span in self.spans {
+            if span.row >= area.height as usize {
+                continue;
+            }
+            let y = area.y + span.row as u16;
+            let end = span.end.min(area.width as usize);
+            for col in span.start..end {
+                let cell = &mut buf[(area.x + col as u16, y)];
+                cell.set_fg(self.fg);
+                cell.modifier.insert(Modifier::UNDERLINED);
+            }
+        }
+    }
+}
+
 impl Widget for CopyModeOverlay {
     fn render(self, area: Rect, buf: &mut Buffer) {
         if area.width == 0 || area.height == 0 {
@@ -504,6 +718,15 @@ impl Widget for CopyModeOverlay {
 
         fill_row_bg(cursor_row, self.cursor_row_bg);
 
+        for (row, start, end) in self.search_matches {
+            if row >= max_row {
+                continue;
+            }
+            for col in start..end.min(width) {
+                buf[(area.x + col as u16, area.y + row as u16)].set_style(self.match_style);
+            }
+        }
+
         let x = area.x + cursor_col as u16;
 
         let cell = &mut buf[(x, y)];
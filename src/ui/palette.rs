@@ -1,6 +1,6 @@
 use ratatui::style::{Color, Modifier, Style};
 
-use crate::settings::AppTheme;
+use crate::settings::{AppSettings, AppTheme, HexColor};
 
 #[derive(Clone, Copy)]
 pub(crate) struct Palette {
@@ -27,7 +27,53 @@ pub(crate) struct Palette {
     pub status_error: (u8, u8, u8),
 }
 
-pub(crate) fn palette_for(theme: AppTheme) -> Palette {
+/// The palette to draw with: the built-in base theme with any colors from the
+/// active `[themes]` entry on top.
+pub(crate) fn resolve_palette(settings: &AppSettings, light: bool) -> Palette {
+    let mut palette = palette_for(settings.base_theme(), light);
+    if let Some((_, custom)) = settings.active_custom_theme() {
+        for (name, color) in custom.valid_colors() {
+            set_color(&mut palette, name, color);
+        }
+    }
+    palette
+}
+
+/// Sets the field called `name`; false for a name `Palette` does not have.
+fn set_color(palette: &mut Palette, name: &str, color: HexColor) -> bool {
+    let slot = match name {
+        "pane_bg" => &mut palette.pane_bg,
+        "meta_bg" => &mut palette.meta_bg,
+        "added_bg" => &mut palette.added_bg,
+        "removed_bg" => &mut palette.removed_bg,
+        "text" => &mut palette.text,
+        "dim" => &mut palette.dim,
+        "line_no" => &mut palette.line_no,
+        "marker_add" => &mut palette.marker_add,
+        "marker_remove" => &mut palette.marker_remove,
+        "marker_context" => &mut palette.marker_context,
+        "border" => &mut palette.border,
+        "border_focus" => &mut palette.border_focus,
+        "selected_bg_focused" => &mut palette.selected_bg_focused,
+        "selected_bg_unfocused" => &mut palette.selected_bg_unfocused,
+        "untracked" => &mut palette.untracked,
+        "footer" => &mut palette.footer,
+        "modal_bg" => &mut palette.modal_bg,
+        "modal_border" => &mut palette.modal_border,
+        "modal_selected_bg" => &mut palette.modal_selected_bg,
+        "status_warn" => &mut palette.status_warn,
+        "status_error" => &mut palette.status_error,
+        _ => return false,
+    };
+    *slot = (color.0, color.1, color.2);
+    true
+}
+
+pub(crate) fn palette_for(theme: AppTheme, light: bool) -> Palette {
+    if light {
+        return light_palette_for(theme);
+    }
+
     match theme {
         AppTheme::Ocean => Palette {
             pane_bg: (17, 20, 27),
@@ -124,6 +170,103 @@ pub(crate) fn palette_for(theme: AppTheme) -> Palette {
     }
 }
 
+fn light_palette_for(theme: AppTheme) -> Palette {
+    match theme {
+        AppTheme::Ocean => Palette {
+            pane_bg: (239, 241, 245),
+            meta_bg: (223, 227, 234),
+            added_bg: (210, 237, 217),
+            removed_bg: (247, 214, 218),
+            text: (43, 48, 59),
+            dim: (96, 108, 124),
+            line_no: (120, 130, 146),
+            marker_add: (34, 124, 58),
+            marker_remove: (184, 44, 58),
+            marker_context: (96, 108, 124),
+            border: (168, 176, 190),
+            border_focus: (170, 110, 16),
+            selected_bg_focused: (198, 211, 233),
+            selected_bg_unfocused: (215, 223, 236),
+            untracked: (18, 122, 114),
+            footer: (96, 108, 124),
+            modal_bg: (248, 249, 251),
+            modal_border: (108, 118, 138),
+            modal_selected_bg: (193, 206, 230),
+            status_warn: (160, 100, 8),
+            status_error: (184, 44, 58),
+        },
+        AppTheme::Eighties => Palette {
+            pane_bg: (242, 240, 236),
+            meta_bg: (228, 224, 218),
+            added_bg: (213, 236, 213),
+            removed_bg: (245, 213, 216),
+            text: (57, 52, 64),
+            dim: (110, 102, 122),
+            line_no: (134, 126, 146),
+            marker_add: (52, 128, 64),
+            marker_remove: (184, 54, 78),
+            marker_context: (110, 102, 122),
+            border: (180, 172, 190),
+            border_focus: (176, 116, 24),
+            selected_bg_focused: (214, 204, 228),
+            selected_bg_unfocused: (228, 221, 236),
+            untracked: (26, 128, 114),
+            footer: (110, 102, 122),
+            modal_bg: (250, 248, 245),
+            modal_border: (118, 108, 134),
+            modal_selected_bg: (208, 196, 226),
+            status_warn: (166, 106, 12),
+            status_error: (184, 54, 78),
+        },
+        AppTheme::Solarized => Palette {
+            pane_bg: (253, 246, 227),
+            meta_bg: (238, 232, 213),
+            added_bg: (222, 236, 204),
+            removed_bg: (246, 219, 206),
+            text: (7, 54, 66),
+            dim: (88, 110, 117),
+            line_no: (131, 148, 150),
+            marker_add: (92, 116, 0),
+            marker_remove: (196, 42, 40),
+            marker_context: (88, 110, 117),
+            border: (188, 183, 165),
+            border_focus: (160, 118, 0),
+            selected_bg_focused: (226, 218, 190),
+            selected_bg_unfocused: (234, 227, 204),
+            untracked: (26, 124, 118),
+            footer: (88, 110, 117),
+            modal_bg: (250, 243, 222),
+            modal_border: (120, 138, 142),
+            modal_selected_bg: (224, 215, 186),
+            status_warn: (160, 118, 0),
+            status_error: (196, 42, 40),
+        },
+        AppTheme::Monokai => Palette {
+            pane_bg: (250, 250, 245),
+            meta_bg: (236, 236, 228),
+            added_bg: (221, 239, 208),
+            removed_bg: (249, 217, 222),
+            text: (39, 40, 34),
+            dim: (108, 105, 88),
+            line_no: (140, 140, 128),
+            marker_add: (74, 128, 18),
+            marker_remove: (190, 28, 86),
+            marker_context: (108, 105, 88),
+            border: (190, 190, 178),
+            border_focus: (180, 114, 0),
+            selected_bg_focused: (226, 226, 208),
+            selected_bg_unfocused: (238, 238, 226),
+            untracked: (18, 128, 138),
+            footer: (108, 105, 88),
+            modal_bg: (255, 255, 250),
+            modal_border: (128, 128, 116),
+            modal_selected_bg: (220, 220, 200),
+            status_warn: (172, 108, 0),
+            status_error: (190, 28, 86),
+        },
+    }
+}
+
 pub(crate) fn border_style(focused: bool, palette: &Palette) -> Style {
     if focused {
         Style::default().fg(rgb(palette.border_focus))
@@ -148,3 +291,52 @@ pub(crate) fn selected_style(focused: bool, palette: &Palette) -> Style {
 pub(crate) fn rgb(value: (u8, u8, u8)) -> Color {
     Color::Rgb(value.0, value.1, value.2)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{palette_for, resolve_palette, set_color};
+    use crate::settings::{AppSettings, AppTheme, CustomTheme, HexColor, PALETTE_COLOR_NAMES};
+
+    #[test]
+    fn every_documented_color_name_sets_a_field() {
+        let mut palette = palette_for(AppTheme::Ocean, false);
+        for name in PALETTE_COLOR_NAMES {
+            assert!(
+                set_color(&mut palette, name, HexColor(1, 2, 3)),
+                "{name} is not a palette field"
+            );
+        }
+        assert!(!set_color(&mut palette, "txt", HexColor(1, 2, 3)));
+    }
+
+    #[test]
+    fn custom_theme_overrides_its_inherited_palette() {
+        let settings = AppSettings {
+            custom_theme: Some(String::from("contrast")),
+            themes: BTreeMap::from([(
+                String::from("contrast"),
+                CustomTheme {
+                    inherit: Some(AppTheme::Monokai),
+                    colors: BTreeMap::from([
+                        (String::from("text"), String::from("#ffffff")),
+                        (String::from("border"), String::from("#12345")),
+                    ]),
+                    ..CustomTheme::default()
+                },
+            )]),
+            ..AppSettings::default()
+        };
+
+        let palette = resolve_palette(&settings, false);
+        let monokai = palette_for(AppTheme::Monokai, false);
+        assert_eq!(palette.text, (255, 255, 255));
+        assert_eq!(palette.pane_bg, monokai.pane_bg);
+        assert_eq!(palette.border, monokai.border);
+
+        let light = resolve_palette(&settings, true);
+        assert_eq!(light.text, (255, 255, 255));
+        assert_eq!(light.pane_bg, palette_for(AppTheme::Monokai, true).pane_bg);
+    }
+}
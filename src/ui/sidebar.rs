@@ -1,16 +1,42 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::style::Style;
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Paragraph};
 
-use crate::app::{App, PaneFocus};
+use crate::app::{App, PaneFocus, TreeRow, TreeRowKind};
+use crate::git::LineStat;
 
 use super::palette::{Palette, border_style, rgb, selected_style};
 
 pub(crate) fn render_tree(frame: &mut Frame, app: &App, area: Rect, palette: &Palette) {
     let focused = app.pane_focus == PaneFocus::Sidebar;
-    let title = format!(" Changes ({}) ", app.tree_files.len());
+    let title = if app.tree_filter_editing {
+        format!(
+            " Changes ({}/{}) filter: {}_ ",
+            app.tree_files.len(),
+            app.tree_file_total(),
+            app.tree_filter
+        )
+    } else if app.tree_filter_active() {
+        format!(
+            " Changes ({}/{}) filter: {} ",
+            app.tree_files.len(),
+            app.tree_file_total(),
+            app.tree_filter.trim()
+        )
+    } else {
+        let (staged, unstaged) = app.tree_line_totals();
+        let mut title = format!(" Changes ({})", app.tree_files.len());
+        if !staged.is_empty() {
+            title.push_str(&format!("  S {}", format_line_stat(staged)));
+        }
+        if !unstaged.is_empty() {
+            title.push_str(&format!("  U {}", format_line_stat(unstaged)));
+        }
+        title.push(' ');
+        title
+    };
     let block = Block::default()
         .borders(Borders::ALL)
         .title(title)
@@ -20,8 +46,8 @@ pub(crate) fn render_tree(frame: &mut Frame, app: &App, area: Rect, palette: &Pa
     let file_rows = visible_rows.saturating_sub(1);
     let start = app
         .tree_scroll
-        .min(app.tree_files.len().saturating_sub(file_rows));
-    let end = (start + file_rows).min(app.tree_files.len());
+        .min(app.tree_rows.len().saturating_sub(file_rows));
+    let end = (start + file_rows).min(app.tree_rows.len());
 
     let mut lines = vec![Line::from(vec![
         Span::styled("S", Style::default().fg(rgb(palette.marker_add))),
@@ -30,14 +56,19 @@ pub(crate) fn render_tree(frame: &mut Frame, app: &App, area: Rect, palette: &Pa
         Span::styled("  Path", Style::default().fg(rgb(palette.dim))),
     ])];
 
-    if app.tree_files.is_empty() {
+    if app.tree_rows.is_empty() && app.tree_filter_active() {
+        lines.push(Line::styled(
+            "(no files match the filter)",
+            Style::default().fg(rgb(palette.dim)),
+        ));
+    } else if app.tree_rows.is_empty() {
         lines.push(Line::styled(
             "(clean) no staged or unstaged files",
             Style::default().fg(rgb(palette.dim)),
         ));
     } else {
         for idx in start..end {
-            let entry = &app.tree_files[idx];
+            let row = &app.tree_rows[idx];
             let selected = app.tree_selected == Some(idx);
             let line_style = if selected {
                 selected_style(focused, palette)
@@ -48,25 +79,32 @@ pub(crate) fn render_tree(frame: &mut Frame, app: &App, area: Rect, palette: &Pa
             let mut spans = vec![
                 Span::raw(if selected { "> " } else { "  " }),
                 Span::styled(
-                    if entry.staged { "M" } else { " " },
+                    if row.staged_count > 0 { "M" } else { " " },
                     Style::default().fg(rgb(palette.marker_add)),
                 ),
                 Span::raw(" "),
                 Span::styled(
-                    if entry.unstaged { "M" } else { " " },
+                    if row.unstaged_count > 0 { "M" } else { " " },
                     Style::default().fg(rgb(palette.marker_remove)),
                 ),
                 Span::raw(" "),
+                Span::raw("  ".repeat(row.depth)),
             ];
 
-            spans.extend(path_spans(&entry.path, entry.untracked, palette));
-
-            if entry.untracked {
-                spans.push(Span::styled(
-                    " [new]",
-                    Style::default().fg(rgb(palette.untracked)),
-                ));
+            match row.kind {
+                TreeRowKind::Directory | TreeRowKind::Group => {
+                    spans.extend(header_spans(row, palette));
+                }
+                TreeRowKind::File => {
+                    let untracked = row
+                        .file_index
+                        .and_then(|file_idx| app.tree_files.get(file_idx))
+                        .map(|entry| entry.untracked)
+                        .unwrap_or(false);
+                    spans.extend(file_spans(row, untracked, palette));
+                }
             }
+            spans.extend(line_stat_spans(row, palette));
 
             lines.push(Line::from(spans).style(line_style));
         }
@@ -78,32 +116,98 @@ pub(crate) fn render_tree(frame: &mut Frame, app: &App, area: Rect, palette: &Pa
     frame.render_widget(paragraph, area);
 }
 
-fn path_spans(path: &str, untracked: bool, palette: &Palette) -> Vec<Span<'static>> {
-    let segments = path.split('/').collect::<Vec<_>>();
-    if segments.is_empty() {
-        return vec![Span::styled(
-            path.to_owned(),
-            Style::default().fg(rgb(palette.text)),
-        )];
+fn header_spans(row: &TreeRow, palette: &Palette) -> Vec<Span<'static>> {
+    let label = match row.kind {
+        TreeRowKind::Directory => format!("{}/", row.label),
+        _ => row.label.clone(),
+    };
+
+    vec![
+        Span::styled(
+            if row.collapsed { "▸ " } else { "▾ " },
+            Style::default().fg(rgb(palette.dim)),
+        ),
+        Span::styled(
+            label,
+            Style::default()
+                .fg(rgb(palette.text))
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(" {}", row.file_count),
+            Style::default().fg(rgb(palette.dim)),
+        ),
+    ]
+}
+
+fn file_spans(row: &TreeRow, untracked: bool, palette: &Palette) -> Vec<Span<'static>> {
+    let mut spans = vec![
+        Span::raw("  "),
+        Span::styled(
+            row.label.clone(),
+            if untracked {
+                Style::default().fg(rgb(palette.untracked))
+            } else {
+                Style::default().fg(rgb(palette.text))
+            },
+        ),
+    ];
+
+    if untracked {
+        spans.push(Span::styled(
+            " [new]",
+            Style::default().fg(rgb(palette.untracked)),
+        ));
     }
 
+    spans
+}
+
+fn line_stat_spans(row: &TreeRow, palette: &Palette) -> Vec<Span<'static>> {
     let mut spans = Vec::new();
-    for segment in &segments[..segments.len().saturating_sub(1)] {
+    for (side, stat) in [("S", row.staged_stat), ("U", row.unstaged_stat)] {
+        if stat.is_empty() {
+            continue;
+        }
+
         spans.push(Span::styled(
-            format!("{segment}/"),
+            format!("  {side} "),
             Style::default().fg(rgb(palette.dim)),
         ));
+        if stat.binary {
+            spans.push(Span::styled("bin", Style::default().fg(rgb(palette.dim))));
+            if stat.added == 0 && stat.removed == 0 {
+                continue;
+            }
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::styled(
+            added_label(stat),
+            Style::default().fg(rgb(palette.marker_add)),
+        ));
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!("-{}", stat.removed),
+            Style::default().fg(rgb(palette.marker_remove)),
+        ));
     }
+    spans
+}
 
-    let file_name = segments.last().copied().unwrap_or(path);
-    spans.push(Span::styled(
-        file_name.to_owned(),
-        if untracked {
-            Style::default().fg(rgb(palette.untracked))
-        } else {
-            Style::default().fg(rgb(palette.text))
-        },
-    ));
+/// `+?` for files too large to count.
+fn added_label(stat: LineStat) -> String {
+    if stat.uncounted {
+        String::from("+?")
+    } else {
+        format!("+{}", stat.added)
+    }
+}
 
-    spans
+fn format_line_stat(stat: LineStat) -> String {
+    let counts = format!("{} -{}", added_label(stat), stat.removed);
+    match (stat.binary, stat.added == 0 && stat.removed == 0) {
+        (true, true) => String::from("bin"),
+        (true, false) => format!("bin {counts}"),
+        (false, _) => counts,
+    }
 }
@@ -2,7 +2,20 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
-use dif::app::{App, FocusSection, GitPanelMode};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use dif::app::{
+    App, FocusSection, GitPanelMode, PaneFocus, StatusKind, TabAttention, TaskRunState, TaskSource,
+    TreeRowKind, UndoAction,
+};
+use dif::highlight::SourceFile;
+use dif::input::handle_event;
+use dif::keymap::Action;
+use dif::settings::{self, DiffViewMode, SidebarGrouping, TerminalDock, ThemeMode};
+use dif::terminal;
+use ratatui::layout::Rect;
 use tempfile::TempDir;
 
 #[test]
@@ -34,6 +47,81 @@ fn supports_undo_confirmation_state_transitions() {
     assert!(!app.has_pending_undo_confirmation());
 }
 
+#[test]
+fn discard_keeps_staged_work_and_reset_drops_it() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(repo.path().join("staged.txt"), "staged\nunstaged edit\n")
+        .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    assert!(
+        app.select_tree_path("staged.txt")
+            .expect("selecting should succeed")
+    );
+
+    app.discard_selected_worktree_changes()
+        .expect("starting discard should succeed");
+    assert_eq!(app.pending_undo_action(), Some(UndoAction::DiscardWorktree));
+    app.confirm_pending_undo_to_mainline()
+        .expect("discard should succeed");
+
+    let content = fs::read_to_string(repo.path().join("staged.txt")).expect("read should succeed");
+    assert_eq!(content, "staged\n");
+    assert!(app.staged.iter().any(|path| path == "staged.txt"));
+
+    assert!(
+        app.select_tree_path("tracked.txt")
+            .expect("selecting should succeed")
+    );
+    git(repo.path(), &["add", "tracked.txt"]).expect("git add should succeed");
+    app.refresh().expect("refresh should succeed");
+    app.reset_selected_to_head()
+        .expect("starting reset should succeed");
+    assert_eq!(app.pending_undo_action(), Some(UndoAction::ResetToHead));
+    app.confirm_pending_undo_to_mainline()
+        .expect("reset should succeed");
+
+    let content = fs::read_to_string(repo.path().join("tracked.txt")).expect("read should succeed");
+    assert_eq!(content, "line_a\n");
+    assert!(!app.staged.iter().any(|path| path == "tracked.txt"));
+}
+
+#[test]
+fn reset_warns_before_deleting_files_missing_from_head() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+
+    assert!(
+        app.select_tree_path("untracked.txt")
+            .expect("selecting should succeed")
+    );
+    app.reset_selected_to_head()
+        .expect("starting reset should succeed");
+    assert!(
+        app.status_text()
+            .starts_with("Delete untracked untracked.txt?")
+    );
+    app.cancel_pending_undo_to_mainline();
+
+    // `staged.txt` was added to the index but never committed.
+    assert!(
+        app.select_tree_path("staged.txt")
+            .expect("selecting should succeed")
+    );
+    app.reset_selected_to_head()
+        .expect("starting reset should succeed");
+    assert!(
+        app.status_text()
+            .starts_with("staged.txt is not in HEAD; delete it?")
+    );
+    app.confirm_pending_undo_to_mainline()
+        .expect("reset should succeed");
+
+    assert!(!repo.path().join("staged.txt").exists());
+    assert!(!app.staged.iter().any(|path| path == "staged.txt"));
+    assert_eq!(app.status_text(), "Deleted staged.txt");
+}
+
 #[test]
 fn creates_switches_and_deletes_branches() {
     let repo = setup_repo().expect("repo setup should succeed");
@@ -135,6 +223,628 @@ fn creates_multiline_commit_with_template() {
     );
 }
 
+#[test]
+fn finder_jumps_and_filter_narrows_sidebar() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    assert_eq!(app.tree_files.len(), 3);
+
+    app.open_finder();
+    for ch in "untr".chars() {
+        app.finder_input_append(ch);
+    }
+    assert_eq!(app.finder_results().len(), 1);
+    app.submit_finder().expect("finder jump should succeed");
+    assert!(!app.finder_open);
+    assert_eq!(app.active_path(), Some("untracked.txt"));
+
+    app.open_tree_filter();
+    for ch in "stgd".chars() {
+        app.tree_filter_append(ch)
+            .expect("filter update should succeed");
+    }
+    app.submit_tree_filter();
+    assert!(app.tree_filter_active());
+    assert_eq!(app.tree_files.len(), 1);
+    assert_eq!(app.tree_file_total(), 3);
+    assert_eq!(app.active_path(), Some("staged.txt"));
+
+    app.clear_tree_filter()
+        .expect("clearing filter should succeed");
+    assert_eq!(app.tree_files.len(), 3);
+    assert_eq!(app.active_path(), Some("staged.txt"));
+}
+
+#[test]
+fn command_palette_filters_actions_and_runs_the_selection() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+
+    let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+    assert!(handle_event(&mut app, Event::Key(ctrl_p)));
+    assert!(app.command_palette_open);
+    let results = app.command_palette_results();
+    assert!(
+        results
+            .iter()
+            .all(|entry| entry.action != Action::OpenCommandPalette)
+    );
+    let close_tab = results
+        .iter()
+        .find(|entry| entry.action == Action::TerminalCloseTab)
+        .expect("terminal actions are listed");
+    assert!(!close_tab.enabled, "no terminal tab to close yet");
+    let stage = results
+        .iter()
+        .find(|entry| entry.action == Action::Stage)
+        .expect("stage is listed");
+    assert!(stage.enabled);
+    assert_eq!(stage.keys, "s");
+
+    for ch in "close the terminal".chars() {
+        app.command_palette_append(ch);
+    }
+    assert_eq!(
+        app.command_palette_results()[0].action,
+        Action::TerminalCloseTab
+    );
+    assert_eq!(app.submit_command_palette(), None);
+    assert!(
+        app.command_palette_open,
+        "disabled commands keep the palette open"
+    );
+    assert_eq!(app.status.kind, StatusKind::Warn);
+
+    for _ in 0.."close the terminal".len() {
+        app.command_palette_backspace();
+    }
+    for ch in "cycle diff view".chars() {
+        app.command_palette_append(ch);
+    }
+    let before = app.settings.diff_view_mode;
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    assert!(handle_event(&mut app, Event::Key(enter)));
+    assert!(!app.command_palette_open);
+    assert_ne!(app.settings.diff_view_mode, before);
+
+    app.open_command_palette();
+    for ch in "quit".chars() {
+        app.command_palette_append(ch);
+    }
+    assert!(!handle_event(&mut app, Event::Key(enter)));
+}
+
+#[test]
+fn long_lines_wrap_or_scroll_sideways() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(
+        repo.path().join("tracked.txt"),
+        format!("line_a\n{}\n", "x".repeat(300)),
+    )
+    .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    app.settings.diff_view_mode = DiffViewMode::Unified;
+    assert!(
+        app.select_tree_path("tracked.txt")
+            .expect("selecting should succeed")
+    );
+    let root = Rect::new(0, 0, 120, 40);
+    app.update_layout(root).expect("layout should update");
+    let unwrapped_height = app.diff_content_height;
+
+    let key = |ch: char| Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::SHIFT));
+    handle_event(&mut app, key('L'));
+    handle_event(&mut app, key('L'));
+    assert_eq!(app.diff_hscroll, 16);
+    handle_event(&mut app, key('H'));
+    handle_event(&mut app, key('H'));
+    handle_event(&mut app, key('H'));
+    assert_eq!(app.diff_hscroll, 0);
+    for _ in 0..100 {
+        app.scroll_diff_horizontal(1);
+    }
+    assert!(
+        app.diff_hscroll < 300,
+        "scrolling stops at the longest line"
+    );
+
+    let w = Event::Key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE));
+    handle_event(&mut app, w);
+    assert!(app.settings.wrap_lines);
+    assert_eq!(app.diff_hscroll, 0);
+    app.update_layout(root).expect("layout should update");
+    assert!(app.diff_content_height > unwrapped_height + 1);
+
+    handle_event(&mut app, key('L'));
+    assert_eq!(app.diff_hscroll, 0);
+    assert_eq!(app.status_kind(), StatusKind::Warn);
+}
+
+#[test]
+fn each_diff_side_keeps_its_own_first_line() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(repo.path().join("run"), "#!/bin/sh\necho hi\n").expect("write should succeed");
+    git(repo.path(), &["add", "run"]).expect("add should succeed");
+    git(repo.path(), &["commit", "-m", "script"]).expect("commit should succeed");
+    fs::write(
+        repo.path().join("run"),
+        "#!/usr/bin/env python3\nprint(\"hi\")\n",
+    )
+    .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    assert!(
+        app.select_tree_path("run")
+            .expect("selecting should succeed")
+    );
+
+    let first_line =
+        |source: Option<&SourceFile>| source.and_then(SourceFile::first_line).map(str::to_owned);
+    assert_eq!(
+        first_line(app.diff_old_source()).as_deref(),
+        Some("#!/bin/sh")
+    );
+    assert_eq!(
+        first_line(app.diff_new_source()).as_deref(),
+        Some("#!/usr/bin/env python3")
+    );
+}
+
+#[test]
+fn auto_theme_mode_follows_the_host_background() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+
+    app.settings.theme_mode = ThemeMode::Light;
+    assert!(app.light_theme());
+
+    app.settings.theme_mode = ThemeMode::Auto;
+    assert!(!app.light_theme(), "unknown backgrounds stay dark");
+    app.set_host_background(Some((250, 250, 245)));
+    assert!(app.light_theme());
+    app.set_host_background(Some((30, 32, 40)));
+    assert!(!app.light_theme());
+    assert!(
+        app.settings_rows()
+            .contains(&("Theme Mode", String::from("Auto (Dark)")))
+    );
+
+    // Picking Auto from the settings modal keeps a known background and
+    // never asks the terminal mid-session.
+    app.settings.theme_mode = ThemeMode::Light;
+    app.toggle_settings_panel();
+    app.settings_selected = 12;
+    app.adjust_selected_setting(1)
+        .expect("adjust should succeed");
+    assert_eq!(app.settings.theme_mode, ThemeMode::Auto);
+    assert!(!app.light_theme());
+}
+
+#[test]
+fn stages_and_collapses_whole_directories() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::create_dir_all(repo.path().join("src/nested")).expect("dir creation should succeed");
+    fs::write(repo.path().join("src/nested/a.txt"), "a\n").expect("write should succeed");
+    fs::write(repo.path().join("src/nested/b.txt"), "b\n").expect("write should succeed");
+    git(repo.path(), &["add", "src"]).expect("git add should succeed");
+    git(repo.path(), &["commit", "-m", "nested", "--", "src"]).expect("git commit should succeed");
+    fs::write(repo.path().join("src/nested/a.txt"), "a2\n").expect("write should succeed");
+    fs::write(repo.path().join("src/nested/b.txt"), "b2\n").expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    let dir_idx = app
+        .tree_rows
+        .iter()
+        .position(|row| row.kind == TreeRowKind::Directory)
+        .expect("directory row should exist");
+    assert_eq!(app.tree_rows[dir_idx].path, "src/nested");
+    assert_eq!(app.tree_rows[dir_idx].file_count, 2);
+    app.tree_selected = Some(dir_idx);
+
+    app.stage_selected()
+        .expect("staging directory should succeed");
+    assert!(app.staged.iter().any(|path| path == "src/nested/a.txt"));
+    assert!(app.staged.iter().any(|path| path == "src/nested/b.txt"));
+
+    app.toggle_selected_directory()
+        .expect("collapsing directory should succeed");
+    let rows_collapsed = app.tree_rows.len();
+    assert!(app.tree_rows.iter().any(|row| row.collapsed));
+
+    app.toggle_stage_state()
+        .expect("toggling staged directory should unstage it");
+    assert!(!app.staged.iter().any(|path| path.starts_with("src/")));
+    assert_eq!(app.tree_rows.len(), rows_collapsed);
+
+    app.toggle_selected_directory()
+        .expect("expanding directory should succeed");
+    assert_eq!(app.tree_rows.len(), rows_collapsed + 2);
+}
+
+#[test]
+fn partly_staged_files_stay_in_their_status_section() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    git(repo.path(), &["add", "tracked.txt"]).expect("git add should succeed");
+    fs::write(repo.path().join("tracked.txt"), "line_a\nline_b\nline_c\n")
+        .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    app.settings.sidebar_grouping = SidebarGrouping::Status;
+    app.refresh().expect("refresh should succeed");
+    let header = |app: &App, label: &str| {
+        app.tree_rows
+            .iter()
+            .position(|row| row.kind == TreeRowKind::Group && row.label == label)
+            .expect("status header should exist")
+    };
+    let unstaged_copy = app
+        .tree_rows
+        .iter()
+        .rposition(|row| row.path == "tracked.txt")
+        .expect("unstaged copy should exist");
+    assert!(unstaged_copy > header(&app, "Unstaged"));
+
+    app.tree_selected = Some(unstaged_copy);
+    app.refresh().expect("refresh should succeed");
+    assert_eq!(app.tree_selected, Some(unstaged_copy));
+    assert!(
+        app.select_tree_path("tracked.txt")
+            .expect("select should succeed")
+    );
+    assert_eq!(app.tree_selected, Some(unstaged_copy));
+
+    app.toggle_all_directories()
+        .expect("collapsing headers should succeed");
+    assert_eq!(app.tree_selected, Some(header(&app, "Unstaged")));
+    app.toggle_all_directories()
+        .expect("expanding headers should succeed");
+
+    app.tree_selected = Some(header(&app, "Unstaged"));
+    app.unstage_selected()
+        .expect("unstage on the Unstaged header should succeed");
+    assert_eq!(app.status.kind, StatusKind::Warn);
+    assert_eq!(app.staged, vec!["staged.txt", "tracked.txt"]);
+
+    app.tree_selected = Some(header(&app, "Staged"));
+    app.stage_selected()
+        .expect("stage on the Staged header should succeed");
+    assert_eq!(app.status.kind, StatusKind::Warn);
+    assert!(app.unstaged.iter().any(|file| file.path == "tracked.txt"));
+
+    app.toggle_stage_state()
+        .expect("toggle on the Staged header should unstage it");
+    assert!(app.staged.is_empty());
+    assert!(app.unstaged.iter().any(|file| file.path == "tracked.txt"));
+}
+
+#[test]
+fn sidebar_rows_carry_per_side_line_counts() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(repo.path().join("staged.txt"), "staged\nmore\n").expect("write should succeed");
+
+    let app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    let entry = |path: &str| {
+        app.tree_files
+            .iter()
+            .find(|entry| entry.path == path)
+            .cloned()
+            .expect("entry should exist")
+    };
+
+    let tracked = entry("tracked.txt");
+    assert_eq!(
+        (tracked.unstaged_stat.added, tracked.unstaged_stat.removed),
+        (1, 0)
+    );
+    assert!(tracked.staged_stat.is_empty());
+
+    let staged = entry("staged.txt");
+    assert_eq!(staged.staged_stat.added, 1);
+    assert_eq!(staged.unstaged_stat.added, 1);
+
+    assert_eq!(entry("untracked.txt").unstaged_stat.added, 1);
+
+    let (staged_total, unstaged_total) = app.tree_line_totals();
+    assert_eq!(staged_total.added, 1);
+    assert_eq!(unstaged_total.added, 3);
+}
+
+#[test]
+fn large_untracked_files_are_left_uncounted() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(repo.path().join("build.log"), "line\n".repeat(300_000))
+        .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    let stat = |app: &App, path: &str| {
+        app.tree_files
+            .iter()
+            .find(|entry| entry.path == path)
+            .map(|entry| entry.unstaged_stat)
+            .expect("entry should exist")
+    };
+
+    let log = stat(&app, "build.log");
+    assert!(log.uncounted);
+    assert_eq!(log.added, 0);
+    assert!(!log.is_empty());
+
+    fs::write(repo.path().join("untracked.txt"), "one\ntwo\nthree\n")
+        .expect("write should succeed");
+    app.refresh().expect("refresh should succeed");
+    assert_eq!(stat(&app, "untracked.txt").added, 3);
+}
+
+#[test]
+fn terminal_tabs_open_rename_switch_and_close() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+
+    app.open_terminal().expect("terminal should open");
+    app.new_terminal_tab().expect("second tab should open");
+    assert_eq!(app.terminal_tabs().len(), 2);
+    assert_eq!(app.terminal_active_tab_index(), 1);
+    assert_eq!(app.terminal_tabs()[1].name, "shell-2");
+
+    app.start_terminal_tab_rename();
+    app.terminal_rename_input.clear();
+    for ch in "tests".chars() {
+        app.terminal_rename_append(ch);
+    }
+    app.submit_terminal_tab_rename();
+    assert_eq!(app.terminal_tabs()[1].name, "tests");
+
+    app.terminal_enter_copy_mode();
+    app.cycle_terminal_tab(1);
+    assert_eq!(app.terminal_active_tab_index(), 0);
+    assert!(!app.terminal_copy_mode());
+    app.select_terminal_tab(1);
+    assert!(app.terminal_copy_mode());
+
+    app.close_terminal();
+    assert!(!app.terminal_open);
+    assert_eq!(app.terminal_tabs().len(), 2);
+
+    app.open_terminal().expect("terminal should reopen");
+    app.close_terminal_tab();
+    assert_eq!(app.terminal_tabs().len(), 1);
+    assert_eq!(app.terminal_tabs()[0].name, "shell");
+}
+
+#[test]
+fn renaming_a_restored_tab_renames_its_tmux_session() {
+    if Command::new("tmux").arg("-V").output().is_err() {
+        return;
+    }
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(
+        repo.path().join(".dif.toml"),
+        "[shell]\nprogram = \"/bin/sh\"\npersist = true\n",
+    )
+    .expect("write should succeed");
+    let prefix = terminal::persistent_session_prefix(repo.path());
+    let tmux = |args: &[&str]| {
+        Command::new("tmux")
+            .args(["-L", "dif"])
+            .args(args)
+            .output()
+            .expect("tmux should run")
+    };
+    let old = format!("{prefix}work");
+    assert!(
+        tmux(&["new-session", "-d", "-s", &old, "/bin/sh"])
+            .status
+            .success()
+    );
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    assert_eq!(app.terminal_tabs().len(), 1);
+    assert_eq!(app.terminal_tabs()[0].name, "work");
+
+    app.start_terminal_tab_rename();
+    app.terminal_rename_input.clear();
+    for ch in "review".chars() {
+        app.terminal_rename_append(ch);
+    }
+    app.submit_terminal_tab_rename();
+    let renamed = format!("{prefix}review");
+    let sessions = terminal::list_persistent_sessions(&prefix);
+    tmux(&["kill-session", "-t", &renamed]);
+    tmux(&["kill-session", "-t", &old]);
+
+    assert_eq!(app.status.kind, StatusKind::Info);
+    assert_eq!(app.terminal_tabs()[0].name, "review");
+    assert_eq!(sessions, vec![renamed]);
+}
+
+#[test]
+fn docked_terminal_joins_focus_ring_and_stays_visible() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    app.settings.terminal_dock = TerminalDock::Bottom;
+    let root = Rect::new(0, 0, 160, 48);
+
+    app.open_terminal().expect("terminal should open");
+    app.update_layout(root).expect("layout should update");
+    assert!(!app.terminal_overlay_open());
+    assert!(app.terminal_has_focus());
+    assert_eq!(app.pane_focus, PaneFocus::Terminal);
+
+    app.cycle_focus_ring(1).expect("focus should cycle");
+    assert_eq!(app.pane_focus, PaneFocus::Sidebar);
+    assert!(!app.terminal_has_focus());
+    app.cycle_focus_ring(-1).expect("focus should cycle back");
+    assert_eq!(app.pane_focus, PaneFocus::Terminal);
+
+    app.leave_terminal();
+    assert!(app.terminal_open);
+    assert_eq!(app.pane_focus, PaneFocus::Diff);
+
+    app.toggle_settings_panel();
+    assert!(app.terminal_open);
+    app.close_settings_panel();
+
+    app.toggle_terminal_pane()
+        .expect("terminal pane should hide");
+    assert!(!app.terminal_open);
+    app.update_layout(root).expect("layout should update");
+    app.cycle_focus_ring(1).expect("focus should cycle");
+    app.cycle_focus_ring(1).expect("focus should cycle");
+    assert_ne!(app.pane_focus, PaneFocus::Terminal);
+}
+
+#[test]
+fn repo_config_overrides_terminal_shell_once_trusted() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(
+        repo.path().join(".dif.toml"),
+        "[shell]\nprogram = \"/bin/sh\"\ninitial_command = \"echo ready\"\n\n[shell.env]\nAGENT_MODE = \"review\"\n",
+    )
+    .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    assert_eq!(app.status_kind(), StatusKind::Warn);
+    assert!(app.status_text().contains("untrusted .dif.toml"));
+    assert_eq!(app.effective_shell(), app.settings.shell);
+
+    app.toggle_repo_trust();
+    assert!(app.has_pending_repo_trust());
+    assert!(app.status_text().contains("`echo ready`"));
+    app.cancel_repo_trust();
+    assert!(!app.repo_config_trusted());
+    assert_eq!(app.effective_shell(), app.settings.shell);
+
+    app.toggle_repo_trust();
+    app.confirm_repo_trust();
+    assert!(app.repo_config_trusted());
+    let shell = app.effective_shell();
+
+    assert_eq!(shell.program.as_deref(), Some("/bin/sh"));
+    assert_eq!(shell.initial_command.as_deref(), Some("echo ready"));
+    assert_eq!(
+        shell.env.get("AGENT_MODE").map(String::as_str),
+        Some("review")
+    );
+}
+
+#[test]
+fn tasks_run_in_their_own_tabs_and_report_failures() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    fs::write(
+        repo.path().join(".dif.toml"),
+        "[shell]\nprogram = \"/bin/sh\"\n\n[tasks]\nbad = \"exit 3\"\n",
+    )
+    .expect("write should succeed");
+
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    app.settings
+        .tasks
+        .insert(String::from("ok"), String::from("true"));
+    app.settings
+        .tasks
+        .insert(String::from("bad"), String::from("true"));
+    let listed = |app: &App| {
+        app.tasks()
+            .into_iter()
+            .map(|task| (task.name, task.command, task.source))
+            .collect::<Vec<_>>()
+    };
+
+    // An untrusted repo cannot replace the global `bad` task.
+    assert_eq!(
+        listed(&app),
+        vec![
+            (
+                String::from("bad"),
+                String::from("true"),
+                TaskSource::Global
+            ),
+            (String::from("ok"), String::from("true"), TaskSource::Global),
+        ]
+    );
+
+    app.toggle_repo_trust();
+    assert!(app.status_text().contains("task bad `exit 3`"));
+    app.cancel_repo_trust();
+    // Trusted in memory only, so the ticks below never save `config.toml`.
+    let config = settings::load_repo_config(repo.path()).expect("repo config should load");
+    app.settings
+        .set_repo_trusted(&settings::repo_trust_key(repo.path()), &config.sha256, true);
+    assert_eq!(
+        listed(&app),
+        vec![
+            (
+                String::from("bad"),
+                String::from("exit 3"),
+                TaskSource::Repo
+            ),
+            (String::from("ok"), String::from("true"), TaskSource::Global),
+        ]
+    );
+
+    app.open_task_picker();
+    assert!(app.task_picker_open);
+    app.run_selected_task().expect("task should start");
+    assert!(!app.task_picker_open);
+    assert!(app.terminal_open);
+    assert_eq!(app.terminal_tabs()[0].task_name(), Some("bad"));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while app.task_run_state("bad") == Some(TaskRunState::Running) && Instant::now() < deadline {
+        app.tick();
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    assert_eq!(app.task_run_state("bad"), Some(TaskRunState::Failed));
+    assert_eq!(app.status_kind(), StatusKind::Error);
+    assert!(app.status_text().contains("Task `bad` failed"));
+    assert_eq!(app.task_run_state("ok"), None);
+}
+
+#[test]
+fn hidden_tabs_flag_attention_and_failures_outrank_other_statuses() {
+    let repo = setup_repo().expect("repo setup should succeed");
+    let mut app = App::new(repo.path().to_path_buf()).expect("app should initialize");
+    app.settings.shell.program = Some(String::from("/bin/sh"));
+    app.settings
+        .tasks
+        .insert(String::from("bad"), String::from("printf '\\a'; exit 1"));
+    app.settings
+        .tasks
+        .insert(String::from("ok"), String::from("true"));
+
+    // Tasks are listed by name, so `bad` runs first and `ok` second.
+    for idx in 0..2 {
+        app.open_task_picker();
+        app.task_picker_selected = idx;
+        app.run_selected_task().expect("task should start");
+    }
+    app.close_terminal();
+
+    // Both tabs ring, pass or fail out of sight within a single pump.
+    thread::sleep(Duration::from_millis(500));
+    assert!(app.tick());
+
+    let attention = |app: &App| {
+        app.terminal_tabs()
+            .iter()
+            .map(|tab| tab.attention())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        attention(&app),
+        [Some(TabAttention::Failed), Some(TabAttention::Finished)]
+    );
+    assert_eq!(app.status_kind(), StatusKind::Error);
+    assert!(app.status_text().contains("Task `bad` failed"));
+
+    app.select_terminal_tab(0);
+    app.open_terminal().expect("terminal should open");
+    app.tick();
+    assert_eq!(attention(&app), [None, Some(TabAttention::Finished)]);
+}
+
 fn setup_repo() -> anyhow::Result<TempDir> {
     let temp = TempDir::new()?;
     let repo = temp.path();